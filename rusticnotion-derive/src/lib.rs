@@ -0,0 +1,187 @@
+//! `#[derive(NotionPage)]` for rusticnotion, see `rusticnotion::models::mapping`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr};
+
+/// Implements `NotionPage` for a struct with named fields.
+///
+/// Every field needs `#[notion(kind = "...")]` with the Notion type of the property,
+/// the name of a `PropertyKind` variant in snake case,
+/// `name = "..."` sets the property name if it differs from the field name.
+/// Fields with `#[notion(skip)]` aren't mapped and read as their default value.
+#[proc_macro_derive(NotionPage, attributes(notion))]
+pub fn derive_notion_page(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct MappedField {
+    ident: Ident,
+    name: String,
+    kind: Ident,
+}
+
+enum Field {
+    Mapped(MappedField),
+    Skipped(Ident),
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "NotionPage can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "NotionPage can only be derived for structs",
+            ))
+        }
+    };
+    let fields = fields
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let mapping = quote!(::rusticnotion::models::mapping);
+    let mapped: Vec<&MappedField> = fields
+        .iter()
+        .filter_map(|field| match field {
+            Field::Mapped(field) => Some(field),
+            Field::Skipped(_) => None,
+        })
+        .collect();
+
+    let schema = mapped.iter().map(|field| {
+        let MappedField { name, kind, .. } = field;
+        quote! {
+            #mapping::PropertySchema {
+                name: #name,
+                kind: #mapping::PropertyKind::#kind,
+            }
+        }
+    });
+    let reads = fields.iter().map(|field| match field {
+        Field::Mapped(MappedField { ident, name, kind }) => quote! {
+            #ident: #mapping::read_field(properties, #name, #mapping::PropertyKind::#kind)?
+        },
+        Field::Skipped(ident) => quote! {
+            #ident: ::std::default::Default::default()
+        },
+    });
+    let writes = mapped.iter().map(|field| {
+        let MappedField { ident, name, kind } = field;
+        quote! {
+            #mapping::write_field(
+                &mut properties,
+                database,
+                #name,
+                #mapping::PropertyKind::#kind,
+                &self.#ident,
+            )?;
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #mapping::NotionPage for #ident #type_generics #where_clause {
+            fn schema() -> ::std::vec::Vec<#mapping::PropertySchema> {
+                ::std::vec![#(#schema),*]
+            }
+
+            fn from_properties(
+                properties: &::rusticnotion::models::Properties,
+            ) -> ::std::result::Result<Self, ::rusticnotion::models::properties::PropertyError> {
+                ::std::result::Result::Ok(Self {
+                    #(#reads),*
+                })
+            }
+
+            fn to_properties(
+                &self,
+                database: &::rusticnotion::models::Database,
+            ) -> ::std::result::Result<
+                ::rusticnotion::models::Properties,
+                ::rusticnotion::models::properties::PropertyError,
+            > {
+                let mut properties = ::std::collections::HashMap::new();
+                #(#writes)*
+                ::std::result::Result::Ok(::rusticnotion::models::Properties { properties })
+            }
+        }
+    })
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<Field> {
+    let ident = field.ident.clone().expect("Named fields have an ident");
+    let mut name = None;
+    let mut kind = None;
+    let mut skip = false;
+
+    for attribute in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("notion"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("kind") {
+                let value: LitStr = meta.value()?.parse()?;
+                let variant = kind_variant(&value.value()).ok_or_else(|| {
+                    syn::Error::new_spanned(
+                        &value,
+                        "expected the Notion name of a property type, e.g. \"rich_text\"",
+                    )
+                })?;
+                kind = Some(Ident::new(&variant, value.span()));
+            } else if meta.path.is_ident("skip") {
+                skip = true;
+            } else {
+                return Err(meta.error("expected `name`, `kind` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    if skip {
+        return Ok(Field::Skipped(ident));
+    }
+    let kind = kind.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &ident,
+            "missing `#[notion(kind = \"...\")]` with the type of the property",
+        )
+    })?;
+
+    Ok(Field::Mapped(MappedField {
+        name: name.unwrap_or_else(|| ident.to_string()),
+        ident,
+        kind,
+    }))
+}
+
+/// The `PropertyKind` variant of a Notion type name, e.g. `RichText` for `rich_text`.
+/// Names that aren't a variant fail to compile where the variant is used.
+fn kind_variant(kind: &str) -> Option<String> {
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+        return None;
+    }
+    Some(
+        kind.split('_')
+            .filter(|word| !word.is_empty())
+            .map(|word| word[..1].to_uppercase() + &word[1..])
+            .collect(),
+    )
+}
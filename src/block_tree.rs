@@ -0,0 +1,72 @@
+use crate::ids::{AsIdentifier, BlockId};
+use crate::models::block::Block;
+use crate::{Error, NotionApi};
+use futures::future::try_join_all;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::sync::Semaphore;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+impl NotionApi {
+    /// Fetches the children of a block or page together with all of their nested blocks
+    /// and fills the `children` fields of the returned blocks.
+    ///
+    /// `max_depth` limits how many levels are fetched, `1` only fetches the direct children.
+    /// Siblings are fetched in parallel, with at most `concurrency` requests in flight.
+    /// The content of child pages and child databases is not fetched.
+    pub async fn fetch_block_tree<T: AsIdentifier<BlockId>>(
+        &self,
+        block_id: T,
+        max_depth: usize,
+        concurrency: usize,
+    ) -> Result<Vec<Block>, Error> {
+        let permits = Semaphore::new(concurrency.max(1));
+        self.fetch_subtree(block_id.as_id().clone(), max_depth, &permits)
+            .await
+    }
+
+    fn fetch_subtree<'a>(
+        &'a self,
+        block_id: BlockId,
+        depth: usize,
+        permits: &'a Semaphore,
+    ) -> BoxFuture<'a, Result<Vec<Block>, Error>> {
+        Box::pin(async move {
+            if depth == 0 {
+                return Ok(vec![]);
+            }
+
+            let mut children = {
+                let _permit = permits
+                    .acquire()
+                    .await
+                    .expect("The semaphore is never closed");
+                self.get_all_block_children(&block_id).await?
+            };
+            if depth == 1 {
+                return Ok(children);
+            }
+
+            let nested: Vec<usize> = children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| {
+                    child.holds_children()
+                        && child.common().is_some_and(|common| common.has_children)
+                })
+                .map(|(index, _)| index)
+                .collect();
+            let subtrees = try_join_all(nested.iter().map(|&index| {
+                self.fetch_subtree(children[index].as_id().clone(), depth - 1, permits)
+            }))
+            .await?;
+
+            for (index, subtree) in nested.into_iter().zip(subtrees) {
+                children[index].set_children(subtree);
+            }
+
+            Ok(children)
+        })
+    }
+}
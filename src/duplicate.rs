@@ -0,0 +1,607 @@
+//! Deep copies of pages, see [NotionApi::duplicate_page()].
+
+use crate::ids::{AsIdentifier, BlockId, PageId};
+use crate::models::block::{Block, CreateBlock, FileObject, FileOrEmojiObject};
+use crate::models::limits::MAX_ARRAY_LENGTH as MAX_BLOCKS_PER_REQUEST;
+use crate::models::properties::{
+    FileReference, PropertyKind, PropertyValue, SelectReference, WriteProperties,
+    WritePropertyValue,
+};
+use crate::models::validation::SchemaError;
+use crate::models::{Database, Page, PageCreateRequest, Parent, Properties};
+use crate::{Error, NotionApi};
+use std::future::Future;
+use std::pin::Pin;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Why a block was left out of a copy.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SkipReason {
+    /// Notion does not expose the content of the block through the API.
+    Unsupported,
+    /// The API can read the block but not create it.
+    NotCreatable,
+    /// The file is hosted by Notion, its url expires and can't be referenced by a new block.
+    NotionHostedFile,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SkippedBlock {
+    /// `None` for blocks of a type unknown to this library.
+    pub id: Option<BlockId>,
+    /// The Notion type name of the block, e.g. `child_database`.
+    pub block_type: &'static str,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SkippedProperty {
+    /// The source page the property belongs to.
+    pub page: PageId,
+    pub name: String,
+}
+
+/// The result of [NotionApi::duplicate_page()].
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DuplicateReport {
+    /// The copy of the source page.
+    pub page: Page,
+    /// Copies of all pages that were nested in the source page, in creation order.
+    pub child_pages: Vec<Page>,
+    /// Child pages that were not at the top level of their page.
+    /// They are appended to the end of the nearest copied page instead.
+    pub moved_child_pages: Vec<PageId>,
+    pub skipped_blocks: Vec<SkippedBlock>,
+    /// Properties that are read-only or not valid for the new parent.
+    pub skipped_properties: Vec<SkippedProperty>,
+}
+
+#[derive(Default)]
+struct Progress {
+    pages: Vec<Page>,
+    moved_child_pages: Vec<PageId>,
+    skipped_blocks: Vec<SkippedBlock>,
+    skipped_properties: Vec<SkippedProperty>,
+}
+
+impl Progress {
+    fn skip(
+        &mut self,
+        id: Option<&BlockId>,
+        block_type: &'static str,
+        reason: SkipReason,
+    ) {
+        tracing::debug!(?id, block_type, ?reason, "Skipping block");
+        self.skipped_blocks.push(SkippedBlock {
+            id: id.cloned(),
+            block_type,
+            reason,
+        })
+    }
+}
+
+/// A block ready to be created, together with what has to be copied after creating it.
+struct PendingBlock {
+    source_id: BlockId,
+    block: Block,
+    /// The children have to be copied with additional requests once the block exists.
+    copy_children: bool,
+    /// Children sent inline with `block`, in the order Notion creates them.
+    inline_children: Vec<PendingBlock>,
+    /// Children that didn't fit into the request creating `block`,
+    /// they are appended once the block exists.
+    deferred_children: Vec<PendingBlock>,
+}
+
+impl PendingBlock {
+    fn new(
+        source_id: BlockId,
+        block: Block,
+        copy_children: bool,
+    ) -> Self {
+        PendingBlock {
+            source_id,
+            block,
+            copy_children,
+            inline_children: vec![],
+            deferred_children: vec![],
+        }
+    }
+
+    fn needs_follow_up(&self) -> bool {
+        self.inline_children.iter().any(|child| {
+            child.copy_children || !child.deferred_children.is_empty() || child.needs_follow_up()
+        })
+    }
+}
+
+impl NotionApi {
+    /// Copies a page with its properties and all nested blocks to `new_parent`.
+    ///
+    /// Child pages are copied recursively as new child pages.
+    /// Blocks the API can't create, like child databases or [Block::Unsupported] blocks,
+    /// are left out and listed in the returned [DuplicateReport].
+    /// When `new_parent` is not a database only the title property is copied, otherwise
+    /// properties are matched to the schema of the database by name.
+    pub async fn duplicate_page<T: AsIdentifier<PageId>>(
+        &self,
+        source: T,
+        new_parent: Parent,
+    ) -> Result<DuplicateReport, Error> {
+        let mut progress = Progress::default();
+        let page = self
+            .duplicate_page_into(source.as_id().clone(), new_parent, &mut progress)
+            .await?;
+
+        Ok(DuplicateReport {
+            page,
+            child_pages: progress.pages.split_off(1),
+            moved_child_pages: progress.moved_child_pages,
+            skipped_blocks: progress.skipped_blocks,
+            skipped_properties: progress.skipped_properties,
+        })
+    }
+
+    fn duplicate_page_into<'a>(
+        &'a self,
+        source: PageId,
+        parent: Parent,
+        progress: &'a mut Progress,
+    ) -> BoxFuture<'a, Result<Page, Error>> {
+        Box::pin(async move {
+            let source_page = self.get_page(&source).await?;
+            let (properties, mut skipped) = writable_properties(source_page.properties, &parent);
+            let mut properties = WriteProperties::from(properties);
+            if let Parent::Database { database_id } = &parent {
+                let database = self.get_database(database_id).await?;
+                skipped.extend(match_schema(&mut properties, &database));
+            }
+            progress
+                .skipped_properties
+                .extend(skipped.into_iter().map(|name| SkippedProperty {
+                    page: source.clone(),
+                    name,
+                }));
+
+            let page = self
+                .create_page(PageCreateRequest {
+                    parent,
+                    properties,
+                    children: None,
+                    icon: source_page
+                        .icon
+                        .filter(|icon| !matches!(icon, FileOrEmojiObject::File { .. })),
+                    cover: source_page
+                        .cover
+                        .filter(|cover| !matches!(cover, FileObject::File { .. })),
+                })
+                .await?;
+            progress.pages.push(page.clone());
+
+            self.copy_children(
+                BlockId::from(source),
+                BlockId::from(page.id.clone()),
+                page.id.clone(),
+                progress,
+            )
+            .await?;
+
+            Ok(page)
+        })
+    }
+
+    /// Copies all children of `source` to the end of `target`.
+    /// Child pages are created in `page`, the nearest copied page.
+    fn copy_children<'a>(
+        &'a self,
+        source: BlockId,
+        target: BlockId,
+        page: PageId,
+        progress: &'a mut Progress,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let target_is_page = target == BlockId::from(page.clone());
+            let mut pending = vec![];
+
+            for block in self.get_all_block_children(&source).await? {
+                if let Block::ChildPage { common, .. } = block {
+                    // Child pages are always appended to the end of their parent page,
+                    // so everything before them has to exist first to keep the order.
+                    if target_is_page {
+                        self.create_pending(&target, &page, std::mem::take(&mut pending), progress)
+                            .await?;
+                    } else {
+                        progress
+                            .moved_child_pages
+                            .push(PageId::from(common.id.clone()));
+                    }
+                    self.duplicate_page_into(
+                        PageId::from(common.id),
+                        Parent::Page {
+                            page_id: page.clone(),
+                        },
+                        progress,
+                    )
+                    .await?;
+                    continue;
+                }
+
+                if let Some(block) = self.prepare_block(block, &page, progress).await? {
+                    pending.push(block);
+                }
+            }
+
+            self.create_pending(&target, &page, pending, progress).await
+        })
+    }
+
+    /// Converts a source block into a [PendingBlock], fetching the children
+    /// Notion requires to be sent inline with the block.
+    fn prepare_block<'a>(
+        &'a self,
+        block: Block,
+        page: &'a PageId,
+        progress: &'a mut Progress,
+    ) -> BoxFuture<'a, Result<Option<PendingBlock>, Error>> {
+        Box::pin(async move {
+            let (common, skipped) = match &block {
+                Block::Unknown => (None, Some(("unknown", SkipReason::Unsupported))),
+                Block::Unsupported { common } => {
+                    (Some(common), Some(("unsupported", SkipReason::Unsupported)))
+                }
+                Block::ChildDatabase { common, .. } => (
+                    Some(common),
+                    Some(("child_database", SkipReason::NotCreatable)),
+                ),
+                Block::LinkPreview { common, .. } => (
+                    Some(common),
+                    Some(("link_preview", SkipReason::NotCreatable)),
+                ),
+                Block::Template { common, .. } => {
+                    (Some(common), Some(("template", SkipReason::NotCreatable)))
+                }
+                Block::Image {
+                    common,
+                    image: FileObject::File { .. },
+                } => (Some(common), Some(("image", SkipReason::NotionHostedFile))),
+                Block::Video {
+                    common,
+                    video: FileObject::File { .. },
+                } => (Some(common), Some(("video", SkipReason::NotionHostedFile))),
+                Block::File {
+                    common,
+                    file: FileObject::File { .. },
+                    ..
+                } => (Some(common), Some(("file", SkipReason::NotionHostedFile))),
+                Block::Pdf {
+                    common,
+                    pdf: FileObject::File { .. },
+                } => (Some(common), Some(("pdf", SkipReason::NotionHostedFile))),
+                Block::Paragraph { common, .. }
+                | Block::Heading1 { common, .. }
+                | Block::Heading2 { common, .. }
+                | Block::Heading3 { common, .. }
+                | Block::Callout { common, .. }
+                | Block::Quote { common, .. }
+                | Block::BulletedListItem { common, .. }
+                | Block::NumberedListItem { common, .. }
+                | Block::ToDo { common, .. }
+                | Block::Toggle { common, .. }
+                | Block::Code { common, .. }
+                | Block::ChildPage { common, .. }
+                | Block::Embed { common, .. }
+                | Block::Image { common, .. }
+                | Block::Video { common, .. }
+                | Block::File { common, .. }
+                | Block::Pdf { common, .. }
+                | Block::Bookmark { common, .. }
+                | Block::Equation { common, .. }
+                | Block::Divider { common }
+                | Block::TableOfContents { common, .. }
+                | Block::Breadcrumb { common }
+                | Block::ColumnList { common, .. }
+                | Block::Column { common, .. }
+                | Block::LinkToPage { common, .. }
+                | Block::Table { common, .. }
+                | Block::SyncedBlock { common, .. }
+                | Block::TableRow { common, .. } => (Some(common), None),
+            };
+            if let Some((block_type, reason)) = skipped {
+                progress.skip(common.map(|common| &common.id), block_type, reason);
+                return Ok(None);
+            }
+
+            let source_id = block.as_id().clone();
+            let has_children = common.is_some_and(|common| common.has_children);
+
+            let prepared = match block {
+                Block::Table {
+                    common, mut table, ..
+                } => {
+                    // Rows can't have children, so nothing is left to copy afterwards.
+                    // Rows that don't fit into the request creating the table are appended.
+                    table.children = self.get_all_block_children(&common.id).await?;
+                    let deferred = table
+                        .children
+                        .split_off(table.children.len().min(MAX_BLOCKS_PER_REQUEST))
+                        .into_iter()
+                        .map(|row| PendingBlock::new(row.as_id().clone(), row, false))
+                        .collect();
+                    PendingBlock {
+                        deferred_children: deferred,
+                        ..PendingBlock::new(source_id, Block::Table { common, table }, false)
+                    }
+                }
+                Block::ColumnList {
+                    common,
+                    mut column_list,
+                } => {
+                    let mut columns = vec![];
+                    for column in self.get_all_block_children(&common.id).await? {
+                        if let Some(column) = self.prepare_block(column, page, progress).await? {
+                            columns.push(column);
+                        }
+                    }
+                    column_list.children =
+                        columns.iter().map(|column| column.block.clone()).collect();
+                    PendingBlock {
+                        inline_children: columns,
+                        ..PendingBlock::new(
+                            source_id,
+                            Block::ColumnList {
+                                common,
+                                column_list,
+                            },
+                            false,
+                        )
+                    }
+                }
+                Block::Column { common, mut column } => {
+                    let mut children = vec![];
+                    for child in self.get_all_block_children(&common.id).await? {
+                        if let Block::ChildPage { common, .. } = child {
+                            progress
+                                .moved_child_pages
+                                .push(PageId::from(common.id.clone()));
+                            self.duplicate_page_into(
+                                PageId::from(common.id),
+                                Parent::Page {
+                                    page_id: page.clone(),
+                                },
+                                progress,
+                            )
+                            .await?;
+                        } else if let Some(child) =
+                            self.prepare_block(child, page, progress).await?
+                        {
+                            children.push(child);
+                        }
+                    }
+                    let deferred = children.split_off(children.len().min(MAX_BLOCKS_PER_REQUEST));
+                    column.children = children.iter().map(|child| child.block.clone()).collect();
+                    PendingBlock {
+                        inline_children: children,
+                        deferred_children: deferred,
+                        ..PendingBlock::new(source_id, Block::Column { common, column }, false)
+                    }
+                }
+                Block::SyncedBlock {
+                    common,
+                    synced_block,
+                } => {
+                    // A reference to another synced block shows the children of the original,
+                    // they can't be appended to the copy.
+                    let is_original = synced_block.synced_from.is_none();
+                    PendingBlock::new(
+                        source_id,
+                        Block::SyncedBlock {
+                            common,
+                            synced_block,
+                        },
+                        is_original && has_children,
+                    )
+                }
+                block => PendingBlock::new(source_id, block, has_children),
+            };
+
+            Ok(Some(prepared))
+        })
+    }
+
+    /// Creates `pending` at the end of `target` and copies the children of every created block.
+    fn create_pending<'a>(
+        &'a self,
+        target: &'a BlockId,
+        page: &'a PageId,
+        pending: Vec<PendingBlock>,
+        progress: &'a mut Progress,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let mut pending = pending.into_iter().peekable();
+            while pending.peek().is_some() {
+                let batch: Vec<PendingBlock> =
+                    pending.by_ref().take(MAX_BLOCKS_PER_REQUEST).collect();
+                let created = self
+                    .append_block_children(
+                        target,
+                        batch
+                            .iter()
+                            .map(|block| CreateBlock::from(block.block.clone()))
+                            .collect(),
+                    )
+                    .await?;
+
+                for (block, created) in batch.into_iter().zip(created.results) {
+                    self.follow_up(block, created.as_id().clone(), page, progress)
+                        .await?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Copies the children of `block`, now created as `created`, that were not sent inline
+    /// or didn't fit into the request creating it.
+    fn follow_up<'a>(
+        &'a self,
+        block: PendingBlock,
+        created: BlockId,
+        page: &'a PageId,
+        progress: &'a mut Progress,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            if block.copy_children {
+                return self
+                    .copy_children(block.source_id, created, page.clone(), progress)
+                    .await;
+            }
+
+            if block.needs_follow_up() {
+                let created_children = self.get_all_block_children(&created).await?;
+                for (child, created_child) in
+                    block.inline_children.into_iter().zip(created_children)
+                {
+                    self.follow_up(child, created_child.as_id().clone(), page, progress)
+                        .await?;
+                }
+            }
+
+            self.create_pending(&created, page, block.deferred_children, progress)
+                .await
+        })
+    }
+}
+
+/// Removes all property values that can't be written to a page with `parent`,
+/// returns the remaining properties and the names of the removed ones.
+fn writable_properties(
+    properties: Properties,
+    parent: &Parent,
+) -> (Properties, Vec<String>) {
+    let in_database = matches!(parent, Parent::Database { .. });
+    let mut skipped = vec![];
+
+    let properties = properties
+        .properties
+        .into_iter()
+        .filter(|(name, value)| {
+            let writable = match value {
+                PropertyValue::Title { .. } => true,
+                PropertyValue::Text { .. }
+                | PropertyValue::Number { .. }
+                | PropertyValue::Select { .. }
+                | PropertyValue::Status { .. }
+                | PropertyValue::MultiSelect { .. }
+                | PropertyValue::Date { .. }
+                | PropertyValue::Relation { .. }
+                | PropertyValue::People { .. }
+                | PropertyValue::Checkbox { .. }
+                | PropertyValue::Url { .. }
+                | PropertyValue::Email { .. }
+                | PropertyValue::PhoneNumber { .. } => in_database,
+                PropertyValue::Files { files, .. } => {
+                    in_database
+                        && files
+                            .iter()
+                            .flatten()
+                            .all(|file| !matches!(file, FileReference::File { .. }))
+                }
+                PropertyValue::Formula { .. }
+                | PropertyValue::Rollup { .. }
+                | PropertyValue::CreatedTime { .. }
+                | PropertyValue::CreatedBy { .. }
+                | PropertyValue::LastEditedTime { .. }
+                | PropertyValue::LastEditedBy { .. }
+                | PropertyValue::Button { .. } => false,
+            };
+            if !writable {
+                skipped.push(name.clone());
+            }
+            writable
+        })
+        .map(|(name, value)| match value {
+            // Pages outside of databases only have a title, which has to be named `title`.
+            PropertyValue::Title { .. } if !in_database => ("title".to_string(), value),
+            value => (name, value),
+        })
+        .collect();
+
+    (Properties { properties }, skipped)
+}
+
+/// Fits the properties of a source page to the schema of `database`: the title is moved to the
+/// title property of `database`, select options are referenced by name, and values
+/// [PageCreateRequest::validate()] rejects are removed. Returns the names of the removed
+/// properties.
+fn match_schema(
+    properties: &mut WriteProperties,
+    database: &Database,
+) -> Vec<String> {
+    let title = database
+        .properties
+        .iter()
+        .find(|(_, config)| config.kind() == PropertyKind::Title);
+    let source_title = properties
+        .properties
+        .iter()
+        .find(|(_, value)| value.kind() == PropertyKind::Title)
+        .map(|(name, _)| name.clone());
+    if let (Some((title, _)), Some(source_title)) = (title, source_title) {
+        let value = properties.properties.remove(&source_title);
+        properties
+            .properties
+            .extend(value.map(|value| (title.clone(), value)));
+    }
+
+    for value in properties.properties.values_mut() {
+        let options: Vec<&mut SelectReference> = match value {
+            WritePropertyValue::Select { select } => select.iter_mut().collect(),
+            WritePropertyValue::Status { status } => status.iter_mut().collect(),
+            WritePropertyValue::MultiSelect { multi_select } => multi_select.iter_mut().collect(),
+            _ => vec![],
+        };
+        for option in options {
+            // Option ids belong to the source database.
+            if option.name.is_some() {
+                option.id = None;
+            }
+        }
+    }
+
+    let request = PageCreateRequest {
+        parent: Parent::Database {
+            database_id: database.id.clone(),
+        },
+        properties: properties.clone(),
+        children: None,
+        icon: None,
+        cover: None,
+    };
+    let mut skipped: Vec<String> = request
+        .validate(database)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|error| match error {
+            SchemaError::UnknownProperty { property }
+            | SchemaError::WrongType { property, .. }
+            | SchemaError::ReadOnly { property, .. } => Some(property),
+            // Notion creates select options that don't exist yet, but no status options.
+            SchemaError::UnknownOption { property, .. } => {
+                let kind = database
+                    .properties
+                    .get(&property)
+                    .map(|config| config.kind());
+                (kind == Some(PropertyKind::Status)).then_some(property)
+            }
+            SchemaError::MissingTitle { .. } => None,
+        })
+        .collect();
+    skipped.dedup();
+    for name in &skipped {
+        properties.properties.remove(name);
+    }
+    skipped
+}
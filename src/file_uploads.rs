@@ -0,0 +1,146 @@
+use crate::models::file_uploads::{
+    CreateFileUploadRequest, FileUpload, FileUploadMode, FileUploadPart, MULTI_PART_CHUNK_SIZE,
+    SINGLE_PART_MAX_SIZE,
+};
+use crate::{Error, NotionApi};
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+impl NotionApi {
+    /// Uploads the file at `path` and returns the uploaded [FileUpload].
+    ///
+    /// Files larger than [SINGLE_PART_MAX_SIZE] are sent as a multi part upload.
+    /// If no `content_type` is given Notion infers it from the file name.
+    pub async fn upload_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        content_type: Option<String>,
+    ) -> Result<FileUpload, Error> {
+        let path = path.as_ref();
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| Error::FileReadError {
+                source: std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{} has no file name", path.display()),
+                ),
+            })?;
+        let file = tokio::fs::File::open(path)
+            .await
+            .map_err(|source| Error::FileReadError { source })?;
+        let content_length = file
+            .metadata()
+            .await
+            .map_err(|source| Error::FileReadError { source })?
+            .len();
+
+        self.upload_from_reader(file, filename, content_type, content_length)
+            .await
+    }
+
+    /// Uploads `content_length` bytes read from `reader` and returns the uploaded [FileUpload].
+    ///
+    /// Content larger than [SINGLE_PART_MAX_SIZE] is sent as a multi part upload,
+    /// reading at most [MULTI_PART_CHUNK_SIZE] bytes into memory at a time.
+    /// When a later part can't be read or sent, the upload stays pending and the error is
+    /// [Error::FileUploadIncomplete] with its id.
+    pub async fn upload_from_reader<R: AsyncRead + Unpin>(
+        &self,
+        mut reader: R,
+        filename: String,
+        content_type: Option<String>,
+        content_length: u64,
+    ) -> Result<FileUpload, Error> {
+        // Data is read before the upload is created, a failing reader
+        // shouldn't leave a pending upload behind.
+        if content_length <= SINGLE_PART_MAX_SIZE {
+            let data = read_part(&mut reader, content_length).await?;
+            let upload = self
+                .create_file_upload(CreateFileUploadRequest {
+                    mode: FileUploadMode::SinglePart,
+                    filename: Some(filename.clone()),
+                    content_type: content_type.clone(),
+                    number_of_parts: None,
+                })
+                .await?;
+
+            return self
+                .send_file_upload(
+                    &upload.id,
+                    FileUploadPart {
+                        filename,
+                        content_type,
+                        data,
+                        part_number: None,
+                    },
+                )
+                .await;
+        }
+
+        let number_of_parts = content_length.div_ceil(MULTI_PART_CHUNK_SIZE) as u32;
+        let mut data = read_part(&mut reader, MULTI_PART_CHUNK_SIZE).await?;
+        let mut remaining = content_length - MULTI_PART_CHUNK_SIZE;
+        let upload = self
+            .create_file_upload(CreateFileUploadRequest {
+                mode: FileUploadMode::MultiPart,
+                filename: Some(filename.clone()),
+                content_type: content_type.clone(),
+                number_of_parts: Some(number_of_parts),
+            })
+            .await?;
+
+        let send_parts = async {
+            for part_number in 1..=number_of_parts {
+                tracing::debug!(part_number, number_of_parts, "Sending file upload part");
+                self.send_file_upload(
+                    &upload.id,
+                    FileUploadPart {
+                        filename: filename.clone(),
+                        content_type: content_type.clone(),
+                        data,
+                        part_number: Some(part_number),
+                    },
+                )
+                .await?;
+
+                let size = remaining.min(MULTI_PART_CHUNK_SIZE);
+                data = read_part(&mut reader, size).await?;
+                remaining -= size;
+            }
+            self.complete_file_upload(&upload.id).await
+        };
+
+        // Only the first part is read before creating the upload, the caller needs its id
+        // to resume or abandon it.
+        send_parts
+            .await
+            .map_err(|source| Error::FileUploadIncomplete {
+                file_upload_id: upload.id.clone(),
+                source: Box::new(source),
+            })
+    }
+}
+
+async fn read_part<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    size: u64,
+) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::with_capacity(size as usize);
+    reader
+        .take(size)
+        .read_to_end(&mut data)
+        .await
+        .map_err(|source| Error::FileReadError { source })?;
+
+    if (data.len() as u64) < size {
+        return Err(Error::FileReadError {
+            source: std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("expected {} bytes but only read {}", size, data.len()),
+            ),
+        });
+    }
+
+    Ok(data)
+}
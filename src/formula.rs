@@ -0,0 +1,100 @@
+//! Local evaluation of Notion formulas, e.g. to preview the result of a formula before
+//! saving it or to check the results Notion computed.
+//!
+//! ```
+//! use rusticnotion::formula::Expression;
+//! use rusticnotion::models::properties::FormulaResultValue;
+//! use rusticnotion::models::Properties;
+//!
+//! let properties: Properties = serde_json::from_str(
+//!     r#"{ "Estimate": { "id": "a", "type": "number", "number": 3 } }"#,
+//! )?;
+//! let expression = Expression::parse(r#"if(prop("Estimate") > 2, "big", "small")"#)?;
+//! assert_eq!(
+//!     expression.evaluate(&properties)?,
+//!     FormulaResultValue::String { string: Some("big".to_string()) }
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Supported are literals, `prop()`, the operators, conditionals and the number, text,
+//! list, date and logic functions of the formula language. Functions are called as in
+//! `length(text)` or as methods as in `text.length()`.
+
+use crate::models::properties::{Formula, FormulaResultValue};
+use crate::models::Properties;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+mod eval;
+mod parse;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FormulaError {
+    #[error("Syntax error at character {}: {}", .position, .message)]
+    Syntax { position: usize, message: String },
+
+    #[error("Unknown function {}", .name)]
+    UnknownFunction { name: String },
+
+    #[error("Property {} does not exist", .property)]
+    UnknownProperty { property: String },
+
+    #[error("{} expects {} arguments, got {}", .function, .expected, .actual)]
+    Arguments {
+        function: String,
+        expected: String,
+        actual: usize,
+    },
+
+    #[error("{} expects a {}, got a {}", .function, .expected, .actual)]
+    Type {
+        function: String,
+        expected: &'static str,
+        actual: &'static str,
+    },
+
+    #[error("{}: {}", .function, .message)]
+    Invalid { function: String, message: String },
+}
+
+/// A parsed formula.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    expr: parse::Expr,
+}
+
+impl Expression {
+    /// Parses a formula as written in Notion or as returned by the API, which refers to
+    /// properties as `{{notion:block_property:…}}`.
+    pub fn parse(source: &str) -> Result<Self, FormulaError> {
+        Ok(Expression {
+            expr: parse::parse(source)?,
+        })
+    }
+
+    /// Evaluates the formula for a page with `properties`.
+    pub fn evaluate(
+        &self,
+        properties: &Properties,
+    ) -> Result<FormulaResultValue, FormulaError> {
+        self.evaluate_at(properties, Utc::now())
+    }
+
+    /// Evaluates the formula as if it was `now`, the result of `now()` and `today()`.
+    pub fn evaluate_at(
+        &self,
+        properties: &Properties,
+        now: DateTime<Utc>,
+    ) -> Result<FormulaResultValue, FormulaError> {
+        let context = eval::Context { properties, now };
+        Ok(context.evaluate(&self.expr)?.into_result())
+    }
+}
+
+impl Formula {
+    /// Parses the expression of this formula property, see [Expression::parse()].
+    pub fn parse(&self) -> Result<Expression, FormulaError> {
+        Expression::parse(&self.expression)
+    }
+}
@@ -0,0 +1,1377 @@
+//! Evaluation of parsed formulas against the properties of a page.
+
+use crate::formula::parse::{Expr, PropertyRef};
+use crate::formula::FormulaError;
+use crate::models::properties::{
+    number_from_f64, plain_text, DateOrDateTime, DateValue, FileReference, FormulaResultValue,
+    PropertyValue, RollupPropertyValue, RollupValue, SelectedValue,
+};
+use crate::models::text::RichText;
+use crate::models::users::User;
+use crate::models::{Number, Properties};
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// The longest text `repeat`, `padStart` and `padEnd` produce, in bytes or characters.
+const MAX_TEXT_LENGTH: usize = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    /// The value of an empty property.
+    Empty,
+    Number(f64),
+    Text(String),
+    Boolean(bool),
+    Date(Date),
+    List(Vec<Value>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Date {
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    /// Dates without a time are formatted and returned without one.
+    has_time: bool,
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Empty => "empty",
+            Value::Number(_) => "number",
+            Value::Text(_) => "text",
+            Value::Boolean(_) => "boolean",
+            Value::Date(_) => "date",
+            Value::List(_) => "list",
+        }
+    }
+
+    /// Notion treats zero, empty texts and lists and `false` as empty.
+    fn is_empty(&self) -> bool {
+        match self {
+            Value::Empty => true,
+            Value::Number(number) => *number == 0.0,
+            Value::Text(text) => text.is_empty(),
+            Value::Boolean(boolean) => !boolean,
+            Value::Date(_) => false,
+            Value::List(values) => values.is_empty(),
+        }
+    }
+
+    /// The text Notion shows for the value, as returned by `format()`.
+    fn format(&self) -> String {
+        match self {
+            Value::Empty => String::new(),
+            Value::Number(number) => number.to_string(),
+            Value::Text(text) => text.clone(),
+            Value::Boolean(boolean) => boolean.to_string(),
+            Value::Date(date) => {
+                let pattern = if date.has_time {
+                    "MMMM D, YYYY h:mm A"
+                } else {
+                    "MMMM D, YYYY"
+                };
+                match date.end {
+                    Some(end) => format!(
+                        "{} → {}",
+                        format_date(date.start, pattern),
+                        format_date(end, pattern)
+                    ),
+                    None => format_date(date.start, pattern),
+                }
+            }
+            Value::List(values) => values
+                .iter()
+                .map(Value::format)
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    pub(crate) fn into_result(self) -> FormulaResultValue {
+        match self {
+            Value::Empty => FormulaResultValue::String { string: None },
+            Value::Number(number) => FormulaResultValue::Number {
+                number: number_from_f64(number),
+            },
+            Value::Boolean(boolean) => FormulaResultValue::Boolean {
+                boolean: Some(boolean),
+            },
+            Value::Date(date) => FormulaResultValue::Date {
+                date: Some(DateValue {
+                    start: to_date_or_date_time(date.start, date.has_time),
+                    end: date.end.map(|end| to_date_or_date_time(end, date.has_time)),
+                    time_zone: None,
+                }),
+            },
+            value => FormulaResultValue::String {
+                string: Some(value.format()),
+            },
+        }
+    }
+}
+
+fn to_date_or_date_time(
+    time: DateTime<Utc>,
+    has_time: bool,
+) -> DateOrDateTime {
+    if has_time {
+        DateOrDateTime::DateTime(time)
+    } else {
+        DateOrDateTime::Date(time.date_naive())
+    }
+}
+
+fn from_date_or_date_time(date: &DateOrDateTime) -> DateTime<Utc> {
+    match date {
+        DateOrDateTime::Date(date) => midnight(*date),
+        DateOrDateTime::DateTime(time) => *time,
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("midnight exists"))
+}
+
+fn from_date_value(date: &Option<DateValue>) -> Value {
+    match date {
+        Some(date) => Value::Date(Date {
+            start: from_date_or_date_time(&date.start),
+            end: date.end.as_ref().map(from_date_or_date_time),
+            has_time: matches!(date.start, DateOrDateTime::DateTime(_)),
+        }),
+        None => Value::Empty,
+    }
+}
+
+fn from_time(time: &DateTime<Utc>) -> Value {
+    Value::Date(Date {
+        start: *time,
+        end: None,
+        has_time: true,
+    })
+}
+
+fn from_text(text: &[RichText]) -> Value {
+    Value::Text(plain_text(text))
+}
+
+fn from_option(text: &Option<String>) -> Value {
+    text.clone().map_or(Value::Empty, Value::Text)
+}
+
+fn from_number(number: &Option<Number>) -> Value {
+    number
+        .as_ref()
+        .and_then(Number::as_f64)
+        .map_or(Value::Empty, Value::Number)
+}
+
+fn from_selected(selected: &Option<SelectedValue>) -> Value {
+    selected
+        .as_ref()
+        .and_then(|selected| selected.name.clone())
+        .map_or(Value::Empty, Value::Text)
+}
+
+fn from_multi_select(selected: &Option<Vec<SelectedValue>>) -> Value {
+    Value::List(
+        selected
+            .iter()
+            .flatten()
+            .filter_map(|selected| selected.name.clone().map(Value::Text))
+            .collect(),
+    )
+}
+
+fn from_user(user: &User) -> Value {
+    let (User::Person { common, .. } | User::Bot { common, .. } | User::User { common, .. }) = user;
+    Value::Text(common.name.clone().unwrap_or_else(|| common.id.to_string()))
+}
+
+fn from_files(files: &Option<Vec<FileReference>>) -> Value {
+    Value::List(
+        files
+            .iter()
+            .flatten()
+            .map(|file| {
+                let (FileReference::External { name, .. }
+                | FileReference::File { name, .. }
+                | FileReference::FileUpload { name, .. }) = file;
+                Value::Text(name.clone())
+            })
+            .collect(),
+    )
+}
+
+fn from_formula(formula: &FormulaResultValue) -> Value {
+    match formula {
+        FormulaResultValue::String { string } => from_option(string),
+        FormulaResultValue::Number { number } => from_number(number),
+        FormulaResultValue::Boolean { boolean } => boolean.map_or(Value::Empty, Value::Boolean),
+        FormulaResultValue::Date { date } => from_date_value(date),
+    }
+}
+
+fn from_rollup(rollup: &Option<RollupValue>) -> Value {
+    match rollup {
+        Some(RollupValue::Number { number }) => from_number(number),
+        Some(RollupValue::Date { date }) => from_date_value(date),
+        Some(RollupValue::Array { array }) => {
+            Value::List(array.iter().map(from_rollup_element).collect())
+        }
+        None => Value::Empty,
+    }
+}
+
+fn from_rollup_element(value: &RollupPropertyValue) -> Value {
+    use RollupPropertyValue::*;
+    match value {
+        Text { rich_text } => from_text(rich_text),
+        Number { number } => from_number(number),
+        Select { select } | Status { status: select } => from_selected(select),
+        MultiSelect { multi_select } => from_multi_select(multi_select),
+        Date { date } => from_date_value(date),
+        Formula { formula } => from_formula(formula),
+        Relation { relation } => Value::List(
+            relation
+                .iter()
+                .flatten()
+                .map(|page| Value::Text(page.id.to_string()))
+                .collect(),
+        ),
+        Rollup { rollup } => from_rollup(rollup),
+        People { people } => Value::List(people.iter().map(from_user).collect()),
+        Files { files } => from_files(files),
+        Checkbox { checkbox } => Value::Boolean(*checkbox),
+        Url { url } => from_option(url),
+        Email { email } => from_option(email),
+        PhoneNumber { phone_number } => Value::Text(phone_number.clone()),
+        CreatedTime { created_time } => from_time(created_time),
+        CreatedBy { created_by } => from_user(created_by),
+        LastEditedTime { last_edited_time } => from_time(last_edited_time),
+        LastEditedBy { last_edited_by } => from_user(last_edited_by),
+    }
+}
+
+fn from_property(value: &PropertyValue) -> Value {
+    use PropertyValue::*;
+    match value {
+        Title { title, .. } => from_text(title),
+        Text { rich_text, .. } => from_text(rich_text),
+        Number { number, .. } => from_number(number),
+        Select { select, .. } | Status { status: select, .. } => from_selected(select),
+        MultiSelect { multi_select, .. } => from_multi_select(multi_select),
+        Date { date, .. } => from_date_value(date),
+        Formula { formula, .. } => from_formula(formula),
+        Relation { relation, .. } => Value::List(
+            relation
+                .iter()
+                .flatten()
+                .map(|page| Value::Text(page.id.to_string()))
+                .collect(),
+        ),
+        Rollup { rollup, .. } => from_rollup(rollup),
+        People { people, .. } => Value::List(people.iter().map(from_user).collect()),
+        Files { files, .. } => from_files(files),
+        Checkbox { checkbox, .. } => Value::Boolean(*checkbox),
+        Url { url, .. } => from_option(url),
+        Email { email, .. } => from_option(email),
+        PhoneNumber { phone_number, .. } => from_option(phone_number),
+        CreatedTime { created_time, .. } => from_time(created_time),
+        CreatedBy { created_by, .. } => from_user(created_by),
+        LastEditedTime {
+            last_edited_time, ..
+        } => from_time(last_edited_time),
+        LastEditedBy { last_edited_by, .. } => from_user(last_edited_by),
+        Button { .. } => Value::Empty,
+    }
+}
+
+/// The page a formula is evaluated for.
+pub(crate) struct Context<'a> {
+    pub(crate) properties: &'a Properties,
+    /// The result of `now()`.
+    pub(crate) now: DateTime<Utc>,
+}
+
+impl Context<'_> {
+    pub(crate) fn evaluate(
+        &self,
+        expr: &Expr,
+    ) -> Result<Value, FormulaError> {
+        match expr {
+            Expr::Number(number) => Ok(Value::Number(*number)),
+            Expr::Text(text) => Ok(Value::Text(text.clone())),
+            Expr::Boolean(boolean) => Ok(Value::Boolean(*boolean)),
+            Expr::Property(property) => self.property(property),
+            Expr::List(items) => items
+                .iter()
+                .map(|item| self.evaluate(item))
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            Expr::Call(function, args) => self.call(function, args),
+        }
+    }
+
+    fn property(
+        &self,
+        property: &PropertyRef,
+    ) -> Result<Value, FormulaError> {
+        let key = match property {
+            PropertyRef::Name(key) | PropertyRef::Id(key) => key,
+        };
+        self.properties
+            .get_by_name_or_id(key)
+            .map(from_property)
+            .ok_or_else(|| FormulaError::UnknownProperty {
+                property: key.clone(),
+            })
+    }
+
+    /// Evaluates the arguments first, except for the conditionals which only evaluate
+    /// what's needed.
+    fn call(
+        &self,
+        function: &str,
+        args: &[Expr],
+    ) -> Result<Value, FormulaError> {
+        match function {
+            "if" => {
+                arity(function, args.len(), 3, 3)?;
+                let condition = self.condition(function, &args[0])?;
+                self.evaluate(&args[if condition { 1 } else { 2 }])
+            }
+            "ifs" => {
+                if args.len() < 3 || args.len().is_multiple_of(2) {
+                    return Err(FormulaError::Arguments {
+                        function: function.to_string(),
+                        expected: "an odd number of at least 3".to_string(),
+                        actual: args.len(),
+                    });
+                }
+                for pair in args.chunks(2) {
+                    match pair {
+                        [condition, value] => {
+                            if self.condition(function, condition)? {
+                                return self.evaluate(value);
+                            }
+                        }
+                        [otherwise] => return self.evaluate(otherwise),
+                        _ => unreachable!("chunks of two"),
+                    }
+                }
+                unreachable!("the last chunk has one element")
+            }
+            "and" | "or" => {
+                arity(function, args.len(), 2, usize::MAX)?;
+                let stop = function == "or";
+                for arg in args {
+                    if self.condition(function, arg)? == stop {
+                        return Ok(Value::Boolean(stop));
+                    }
+                }
+                Ok(Value::Boolean(!stop))
+            }
+            _ => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.evaluate(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Args { function, values }.call(self.now)
+            }
+        }
+    }
+
+    fn condition(
+        &self,
+        function: &str,
+        expr: &Expr,
+    ) -> Result<bool, FormulaError> {
+        let value = self.evaluate(expr)?;
+        Args {
+            function,
+            values: vec![value],
+        }
+        .boolean(0)
+    }
+}
+
+fn arity(
+    function: &str,
+    actual: usize,
+    min: usize,
+    max: usize,
+) -> Result<(), FormulaError> {
+    if (min..=max).contains(&actual) {
+        return Ok(());
+    }
+    Err(FormulaError::Arguments {
+        function: function.to_string(),
+        expected: if min == max {
+            min.to_string()
+        } else if max == usize::MAX {
+            format!("at least {}", min)
+        } else {
+            format!("{} to {}", min, max)
+        },
+        actual,
+    })
+}
+
+/// The evaluated arguments of a function call.
+struct Args<'a> {
+    function: &'a str,
+    values: Vec<Value>,
+}
+
+impl Args<'_> {
+    fn count(
+        &self,
+        min: usize,
+        max: usize,
+    ) -> Result<(), FormulaError> {
+        arity(self.function, self.values.len(), min, max)
+    }
+
+    fn wrong_type(
+        &self,
+        expected: &'static str,
+        actual: &Value,
+    ) -> FormulaError {
+        FormulaError::Type {
+            function: self.function.to_string(),
+            expected,
+            actual: actual.type_name(),
+        }
+    }
+
+    fn invalid(
+        &self,
+        message: String,
+    ) -> FormulaError {
+        FormulaError::Invalid {
+            function: self.function.to_string(),
+            message,
+        }
+    }
+
+    fn number(
+        &self,
+        index: usize,
+    ) -> Result<f64, FormulaError> {
+        match &self.values[index] {
+            Value::Number(number) => Ok(*number),
+            Value::Empty => Ok(0.0),
+            other => Err(self.wrong_type("number", other)),
+        }
+    }
+
+    fn text(
+        &self,
+        index: usize,
+    ) -> Result<&str, FormulaError> {
+        match &self.values[index] {
+            Value::Text(text) => Ok(text),
+            Value::Empty => Ok(""),
+            other => Err(self.wrong_type("text", other)),
+        }
+    }
+
+    fn boolean(
+        &self,
+        index: usize,
+    ) -> Result<bool, FormulaError> {
+        match &self.values[index] {
+            Value::Boolean(boolean) => Ok(*boolean),
+            Value::Empty => Ok(false),
+            other => Err(self.wrong_type("boolean", other)),
+        }
+    }
+
+    /// `None` for an empty date, date functions then return empty as well.
+    fn date(
+        &self,
+        index: usize,
+    ) -> Result<Option<&Date>, FormulaError> {
+        match &self.values[index] {
+            Value::Date(date) => Ok(Some(date)),
+            Value::Empty => Ok(None),
+            other => Err(self.wrong_type("date", other)),
+        }
+    }
+
+    fn list(
+        &self,
+        index: usize,
+    ) -> Result<&[Value], FormulaError> {
+        match &self.values[index] {
+            Value::List(values) => Ok(values),
+            Value::Empty => Ok(&[]),
+            other => Err(self.wrong_type("list", other)),
+        }
+    }
+
+    /// All numbers of the arguments, including those in lists.
+    fn numbers(&self) -> Result<Vec<f64>, FormulaError> {
+        let mut numbers = vec![];
+        for value in &self.values {
+            let values = match value {
+                Value::List(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            for value in values {
+                match value {
+                    Value::Number(number) => numbers.push(*number),
+                    Value::Empty => {}
+                    other => return Err(self.wrong_type("number", other)),
+                }
+            }
+        }
+        Ok(numbers)
+    }
+
+    fn math<F: Fn(f64) -> f64>(
+        &self,
+        f: F,
+    ) -> Result<Value, FormulaError> {
+        self.count(1, 1)?;
+        Ok(Value::Number(f(self.number(0)?)))
+    }
+
+    fn arithmetic<F: Fn(f64, f64) -> f64>(
+        &self,
+        f: F,
+    ) -> Result<Value, FormulaError> {
+        self.count(2, 2)?;
+        Ok(Value::Number(f(self.number(0)?, self.number(1)?)))
+    }
+
+    fn compare(&self) -> Result<Ordering, FormulaError> {
+        self.count(2, 2)?;
+        match (&self.values[0], &self.values[1]) {
+            (Value::Number(a), Value::Number(b)) => Ok(a.partial_cmp(b).unwrap_or(Ordering::Equal)),
+            (Value::Text(a), Value::Text(b)) => Ok(a.cmp(b)),
+            (Value::Boolean(a), Value::Boolean(b)) => Ok(a.cmp(b)),
+            (Value::Date(a), Value::Date(b)) => Ok(a.start.cmp(&b.start)),
+            (Value::Empty, Value::Empty) => Ok(Ordering::Equal),
+            (a, b) if a.type_name() == b.type_name() => {
+                Err(self.wrong_type("number, text, boolean or date", a))
+            }
+            (a, b) => Err(self.wrong_type(a.type_name(), b)),
+        }
+    }
+
+    fn call(
+        self,
+        now: DateTime<Utc>,
+    ) -> Result<Value, FormulaError> {
+        let values = &self.values;
+        Ok(match self.function {
+            // Numbers
+            "add" => {
+                self.count(2, 2)?;
+                match (&values[0], &values[1]) {
+                    (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+                    (Value::Text(_), _) | (_, Value::Text(_)) => {
+                        Value::Text(values[0].format() + &values[1].format())
+                    }
+                    (Value::Empty, other) | (other, Value::Empty) => other.clone(),
+                    (other, _) => return Err(self.wrong_type("number or text", other)),
+                }
+            }
+            "subtract" => self.arithmetic(|a, b| a - b)?,
+            "multiply" => self.arithmetic(|a, b| a * b)?,
+            "divide" => self.arithmetic(|a, b| a / b)?,
+            "mod" => self.arithmetic(|a, b| a % b)?,
+            "pow" => self.arithmetic(f64::powf)?,
+            "unaryMinus" => self.math(|a| -a)?,
+            "unaryPlus" | "toNumber" => {
+                self.count(1, 1)?;
+                match &values[0] {
+                    Value::Number(number) => Value::Number(*number),
+                    Value::Text(text) => text.trim().parse().map_or(Value::Empty, Value::Number),
+                    Value::Boolean(boolean) => Value::Number(*boolean as u8 as f64),
+                    Value::Date(date) => Value::Number(date.start.timestamp_millis() as f64),
+                    Value::Empty => Value::Empty,
+                    other => return Err(self.wrong_type("number, text, boolean or date", other)),
+                }
+            }
+            "abs" => self.math(f64::abs)?,
+            "cbrt" => self.math(f64::cbrt)?,
+            "ceil" => self.math(f64::ceil)?,
+            "exp" => self.math(f64::exp)?,
+            "floor" => self.math(f64::floor)?,
+            "ln" => self.math(f64::ln)?,
+            "log10" => self.math(f64::log10)?,
+            "log2" => self.math(f64::log2)?,
+            "sqrt" => self.math(f64::sqrt)?,
+            "sign" => self.math(|a| if a == 0.0 { 0.0 } else { a.signum() })?,
+            "round" => {
+                self.count(1, 2)?;
+                let factor = 10f64.powi(match values.get(1) {
+                    Some(_) => self.number(1)? as i32,
+                    None => 0,
+                });
+                // Halves are rounded up like JavaScript's Math.round(), -2.5 becomes -2.
+                Value::Number((self.number(0)? * factor + 0.5).floor() / factor)
+            }
+            "max" | "min" | "sum" | "mean" | "median" => {
+                self.count(1, usize::MAX)?;
+                let mut numbers = self.numbers()?;
+                if numbers.is_empty() {
+                    return Ok(Value::Empty);
+                }
+                let count = numbers.len() as f64;
+                Value::Number(match self.function {
+                    "max" => numbers.iter().copied().fold(f64::MIN, f64::max),
+                    "min" => numbers.iter().copied().fold(f64::MAX, f64::min),
+                    "sum" => numbers.iter().sum(),
+                    "mean" => numbers.iter().sum::<f64>() / count,
+                    _ => {
+                        numbers.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                        let middle = numbers.len() / 2;
+                        if numbers.len().is_multiple_of(2) {
+                            (numbers[middle - 1] + numbers[middle]) / 2.0
+                        } else {
+                            numbers[middle]
+                        }
+                    }
+                })
+            }
+
+            // Comparisons and logic
+            "equal" => {
+                self.count(2, 2)?;
+                Value::Boolean(values[0] == values[1])
+            }
+            "unequal" => {
+                self.count(2, 2)?;
+                Value::Boolean(values[0] != values[1])
+            }
+            "larger" => Value::Boolean(self.compare()? == Ordering::Greater),
+            "largerEq" => Value::Boolean(self.compare()? != Ordering::Less),
+            "smaller" => Value::Boolean(self.compare()? == Ordering::Less),
+            "smallerEq" => Value::Boolean(self.compare()? != Ordering::Greater),
+            "not" => {
+                self.count(1, 1)?;
+                Value::Boolean(!self.boolean(0)?)
+            }
+            "empty" => {
+                self.count(1, 1)?;
+                Value::Boolean(values[0].is_empty())
+            }
+
+            // Text and lists
+            "format" => {
+                self.count(1, 1)?;
+                Value::Text(values[0].format())
+            }
+            "concat" => {
+                self.count(1, usize::MAX)?;
+                if values.iter().all(|value| matches!(value, Value::List(_))) {
+                    Value::List(
+                        values
+                            .iter()
+                            .flat_map(|value| self.list_of(value))
+                            .collect(),
+                    )
+                } else {
+                    let mut text = String::new();
+                    for index in 0..values.len() {
+                        text.push_str(self.text(index)?);
+                    }
+                    Value::Text(text)
+                }
+            }
+            "join" => {
+                self.count(1, usize::MAX)?;
+                if let Value::List(items) = &values[0] {
+                    self.count(2, 2)?;
+                    let items: Vec<String> = items.iter().map(Value::format).collect();
+                    Value::Text(items.join(self.text(1)?))
+                } else {
+                    let mut items = vec![];
+                    for index in 1..values.len() {
+                        items.push(self.text(index)?);
+                    }
+                    Value::Text(items.join(self.text(0)?))
+                }
+            }
+            "length" => {
+                self.count(1, 1)?;
+                match &values[0] {
+                    Value::List(items) => Value::Number(items.len() as f64),
+                    _ => Value::Number(self.text(0)?.chars().count() as f64),
+                }
+            }
+            "slice" | "substring" => {
+                self.count(2, 3)?;
+                let start = self.number(1)?;
+                let end = match values.get(2) {
+                    Some(_) => Some(self.number(2)?),
+                    None => None,
+                };
+                match &values[0] {
+                    Value::List(items) => {
+                        let (start, end) = bounds(items.len(), start, end);
+                        Value::List(items[start..end].to_vec())
+                    }
+                    _ => {
+                        let chars: Vec<char> = self.text(0)?.chars().collect();
+                        let (start, end) = bounds(chars.len(), start, end);
+                        Value::Text(chars[start..end].iter().collect())
+                    }
+                }
+            }
+            "contains" | "includes" => {
+                self.count(2, 2)?;
+                match &values[0] {
+                    Value::List(items) => Value::Boolean(items.contains(&values[1])),
+                    _ => Value::Boolean(self.text(0)?.contains(self.text(1)?)),
+                }
+            }
+            "replace" => {
+                self.count(3, 3)?;
+                Value::Text(self.text(0)?.replacen(self.text(1)?, self.text(2)?, 1))
+            }
+            "replaceAll" => {
+                self.count(3, 3)?;
+                Value::Text(self.text(0)?.replace(self.text(1)?, self.text(2)?))
+            }
+            "lower" => {
+                self.count(1, 1)?;
+                Value::Text(self.text(0)?.to_lowercase())
+            }
+            "upper" => {
+                self.count(1, 1)?;
+                Value::Text(self.text(0)?.to_uppercase())
+            }
+            "trim" => {
+                self.count(1, 1)?;
+                Value::Text(self.text(0)?.trim().to_string())
+            }
+            "repeat" => {
+                self.count(2, 2)?;
+                let text = self.text(0)?;
+                let count = self.length(1)?;
+                if text.len().saturating_mul(count) > MAX_TEXT_LENGTH {
+                    return Err(self.invalid("the text would be too long".to_string()));
+                }
+                Value::Text(text.repeat(count))
+            }
+            "split" => {
+                self.count(2, 2)?;
+                Value::List(
+                    self.text(0)?
+                        .split(self.text(1)?)
+                        .map(|part| Value::Text(part.to_string()))
+                        .collect(),
+                )
+            }
+            "padStart" | "padEnd" => {
+                self.count(3, 3)?;
+                let text = self.text(0)?;
+                let missing = self.length(1)?.saturating_sub(text.chars().count());
+                let padding: String = self.text(2)?.chars().cycle().take(missing).collect();
+                Value::Text(if self.function == "padStart" {
+                    padding + text
+                } else {
+                    text.to_string() + &padding
+                })
+            }
+            "at" => {
+                self.count(2, 2)?;
+                let items = self.list(0)?;
+                let (start, end) = bounds(items.len(), self.number(1)?, None);
+                items[start..end].first().cloned().unwrap_or(Value::Empty)
+            }
+            "first" => {
+                self.count(1, 1)?;
+                self.list(0)?.first().cloned().unwrap_or(Value::Empty)
+            }
+            "last" => {
+                self.count(1, 1)?;
+                self.list(0)?.last().cloned().unwrap_or(Value::Empty)
+            }
+
+            // Dates
+            "now" => {
+                self.count(0, 0)?;
+                from_time(&now)
+            }
+            "today" => {
+                self.count(0, 0)?;
+                Value::Date(Date {
+                    start: midnight(now.date_naive()),
+                    end: None,
+                    has_time: false,
+                })
+            }
+            "dateAdd" | "dateSubtract" => {
+                self.count(3, 3)?;
+                let date = match self.date(0)? {
+                    Some(date) => date,
+                    None => return Ok(Value::Empty),
+                };
+                let amount = self.number(1)?;
+                let amount = if self.function == "dateSubtract" {
+                    -amount
+                } else {
+                    amount
+                };
+                let unit = self.unit(2)?;
+                let shift = |time| self.shift(time, amount, unit);
+                Value::Date(Date {
+                    start: shift(date.start)?,
+                    end: date.end.map(shift).transpose()?,
+                    has_time: date.has_time || unit.milliseconds().is_some_and(|ms| ms < DAY),
+                })
+            }
+            "dateBetween" => {
+                self.count(3, 3)?;
+                match (self.date(0)?, self.date(1)?) {
+                    (Some(a), Some(b)) => Value::Number(between(a.start, b.start, self.unit(2)?)),
+                    _ => Value::Empty,
+                }
+            }
+            "formatDate" => {
+                self.count(2, 2)?;
+                match self.date(0)? {
+                    Some(date) => Value::Text(format_date(date.start, self.text(1)?)),
+                    None => Value::Empty,
+                }
+            }
+            "parseDate" => {
+                self.count(1, 1)?;
+                let text = self.text(0)?.trim();
+                if let Ok(date) = text.parse::<NaiveDate>() {
+                    Value::Date(Date {
+                        start: midnight(date),
+                        end: None,
+                        has_time: false,
+                    })
+                } else if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+                    from_time(&time.with_timezone(&Utc))
+                } else if let Ok(time) = text.parse::<NaiveDateTime>() {
+                    from_time(&Utc.from_utc_datetime(&time))
+                } else {
+                    return Err(self.invalid(format!("{:?} is not an ISO 8601 date", text)));
+                }
+            }
+            "fromTimestamp" => {
+                self.count(1, 1)?;
+                let time = Utc
+                    .timestamp_millis_opt(self.number(0)? as i64)
+                    .single()
+                    .ok_or_else(|| self.invalid("the timestamp is out of range".to_string()))?;
+                from_time(&time)
+            }
+            "timestamp" => {
+                self.count(1, 1)?;
+                match self.date(0)? {
+                    Some(date) => Value::Number(date.start.timestamp_millis() as f64),
+                    None => Value::Empty,
+                }
+            }
+            "minute" | "hour" | "day" | "date" | "week" | "month" | "year" => {
+                self.count(1, 1)?;
+                let time = match self.date(0)? {
+                    Some(date) => date.start,
+                    None => return Ok(Value::Empty),
+                };
+                Value::Number(match self.function {
+                    "minute" => time.minute() as f64,
+                    "hour" => time.hour() as f64,
+                    "day" => time.weekday().number_from_monday() as f64,
+                    "date" => time.day() as f64,
+                    "week" => time.iso_week().week() as f64,
+                    "month" => time.month() as f64,
+                    _ => time.year() as f64,
+                })
+            }
+            "start" | "dateStart" | "end" | "dateEnd" => {
+                self.count(1, 1)?;
+                match self.date(0)? {
+                    Some(date) => Value::Date(Date {
+                        start: if matches!(self.function, "start" | "dateStart") {
+                            date.start
+                        } else {
+                            date.end.unwrap_or(date.start)
+                        },
+                        end: None,
+                        has_time: date.has_time,
+                    }),
+                    None => Value::Empty,
+                }
+            }
+            "dateRange" => {
+                self.count(2, 2)?;
+                match (self.date(0)?, self.date(1)?) {
+                    (Some(start), Some(end)) => Value::Date(Date {
+                        start: start.start,
+                        end: Some(end.start),
+                        has_time: start.has_time || end.has_time,
+                    }),
+                    _ => Value::Empty,
+                }
+            }
+            function => {
+                return Err(FormulaError::UnknownFunction {
+                    name: function.to_string(),
+                })
+            }
+        })
+    }
+
+    fn list_of(
+        &self,
+        value: &Value,
+    ) -> Vec<Value> {
+        match value {
+            Value::List(items) => items.clone(),
+            value => vec![value.clone()],
+        }
+    }
+
+    /// A length for the text functions, at most [MAX_TEXT_LENGTH].
+    fn length(
+        &self,
+        index: usize,
+    ) -> Result<usize, FormulaError> {
+        let length = self.number(index)?.max(0.0);
+        if length > MAX_TEXT_LENGTH as f64 {
+            return Err(self.invalid("the text would be too long".to_string()));
+        }
+        Ok(length as usize)
+    }
+
+    fn unit(
+        &self,
+        index: usize,
+    ) -> Result<Unit, FormulaError> {
+        let name = self.text(index)?;
+        Unit::parse(name).ok_or_else(|| self.invalid(format!("unknown unit {:?}", name)))
+    }
+
+    fn shift(
+        &self,
+        time: DateTime<Utc>,
+        amount: f64,
+        unit: Unit,
+    ) -> Result<DateTime<Utc>, FormulaError> {
+        let shifted = match unit.milliseconds() {
+            Some(milliseconds) => checked_i64(amount * milliseconds as f64)
+                .and_then(Duration::try_milliseconds)
+                .and_then(|duration| time.checked_add_signed(duration)),
+            None => checked_i64(amount.trunc())
+                .and_then(|amount| amount.checked_mul(unit.months()))
+                .and_then(|months| {
+                    let shift = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+                    if months < 0 {
+                        time.checked_sub_months(shift)
+                    } else {
+                        time.checked_add_months(shift)
+                    }
+                }),
+        };
+        shifted.ok_or_else(|| self.invalid("the date is out of range".to_string()))
+    }
+}
+
+/// The number as an integer, `None` if it isn't finite or doesn't fit into an `i64`.
+fn checked_i64(number: f64) -> Option<i64> {
+    if number.is_finite() && number.abs() < i64::MAX as f64 {
+        Some(number as i64)
+    } else {
+        None
+    }
+}
+
+/// Resolves JavaScript style slice bounds, negative ones count from the end.
+fn bounds(
+    length: usize,
+    start: f64,
+    end: Option<f64>,
+) -> (usize, usize) {
+    let resolve = |index: f64| {
+        let index = index.trunc();
+        if index < 0.0 {
+            (length as f64 + index).max(0.0) as usize
+        } else {
+            (index as usize).min(length)
+        }
+    };
+    let start = resolve(start);
+    let end = end.map_or(length, resolve);
+    (start, end.max(start))
+}
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Unit {
+    Years,
+    Quarters,
+    Months,
+    Weeks,
+    Days,
+    Hours,
+    Minutes,
+    Seconds,
+    Milliseconds,
+}
+
+impl Unit {
+    /// Accepts the singular and plural names Notion uses, e.g. `"day"` and `"days"`.
+    fn parse(name: &str) -> Option<Unit> {
+        Some(match name.trim_end_matches('s') {
+            "year" => Unit::Years,
+            "quarter" => Unit::Quarters,
+            "month" => Unit::Months,
+            "week" => Unit::Weeks,
+            "day" => Unit::Days,
+            "hour" => Unit::Hours,
+            "minute" => Unit::Minutes,
+            "second" => Unit::Seconds,
+            "millisecond" => Unit::Milliseconds,
+            _ => return None,
+        })
+    }
+
+    /// The fixed length of the unit, `None` for units counted in months.
+    fn milliseconds(self) -> Option<i64> {
+        match self {
+            Unit::Years | Unit::Quarters | Unit::Months => None,
+            Unit::Weeks => Some(7 * DAY),
+            Unit::Days => Some(DAY),
+            Unit::Hours => Some(60 * 60 * 1000),
+            Unit::Minutes => Some(60 * 1000),
+            Unit::Seconds => Some(1000),
+            Unit::Milliseconds => Some(1),
+        }
+    }
+
+    fn months(self) -> i64 {
+        match self {
+            Unit::Years => 12,
+            Unit::Quarters => 3,
+            _ => 1,
+        }
+    }
+}
+
+/// The whole number of `unit`s from `b` to `a`, negative if `a` is earlier.
+fn between(
+    a: DateTime<Utc>,
+    b: DateTime<Utc>,
+    unit: Unit,
+) -> f64 {
+    if let Some(milliseconds) = unit.milliseconds() {
+        return ((a - b).num_milliseconds() / milliseconds) as f64;
+    }
+
+    let mut months = (a.year() as i64 - b.year() as i64) * 12 + a.month() as i64 - b.month() as i64;
+    let shifted = |months: i64| {
+        let amount = Months::new(months.unsigned_abs() as u32);
+        if months < 0 {
+            b.checked_sub_months(amount)
+        } else {
+            b.checked_add_months(amount)
+        }
+    };
+    // Not a whole month yet if the day or time of `a` hasn't been reached.
+    if months > 0 && shifted(months).is_some_and(|shifted| shifted > a) {
+        months -= 1;
+    } else if months < 0 && shifted(months).is_some_and(|shifted| shifted < a) {
+        months += 1;
+    }
+    (months / unit.months()) as f64
+}
+
+/// Formats with the Moment.js tokens `formatDate()` accepts, text in `[]` is kept as is.
+fn format_date(
+    time: DateTime<Utc>,
+    pattern: &str,
+) -> String {
+    const TOKENS: &[&str] = &[
+        "YYYY", "YY", "MMMM", "MMM", "MM", "M", "Do", "DD", "D", "dddd", "ddd", "HH", "H", "hh",
+        "h", "mm", "m", "ss", "s", "A", "a",
+    ];
+
+    let hour12 = match time.hour() % 12 {
+        0 => 12,
+        hour => hour,
+    };
+    let mut formatted = String::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                formatted.push_str(&rest[1..end]);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        match TOKENS.iter().find(|token| rest.starts_with(*token)) {
+            Some(token) => {
+                formatted.push_str(&match *token {
+                    "YYYY" => time.year().to_string(),
+                    "YY" => format!("{:02}", time.year() % 100),
+                    "MMMM" => MONTHS[time.month0() as usize].to_string(),
+                    "MMM" => MONTHS[time.month0() as usize][..3].to_string(),
+                    "MM" => format!("{:02}", time.month()),
+                    "M" => time.month().to_string(),
+                    "Do" => ordinal(time.day()),
+                    "DD" => format!("{:02}", time.day()),
+                    "D" => time.day().to_string(),
+                    "dddd" => WEEKDAYS[time.weekday().num_days_from_monday() as usize].to_string(),
+                    "ddd" => {
+                        WEEKDAYS[time.weekday().num_days_from_monday() as usize][..3].to_string()
+                    }
+                    "HH" => format!("{:02}", time.hour()),
+                    "H" => time.hour().to_string(),
+                    "hh" => format!("{:02}", hour12),
+                    "h" => hour12.to_string(),
+                    "mm" => format!("{:02}", time.minute()),
+                    "m" => time.minute().to_string(),
+                    "ss" => format!("{:02}", time.second()),
+                    "s" => time.second().to_string(),
+                    "A" => if time.hour() < 12 { "AM" } else { "PM" }.to_string(),
+                    _ => if time.hour() < 12 { "am" } else { "pm" }.to_string(),
+                });
+                rest = &rest[token.len()..];
+            }
+            None => {
+                formatted.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    formatted
+}
+
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", day, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formula::{Expression, FormulaError};
+    use crate::models::properties::{DateOrDateTime, DateValue, FormulaResultValue};
+    use crate::models::{Number, Properties};
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use serde_json::json;
+
+    fn properties() -> Properties {
+        serde_json::from_value(json!({
+            "Name": {
+                "id": "title",
+                "type": "title",
+                "title": [{ "type": "text", "text": { "content": "Write docs" }, "plain_text": "Write docs" }]
+            },
+            "Estimate": { "id": "est", "type": "number", "number": 3 },
+            "Done": { "id": "done", "type": "checkbox", "checkbox": false },
+            "Due": { "id": "due", "type": "date", "date": { "start": "2024-02-28" } },
+            "Tags": {
+                "id": "tags",
+                "type": "multi_select",
+                "multi_select": [
+                    { "name": "docs", "color": "blue" },
+                    { "name": "urgent", "color": "red" }
+                ]
+            },
+            "Empty": { "id": "empty", "type": "number", "number": null }
+        }))
+        .unwrap()
+    }
+
+    fn evaluate(source: &str) -> Result<FormulaResultValue, FormulaError> {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 15, 30, 0).unwrap();
+        Expression::parse(source)?.evaluate_at(&properties(), now)
+    }
+
+    fn number(number: i64) -> FormulaResultValue {
+        FormulaResultValue::Number {
+            number: Some(Number::from(number)),
+        }
+    }
+
+    fn text(text: &str) -> FormulaResultValue {
+        FormulaResultValue::String {
+            string: Some(text.to_string()),
+        }
+    }
+
+    fn boolean(boolean: bool) -> FormulaResultValue {
+        FormulaResultValue::Boolean {
+            boolean: Some(boolean),
+        }
+    }
+
+    #[test]
+    fn arithmetic_and_logic() {
+        assert_eq!(evaluate(r#"prop("Estimate") * 2 + 1"#), Ok(number(7)));
+        assert_eq!(evaluate("-2 ^ 2 + 10 % 4"), Ok(number(-2)));
+        assert_eq!(evaluate("round(2.5) + round(-2.5)"), Ok(number(1)));
+        assert_eq!(evaluate("round(1.2345, 2) == 1.23"), Ok(boolean(true)));
+        assert_eq!(evaluate(r#"prop("Empty") + 1"#), Ok(number(1)));
+        assert_eq!(
+            evaluate("max(1, 5, 3) == 5 and not false"),
+            Ok(boolean(true))
+        );
+        assert_eq!(
+            evaluate(r#"if(prop("Done"), "done", prop("Estimate") > 2 ? "big" : "small")"#),
+            Ok(text("big"))
+        );
+        assert_eq!(
+            evaluate(r#"ifs(prop("Estimate") < 2, "S", prop("Estimate") < 5, "M", "L")"#),
+            Ok(text("M"))
+        );
+        assert_eq!(
+            evaluate(r#"empty(prop("Empty")) && !empty(prop("Tags"))"#),
+            Ok(boolean(true))
+        );
+    }
+
+    #[test]
+    fn text_functions() {
+        assert_eq!(
+            evaluate(r#"concat(upper(slice(prop("Name"), 0, 5)), "!")"#),
+            Ok(text("WRITE!"))
+        );
+        assert_eq!(evaluate(r#"prop("Name").length()"#), Ok(number(10)));
+        assert_eq!(
+            evaluate(r#"prop("Estimate") + " points, " + join(prop("Tags"), "/")"#),
+            Ok(text("3 points, docs/urgent"))
+        );
+        assert_eq!(
+            evaluate(r#"replaceAll("a-b-c", "-", "+") + format(true)"#),
+            Ok(text("a+b+ctrue"))
+        );
+        assert_eq!(
+            evaluate(r#"contains(prop("Tags"), "urgent")"#),
+            Ok(boolean(true))
+        );
+    }
+
+    #[test]
+    fn date_functions() {
+        assert_eq!(
+            evaluate(r#"dateAdd(prop("Due"), 1, "months")"#),
+            Ok(FormulaResultValue::Date {
+                date: Some(DateValue {
+                    start: DateOrDateTime::Date(NaiveDate::from_ymd_opt(2024, 3, 28).unwrap()),
+                    end: None,
+                    time_zone: None,
+                }),
+            })
+        );
+        assert_eq!(
+            evaluate(r#"dateBetween(now(), prop("Due"), "days")"#),
+            Ok(number(2))
+        );
+        assert_eq!(
+            evaluate(r#"dateBetween(prop("Due"), now(), "months")"#),
+            Ok(number(0))
+        );
+        assert_eq!(
+            evaluate(r#"formatDate(now(), "dddd, MMMM Do YYYY [at] h:mm A")"#),
+            Ok(text("Friday, March 1st 2024 at 3:30 PM"))
+        );
+        assert_eq!(
+            evaluate(r#"format(dateSubtract(prop("Due"), 2, "weeks"))"#),
+            Ok(text("February 14, 2024"))
+        );
+        assert_eq!(
+            evaluate(r#"month(prop("Due")) + day(parseDate("2024-03-03"))"#),
+            Ok(number(9))
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(
+            evaluate(r#"prop("Missing")"#),
+            Err(FormulaError::UnknownProperty {
+                property: "Missing".to_string(),
+            })
+        );
+        assert_eq!(
+            evaluate("nope(1)"),
+            Err(FormulaError::UnknownFunction {
+                name: "nope".to_string(),
+            })
+        );
+        assert_eq!(
+            evaluate(r#"abs("x")"#),
+            Err(FormulaError::Type {
+                function: "abs".to_string(),
+                expected: "number",
+                actual: "text",
+            })
+        );
+        assert_eq!(
+            evaluate("if(true, 1)"),
+            Err(FormulaError::Arguments {
+                function: "if".to_string(),
+                expected: "3".to_string(),
+                actual: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn out_of_range_arguments_are_errors() {
+        for source in [
+            r#"dateAdd(now(), -1e30, "days")"#,
+            r#"dateAdd(now(), 1e30, "years")"#,
+            r#"dateSubtract(now(), 1e300, "milliseconds")"#,
+            r#"repeat("x", 1e20)"#,
+            r#"padStart("x", 1e19, "y")"#,
+            r#"padEnd("x", 1e19, "y")"#,
+        ] {
+            assert!(
+                matches!(evaluate(source), Err(FormulaError::Invalid { .. })),
+                "{}",
+                source
+            );
+        }
+        assert_eq!(evaluate(r#"repeat("ab", 3)"#), Ok(text("ababab")));
+    }
+}
@@ -0,0 +1,491 @@
+//! Parsing of formula expressions into calls of the formula functions.
+
+use crate::formula::FormulaError;
+
+/// A parsed formula, operators are calls of the function Notion documents them as,
+/// e.g. `a + b` is `add(a, b)`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Number(f64),
+    Text(String),
+    Boolean(bool),
+    Property(PropertyRef),
+    List(Vec<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PropertyRef {
+    /// `prop("Name")`
+    Name(String),
+    /// `{{notion:block_property:<id>:…}}`, as the API returns formula expressions.
+    Id(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Ident(String),
+    Property(String),
+    Operator(&'static str),
+    End,
+}
+
+/// Operators and punctuation, longer ones first so that `>=` isn't read as `>`.
+const OPERATORS: &[&str] = &[
+    "==", "!=", ">=", "<=", "&&", "||", "+", "-", "*", "/", "%", "^", ">", "<", "!", "(", ")", "[",
+    "]", ",", ".", "?", ":",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, FormulaError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut position = 0;
+
+    while position < chars.len() {
+        let start = position;
+        let c = chars[position];
+        if c.is_whitespace() {
+            position += 1;
+            continue;
+        }
+
+        let token = if c.is_ascii_digit()
+            || (c == '.' && chars.get(position + 1).is_some_and(char::is_ascii_digit))
+        {
+            while position < chars.len()
+                && (chars[position].is_ascii_digit() || chars[position] == '.')
+            {
+                position += 1;
+            }
+            if position < chars.len() && (chars[position] == 'e' || chars[position] == 'E') {
+                let mut exponent = position + 1;
+                if exponent < chars.len() && (chars[exponent] == '+' || chars[exponent] == '-') {
+                    exponent += 1;
+                }
+                if exponent < chars.len() && chars[exponent].is_ascii_digit() {
+                    position = exponent;
+                    while position < chars.len() && chars[position].is_ascii_digit() {
+                        position += 1;
+                    }
+                }
+            }
+            let literal: String = chars[start..position].iter().collect();
+            Token::Number(literal.parse().map_err(|_| FormulaError::Syntax {
+                position: start,
+                message: format!("invalid number {}", literal),
+            })?)
+        } else if c == '"' || c == '\'' {
+            position += 1;
+            let mut text = String::new();
+            loop {
+                match chars.get(position) {
+                    None => {
+                        return Err(FormulaError::Syntax {
+                            position: start,
+                            message: "unterminated string".to_string(),
+                        })
+                    }
+                    Some(&quote) if quote == c => break,
+                    Some('\\') => {
+                        position += 1;
+                        text.push(match chars.get(position) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some(&escaped) => escaped,
+                            None => continue,
+                        });
+                    }
+                    Some(&other) => text.push(other),
+                }
+                position += 1;
+            }
+            position += 1;
+            Token::Text(text)
+        } else if c.is_alphabetic() || c == '_' {
+            while position < chars.len()
+                && (chars[position].is_alphanumeric() || chars[position] == '_')
+            {
+                position += 1;
+            }
+            Token::Ident(chars[start..position].iter().collect())
+        } else if source_at(&chars, position, "{{") {
+            let end = (position..chars.len())
+                .find(|&index| source_at(&chars, index, "}}"))
+                .ok_or_else(|| FormulaError::Syntax {
+                    position: start,
+                    message: "unterminated {{".to_string(),
+                })?;
+            let reference: String = chars[position + 2..end].iter().collect();
+            position = end + 2;
+            match reference.strip_prefix("notion:block_property:") {
+                Some(rest) => {
+                    Token::Property(rest.split(':').next().unwrap_or_default().to_string())
+                }
+                None => {
+                    return Err(FormulaError::Syntax {
+                        position: start,
+                        message: format!("unknown reference {}", reference),
+                    })
+                }
+            }
+        } else {
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| source_at(&chars, position, operator))
+                .ok_or_else(|| FormulaError::Syntax {
+                    position: start,
+                    message: format!("unexpected character {}", c),
+                })?;
+            position += operator.chars().count();
+            Token::Operator(operator)
+        };
+        tokens.push((start, token));
+    }
+
+    tokens.push((chars.len(), Token::End));
+    Ok(tokens)
+}
+
+fn source_at(
+    chars: &[char],
+    position: usize,
+    expected: &str,
+) -> bool {
+    expected
+        .chars()
+        .enumerate()
+        .all(|(offset, c)| chars.get(position + offset) == Some(&c))
+}
+
+pub(crate) fn parse(source: &str) -> Result<Expr, FormulaError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        next: 0,
+    };
+    let expr = parser.conditional()?;
+    match parser.peek() {
+        Token::End => Ok(expr),
+        _ => Err(parser.unexpected()),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+}
+
+fn call(
+    function: &str,
+    args: Vec<Expr>,
+) -> Expr {
+    Expr::Call(function.to_string(), args)
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].1
+    }
+
+    /// Consumes the next token, callers step back before reporting it as unexpected.
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].1.clone();
+        self.next += 1;
+        token
+    }
+
+    fn unexpected(&self) -> FormulaError {
+        let (position, token) = &self.tokens[self.next];
+        FormulaError::Syntax {
+            position: *position,
+            message: match token {
+                Token::End => "unexpected end of formula".to_string(),
+                token => format!("unexpected {:?}", token),
+            },
+        }
+    }
+
+    /// Consumes the next token if it's one of `operators`, also accepting the keywords
+    /// `and`, `or` and `not` as operators.
+    fn operator(
+        &mut self,
+        operators: &[&'static str],
+    ) -> Option<&'static str> {
+        let found = match self.peek() {
+            Token::Operator(operator) => operators.iter().find(|&o| o == operator).copied(),
+            Token::Ident(word) => operators.iter().find(|&o| o == word).copied(),
+            _ => None,
+        };
+        if found.is_some() {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect(
+        &mut self,
+        operator: &'static str,
+    ) -> Result<(), FormulaError> {
+        match self.operator(&[operator]) {
+            Some(_) => Ok(()),
+            None => Err(self.unexpected()),
+        }
+    }
+
+    fn conditional(&mut self) -> Result<Expr, FormulaError> {
+        let condition = self.or()?;
+        if self.operator(&["?"]).is_none() {
+            return Ok(condition);
+        }
+        let then = self.conditional()?;
+        self.expect(":")?;
+        let otherwise = self.conditional()?;
+        Ok(call("if", vec![condition, then, otherwise]))
+    }
+
+    fn or(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.and()?;
+        while self.operator(&["||", "or"]).is_some() {
+            left = call("or", vec![left, self.and()?]);
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.not()?;
+        while self.operator(&["&&", "and"]).is_some() {
+            left = call("and", vec![left, self.not()?]);
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, FormulaError> {
+        if self.operator(&["!", "not"]).is_some() {
+            return Ok(call("not", vec![self.not()?]));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.additive()?;
+        while let Some(operator) = self.operator(&["==", "!=", ">=", "<=", ">", "<"]) {
+            let function = match operator {
+                "==" => "equal",
+                "!=" => "unequal",
+                ">=" => "largerEq",
+                "<=" => "smallerEq",
+                ">" => "larger",
+                _ => "smaller",
+            };
+            left = call(function, vec![left, self.additive()?]);
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.multiplicative()?;
+        while let Some(operator) = self.operator(&["+", "-"]) {
+            let function = if operator == "+" { "add" } else { "subtract" };
+            left = call(function, vec![left, self.multiplicative()?]);
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.unary()?;
+        while let Some(operator) = self.operator(&["*", "/", "%"]) {
+            let function = match operator {
+                "*" => "multiply",
+                "/" => "divide",
+                _ => "mod",
+            };
+            left = call(function, vec![left, self.unary()?]);
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, FormulaError> {
+        match self.operator(&["-", "+"]) {
+            Some("-") => Ok(call("unaryMinus", vec![self.unary()?])),
+            Some(_) => Ok(call("unaryPlus", vec![self.unary()?])),
+            None => self.power(),
+        }
+    }
+
+    /// `^` binds tighter than unary minus and is right associative, `-2^2` is `-4`.
+    fn power(&mut self) -> Result<Expr, FormulaError> {
+        let base = self.postfix()?;
+        if self.operator(&["^"]).is_some() {
+            return Ok(call("pow", vec![base, self.unary()?]));
+        }
+        Ok(base)
+    }
+
+    /// Method calls like `prop("Name").length()`, the same as `length(prop("Name"))`.
+    fn postfix(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.primary()?;
+        while self.operator(&["."]).is_some() {
+            let name = match self.advance() {
+                Token::Ident(name) => name,
+                _ => {
+                    self.next -= 1;
+                    return Err(self.unexpected());
+                }
+            };
+            self.expect("(")?;
+            let mut args = vec![expr];
+            args.extend(self.arguments(")")?);
+            expr = Expr::Call(name, args);
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, FormulaError> {
+        match self.advance() {
+            Token::Number(number) => Ok(Expr::Number(number)),
+            Token::Text(text) => Ok(Expr::Text(text)),
+            Token::Property(id) => Ok(Expr::Property(PropertyRef::Id(id))),
+            Token::Operator("(") => {
+                let expr = self.conditional()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Operator("[") => Ok(Expr::List(self.arguments("]")?)),
+            Token::Ident(name) => {
+                if self.operator(&["("]).is_some() {
+                    let args = self.arguments(")")?;
+                    return Ok(match (name.as_str(), args.as_slice()) {
+                        ("prop", [Expr::Text(property)]) => {
+                            Expr::Property(PropertyRef::Name(property.clone()))
+                        }
+                        _ => Expr::Call(name, args),
+                    });
+                }
+                match name.as_str() {
+                    "true" => Ok(Expr::Boolean(true)),
+                    "false" => Ok(Expr::Boolean(false)),
+                    "pi" => Ok(Expr::Number(std::f64::consts::PI)),
+                    "e" => Ok(Expr::Number(std::f64::consts::E)),
+                    _ => {
+                        self.next -= 1;
+                        Err(self.unexpected())
+                    }
+                }
+            }
+            _ => {
+                self.next -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+
+    /// Comma separated expressions up to the closing `end`, which is consumed.
+    fn arguments(
+        &mut self,
+        end: &'static str,
+    ) -> Result<Vec<Expr>, FormulaError> {
+        let mut args = vec![];
+        if self.operator(&[end]).is_some() {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.conditional()?);
+            if self.operator(&[end]).is_some() {
+                return Ok(args);
+            }
+            self.expect(",")?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Expr, PropertyRef};
+    use crate::formula::FormulaError;
+
+    fn call(
+        function: &str,
+        args: Vec<Expr>,
+    ) -> Expr {
+        Expr::Call(function.to_string(), args)
+    }
+
+    #[test]
+    fn operator_precedence() {
+        assert_eq!(
+            parse("1 + 2 * -3 ^ 2 > 4 and not true").unwrap(),
+            call(
+                "and",
+                vec![
+                    call(
+                        "larger",
+                        vec![
+                            call(
+                                "add",
+                                vec![
+                                    Expr::Number(1.0),
+                                    call(
+                                        "multiply",
+                                        vec![
+                                            Expr::Number(2.0),
+                                            call(
+                                                "unaryMinus",
+                                                vec![call(
+                                                    "pow",
+                                                    vec![Expr::Number(3.0), Expr::Number(2.0)]
+                                                )]
+                                            ),
+                                        ]
+                                    ),
+                                ]
+                            ),
+                            Expr::Number(4.0),
+                        ]
+                    ),
+                    call("not", vec![Expr::Boolean(true)]),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn properties_methods_and_conditionals() {
+        assert_eq!(
+            parse(r#"prop("Name").length() > 3 ? "long" : {{notion:block_property:a%3Bb:00000000-0000-0000-0000-000000000000:x}}"#)
+                .unwrap(),
+            call(
+                "if",
+                vec![
+                    call(
+                        "larger",
+                        vec![
+                            call(
+                                "length",
+                                vec![Expr::Property(PropertyRef::Name("Name".to_string()))]
+                            ),
+                            Expr::Number(3.0),
+                        ]
+                    ),
+                    Expr::Text("long".to_string()),
+                    Expr::Property(PropertyRef::Id("a%3Bb".to_string())),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        assert_eq!(
+            parse("1 +"),
+            Err(FormulaError::Syntax {
+                position: 3,
+                message: "unexpected end of formula".to_string(),
+            })
+        );
+        assert!(matches!(
+            parse(r#"concat("a", "b"#),
+            Err(FormulaError::Syntax { position: 12, .. })
+        ));
+    }
+}
@@ -0,0 +1,640 @@
+//! Rendering of Notion blocks and rich text as HTML.
+//!
+//! All text is escaped and only `http`, `https` and `mailto` links are kept, so the output can
+//! be embedded into other pages. Colors become CSS classes like `notion-red` or
+//! `notion-blue_background`, other elements carry `notion-…` classes for styling as well.
+//!
+//! [render_blocks()] uses the [DefaultRenderer]. To change how some blocks are rendered,
+//! implement [HtmlRenderer::render_block()] and fall back to [default_block()]:
+//!
+//! ```
+//! use rusticnotion::html::{default_block, HtmlRenderer};
+//! use rusticnotion::models::block::Block;
+//!
+//! struct NoDividers;
+//!
+//! impl HtmlRenderer for NoDividers {
+//!     fn render_block(&self, block: &Block) -> String {
+//!         match block {
+//!             Block::Divider { .. } => String::new(),
+//!             block => default_block(self, block),
+//!         }
+//!     }
+//! }
+//!
+//! let html = NoDividers.render_blocks(&[]);
+//! ```
+
+use crate::ids::notion_url;
+use crate::models::block::{
+    Block, FileObject, FileOrEmojiObject, LinkToPageFields, TableFields, TextAndChildren,
+};
+use crate::models::text::{RichText, TextColor};
+
+/// Renders blocks and rich text as HTML, every method can be overridden.
+pub trait HtmlRenderer {
+    /// Renders a list of sibling blocks, wrapping consecutive list items in `<ul>` or `<ol>`.
+    fn render_blocks(
+        &self,
+        blocks: &[Block],
+    ) -> String {
+        let mut html = String::new();
+        let mut open_list: Option<&str> = None;
+        for block in blocks {
+            let list = match block {
+                Block::BulletedListItem { .. } => Some("ul"),
+                Block::NumberedListItem { .. } => Some("ol"),
+                Block::ToDo { .. } => Some("ul class=\"notion-to-do\""),
+                _ => None,
+            };
+            if open_list != list {
+                if let Some(open) = open_list {
+                    html.push_str(&format!("</{}>", tag_name(open)));
+                }
+                if let Some(list) = list {
+                    html.push_str(&format!("<{}>", list));
+                }
+                open_list = list;
+            }
+            html.push_str(&self.render_block(block));
+        }
+        if let Some(open) = open_list {
+            html.push_str(&format!("</{}>", tag_name(open)));
+        }
+        html
+    }
+
+    /// Renders a single block with its children, see [default_block()].
+    fn render_block(
+        &self,
+        block: &Block,
+    ) -> String {
+        default_block(self, block)
+    }
+
+    /// Renders rich text with its annotations, links and mentions.
+    fn render_text(
+        &self,
+        text: &[RichText],
+    ) -> String {
+        text.iter().map(render_run).collect()
+    }
+}
+
+/// Renders blocks like Notion structures them, without any overrides.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DefaultRenderer;
+
+impl HtmlRenderer for DefaultRenderer {}
+
+/// Renders blocks with the [DefaultRenderer].
+pub fn render_blocks(blocks: &[Block]) -> String {
+    DefaultRenderer.render_blocks(blocks)
+}
+
+/// Renders rich text with the [DefaultRenderer].
+pub fn render_rich_text(text: &[RichText]) -> String {
+    DefaultRenderer.render_text(text)
+}
+
+/// The default rendering of a block, children are rendered with `renderer`.
+pub fn default_block<R: HtmlRenderer + ?Sized>(
+    renderer: &R,
+    block: &Block,
+) -> String {
+    let children = |children: Option<&[Block]>| match children {
+        Some(children) if !children.is_empty() => renderer.render_blocks(children),
+        _ => String::new(),
+    };
+    let text_block = |tag: &str, fields: &TextAndChildren| {
+        format!(
+            "<{tag}{}>{}{}</{tag}>",
+            class_attribute("", fields.color),
+            renderer.render_text(&fields.rich_text),
+            children(fields.children.as_deref()),
+            tag = tag
+        )
+    };
+
+    match block {
+        Block::Paragraph { paragraph, .. } => match paragraph.children.as_deref() {
+            Some(nested) if !nested.is_empty() => format!(
+                "<p{}>{}</p><div class=\"notion-indent\">{}</div>",
+                class_attribute("", paragraph.color),
+                renderer.render_text(&paragraph.rich_text),
+                renderer.render_blocks(nested)
+            ),
+            _ => text_block("p", paragraph),
+        },
+        Block::Heading1 { heading_1, .. } => {
+            format!("<h1>{}</h1>", renderer.render_text(&heading_1.rich_text))
+        }
+        Block::Heading2 { heading_2, .. } => {
+            format!("<h2>{}</h2>", renderer.render_text(&heading_2.rich_text))
+        }
+        Block::Heading3 { heading_3, .. } => {
+            format!("<h3>{}</h3>", renderer.render_text(&heading_3.rich_text))
+        }
+        Block::Callout { callout, .. } => {
+            let icon = match &callout.icon {
+                FileOrEmojiObject::Emoji { emoji } => escape(emoji),
+                FileOrEmojiObject::CustomEmoji { custom_emoji } => {
+                    image(&custom_emoji.url, &custom_emoji.name)
+                }
+                FileOrEmojiObject::File { file } => image(&file.url, ""),
+                FileOrEmojiObject::External { external } => image(&external.url, ""),
+                FileOrEmojiObject::FileUpload { .. } => String::new(),
+            };
+            format!(
+                "<aside{}><span class=\"notion-callout-icon\">{}</span><div>{}</div></aside>",
+                class_attribute("notion-callout", callout.color),
+                icon,
+                renderer.render_text(&callout.rich_text)
+            )
+        }
+        Block::Quote { quote, .. } => text_block("blockquote", quote),
+        Block::BulletedListItem {
+            bulleted_list_item, ..
+        } => text_block("li", bulleted_list_item),
+        Block::NumberedListItem {
+            numbered_list_item, ..
+        } => text_block("li", numbered_list_item),
+        Block::ToDo { to_do, .. } => format!(
+            "<li{}><input type=\"checkbox\" disabled{}> {}{}</li>",
+            class_attribute("", to_do.color),
+            if to_do.checked { " checked" } else { "" },
+            renderer.render_text(&to_do.rich_text),
+            children(to_do.children.as_deref())
+        ),
+        Block::Toggle { toggle, .. } => format!(
+            "<details{}><summary>{}</summary>{}</details>",
+            class_attribute("", toggle.color),
+            renderer.render_text(&toggle.rich_text),
+            children(toggle.children.as_deref())
+        ),
+        Block::Code { code, .. } => {
+            let content: String = code.rich_text.iter().map(RichText::plain_text).collect();
+            let language = serde_json::to_value(&code.language)
+                .ok()
+                .and_then(|name| name.as_str().map(|name| name.replace(' ', "-")))
+                .unwrap_or_default();
+            format!(
+                "<pre><code class=\"language-{}\">{}</code></pre>",
+                escape(&language),
+                escape(&content)
+            )
+        }
+        Block::ChildPage { common, child_page } => format!(
+            "<p class=\"notion-child-page\">{}</p>",
+            link(&notion_url(&common.id), &escape(&child_page.title))
+        ),
+        Block::ChildDatabase {
+            common,
+            child_database,
+        } => format!(
+            "<p class=\"notion-child-database\">{}</p>",
+            link(&notion_url(&common.id), &escape(&child_database.title))
+        ),
+        Block::Embed { embed, .. } => embed_frame("notion-embed", &embed.url),
+        Block::Image { image: file, .. } => match file.url() {
+            Some(url) if is_safe_url(url) => {
+                format!("<figure class=\"notion-image\">{}</figure>", image(url, ""))
+            }
+            _ => comment("image"),
+        },
+        Block::Video { video, .. } => match video.url() {
+            Some(url) if is_safe_url(url) => format!(
+                "<video class=\"notion-video\" controls src=\"{}\"></video>",
+                escape(url)
+            ),
+            _ => comment("video"),
+        },
+        Block::Pdf { pdf, .. } => match pdf.url() {
+            Some(url) => embed_frame("notion-pdf", url),
+            None => comment("pdf"),
+        },
+        Block::File { file, caption, .. } => {
+            let caption = renderer.render_text(&caption.rich_text);
+            file_link(file, if caption.is_empty() { "file" } else { &caption })
+        }
+        Block::Bookmark { bookmark, .. } => {
+            let caption = renderer.render_text(&bookmark.caption);
+            format!(
+                "<p class=\"notion-bookmark\">{}</p>",
+                link(
+                    &bookmark.url,
+                    &if caption.is_empty() {
+                        escape(&bookmark.url)
+                    } else {
+                        caption
+                    }
+                )
+            )
+        }
+        Block::Equation { equation, .. } => format!(
+            "<div class=\"notion-equation\">{}</div>",
+            escape(&equation.expression)
+        ),
+        Block::Divider { .. } => "<hr>".to_string(),
+        Block::TableOfContents { .. } => comment("table_of_contents"),
+        Block::Breadcrumb { .. } => comment("breadcrumb"),
+        Block::ColumnList { column_list, .. } => format!(
+            "<div class=\"notion-columns\">{}</div>",
+            renderer.render_blocks(&column_list.children)
+        ),
+        Block::Column { column, .. } => format!(
+            "<div class=\"notion-column\">{}</div>",
+            renderer.render_blocks(&column.children)
+        ),
+        Block::LinkPreview { link_preview, .. } => format!(
+            "<p class=\"notion-link-preview\">{}</p>",
+            link(&link_preview.url, &escape(&link_preview.url))
+        ),
+        Block::Template { template, .. } => format!(
+            "<div class=\"notion-template\"><p>{}</p>{}</div>",
+            renderer.render_text(&template.rich_text),
+            children(Some(&template.children))
+        ),
+        Block::LinkToPage { link_to_page, .. } => {
+            let url = match link_to_page {
+                LinkToPageFields::PageId { page_id } => notion_url(page_id),
+                LinkToPageFields::DatabaseId { database_id } => notion_url(database_id),
+            };
+            format!(
+                "<p class=\"notion-link-to-page\">{}</p>",
+                link(&url, &escape(&url))
+            )
+        }
+        Block::Table { table, .. } => render_table(renderer, table),
+        Block::SyncedBlock { synced_block, .. } => children(Some(&synced_block.children)),
+        Block::TableRow { table_row, .. } => format!(
+            "<tr>{}</tr>",
+            table_row
+                .cells
+                .iter()
+                .map(|cell| format!("<td>{}</td>", renderer.render_text(cell)))
+                .collect::<String>()
+        ),
+        Block::Unsupported { common } => comment(&format!("unsupported block {}", common.id)),
+        Block::Unknown => comment("unknown block"),
+    }
+}
+
+fn render_table<R: HtmlRenderer + ?Sized>(
+    renderer: &R,
+    table: &TableFields,
+) -> String {
+    let rows: Vec<String> = table
+        .children
+        .iter()
+        .filter_map(|row| match row {
+            Block::TableRow { table_row, .. } => Some(&table_row.cells),
+            _ => None,
+        })
+        .enumerate()
+        .map(|(row, cells)| {
+            let cells: String = cells
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    let text = renderer.render_text(cell);
+                    if row == 0 && table.has_column_header {
+                        format!("<th scope=\"col\">{}</th>", text)
+                    } else if column == 0 && table.has_row_header {
+                        format!("<th scope=\"row\">{}</th>", text)
+                    } else {
+                        format!("<td>{}</td>", text)
+                    }
+                })
+                .collect();
+            format!("<tr>{}</tr>", cells)
+        })
+        .collect();
+
+    let (head, body) = if table.has_column_header && !rows.is_empty() {
+        (format!("<thead>{}</thead>", rows[0]), &rows[1..])
+    } else {
+        (String::new(), &rows[..])
+    };
+    format!(
+        "<table class=\"notion-table\">{}<tbody>{}</tbody></table>",
+        head,
+        body.concat()
+    )
+}
+
+fn render_run(run: &RichText) -> String {
+    let (common, mut html) = match run {
+        RichText::Text { rich_text, text } => (rich_text, escape(&text.content)),
+        RichText::Mention { rich_text, .. } => (
+            rich_text,
+            format!(
+                "<span class=\"notion-mention\">{}</span>",
+                escape(&rich_text.plain_text)
+            ),
+        ),
+        RichText::Equation {
+            rich_text,
+            equation,
+        } => (
+            rich_text,
+            format!(
+                "<span class=\"notion-equation\">{}</span>",
+                escape(&equation.expression)
+            ),
+        ),
+    };
+    html = html.replace('\n', "<br>");
+
+    if let Some(annotations) = &common.annotations {
+        let is_set = |flag: Option<bool>| flag == Some(true);
+        for (flag, tag) in [
+            (annotations.code, "code"),
+            (annotations.strikethrough, "s"),
+            (annotations.underline, "u"),
+            (annotations.italic, "em"),
+            (annotations.bold, "strong"),
+        ] {
+            if is_set(flag) {
+                html = format!("<{tag}>{}</{tag}>", html, tag = tag);
+            }
+        }
+        if let Some(color) = annotations.color {
+            if color != TextColor::Default {
+                html = format!("<span{}>{}</span>", class_attribute("", color), html);
+            }
+        }
+    }
+    match &common.href {
+        Some(url) => link(url, &html),
+        None => html,
+    }
+}
+
+fn tag_name(list: &str) -> &str {
+    list.split(' ').next().unwrap_or(list)
+}
+
+fn class_attribute(
+    class: &str,
+    color: TextColor,
+) -> String {
+    let color = match color {
+        TextColor::Default => String::new(),
+        color => format!(
+            "notion-{}",
+            serde_json::to_value(color)
+                .ok()
+                .and_then(|name| name.as_str().map(str::to_string))
+                .unwrap_or_default()
+        ),
+    };
+    let classes: Vec<&str> = [class, color.as_str()]
+        .iter()
+        .copied()
+        .filter(|class| !class.is_empty())
+        .collect();
+    if classes.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"{}\"", classes.join(" "))
+    }
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim_start().to_lowercase();
+    url.starts_with("https://") || url.starts_with("http://") || url.starts_with("mailto:")
+}
+
+/// A link to `url` around `html`, just `html` if the URL could run scripts.
+fn link(
+    url: &str,
+    html: &str,
+) -> String {
+    if is_safe_url(url) {
+        format!("<a href=\"{}\">{}</a>", escape(url), html)
+    } else {
+        html.to_string()
+    }
+}
+
+fn image(
+    url: &str,
+    alt: &str,
+) -> String {
+    if is_safe_url(url) {
+        format!("<img src=\"{}\" alt=\"{}\">", escape(url), escape(alt))
+    } else {
+        String::new()
+    }
+}
+
+/// Embedded content runs sandboxed: it can run scripts and open popups, but can't act as the
+/// embedding page, navigate it or learn its URL.
+fn embed_frame(
+    class: &str,
+    url: &str,
+) -> String {
+    if is_safe_url(url) {
+        format!(
+            "<iframe class=\"{}\" src=\"{}\" sandbox=\"allow-scripts allow-popups\" \
+             referrerpolicy=\"no-referrer\" loading=\"lazy\"></iframe>",
+            class,
+            escape(url)
+        )
+    } else {
+        comment(class)
+    }
+}
+
+fn file_link(
+    file: &FileObject,
+    html: &str,
+) -> String {
+    match file.url() {
+        Some(url) => format!("<p class=\"notion-file\">{}</p>", link(url, html)),
+        None => comment("file"),
+    }
+}
+
+fn comment(text: &str) -> String {
+    format!("<!-- {} -->", text.replace("--", ""))
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn block(
+        kind: &str,
+        fields: Value,
+    ) -> Block {
+        let mut block = json!({
+            "object": "block",
+            "id": "b8b944b5-cc3d-444e-a25c-a7ddacd528cb",
+            "created_time": "2022-05-13T20:08:00.000Z",
+            "last_edited_time": "2022-05-13T20:08:00.000Z",
+            "created_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+            "last_edited_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+            "has_children": false,
+            "type": kind,
+        });
+        block[kind] = fields;
+        serde_json::from_value(block).unwrap()
+    }
+
+    fn text(content: &str) -> Value {
+        json!([{ "type": "text", "text": { "content": content, "link": null }, "plain_text": content }])
+    }
+
+    fn item(
+        kind: &str,
+        content: &str,
+    ) -> Block {
+        block(
+            kind,
+            json!({ "rich_text": text(content), "color": "default" }),
+        )
+    }
+
+    #[test]
+    fn escapes_text_and_drops_unsafe_links() {
+        let text: Vec<RichText> = serde_json::from_value(json!([
+            { "type": "text", "text": { "content": "<b>", "link": null }, "plain_text": "<b>",
+              "annotations": { "bold": true, "color": "red" } },
+            { "type": "text", "text": { "content": "site", "link": { "url": "https://example.com?a=1&b=2" } },
+              "plain_text": "site", "href": "https://example.com?a=1&b=2" },
+            { "type": "text", "text": { "content": "script", "link": { "url": "javascript:alert(1)" } },
+              "plain_text": "script", "href": "javascript:alert(1)" }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            render_rich_text(&text),
+            "<span class=\"notion-red\"><strong>&lt;b&gt;</strong></span>\
+             <a href=\"https://example.com?a=1&amp;b=2\">site</a>script"
+        );
+    }
+
+    #[test]
+    fn groups_list_items() {
+        let blocks = vec![
+            item("bulleted_list_item", "a"),
+            item("bulleted_list_item", "b"),
+            item("numbered_list_item", "c"),
+            item("paragraph", "d"),
+        ];
+
+        assert_eq!(
+            render_blocks(&blocks),
+            "<ul><li>a</li><li>b</li></ul><ol><li>c</li></ol><p>d</p>"
+        );
+    }
+
+    #[test]
+    fn renders_tables_with_headers() {
+        let row = |cells: Vec<&str>| {
+            block(
+                "table_row",
+                json!({ "cells": cells.into_iter().map(text).collect::<Vec<_>>() }),
+            )
+        };
+        let table = block(
+            "table",
+            json!({
+                "table_width": 2,
+                "has_column_header": true,
+                "has_row_header": true,
+                "children": [row(vec!["", "b"]), row(vec!["1", "2"])]
+            }),
+        );
+
+        assert_eq!(
+            render_blocks(&[table]),
+            "<table class=\"notion-table\"><thead><tr><th scope=\"col\"></th><th scope=\"col\">b</th></tr></thead>\
+             <tbody><tr><th scope=\"row\">1</th><td>2</td></tr></tbody></table>"
+        );
+    }
+
+    #[test]
+    fn renders_callouts_and_toggles() {
+        let blocks = vec![
+            block(
+                "callout",
+                json!({ "rich_text": text("Note"), "icon": { "type": "emoji", "emoji": "💡" }, "color": "blue_background" }),
+            ),
+            block(
+                "toggle",
+                json!({ "rich_text": text("More"), "color": "default", "children": [item("paragraph", "hidden")] }),
+            ),
+        ];
+
+        assert_eq!(
+            render_blocks(&blocks),
+            "<aside class=\"notion-callout notion-blue_background\"><span class=\"notion-callout-icon\">💡</span><div>Note</div></aside>\
+             <details><summary>More</summary><p>hidden</p></details>"
+        );
+    }
+
+    #[test]
+    fn sandboxes_embeds() {
+        let blocks = vec![
+            block(
+                "embed",
+                json!({ "url": "https://example.com/widget?a=1&b=2" }),
+            ),
+            block("embed", json!({ "url": "javascript:alert(1)" })),
+        ];
+
+        assert_eq!(
+            render_blocks(&blocks),
+            "<iframe class=\"notion-embed\" src=\"https://example.com/widget?a=1&amp;b=2\" \
+             sandbox=\"allow-scripts allow-popups\" referrerpolicy=\"no-referrer\" \
+             loading=\"lazy\"></iframe><!-- notion-embed -->"
+        );
+    }
+
+    #[test]
+    fn uses_overrides() {
+        struct Headings;
+
+        impl HtmlRenderer for Headings {
+            fn render_block(
+                &self,
+                block: &Block,
+            ) -> String {
+                match block {
+                    Block::Paragraph { paragraph, .. } => {
+                        format!("<h4>{}</h4>", self.render_text(&paragraph.rich_text))
+                    }
+                    block => default_block(self, block),
+                }
+            }
+        }
+
+        let quote = block(
+            "quote",
+            json!({ "rich_text": text("q"), "color": "default", "children": [item("paragraph", "p")] }),
+        );
+        assert_eq!(
+            Headings.render_blocks(&[quote]),
+            "<blockquote>q<h4>p</h4></blockquote>"
+        );
+    }
+}
@@ -28,6 +28,11 @@ where
     }
 }
 
+/// The address of a page, database or block in the Notion web app.
+pub(crate) fn notion_url<T: Identifier>(id: &T) -> String {
+    format!("https://www.notion.so/{}", id.value().replace('-', ""))
+}
+
 macro_rules! identifer {
     ($name:ident) => {
         #[derive(serde::Serialize, serde::Deserialize, Debug, Eq, PartialEq, Hash, Clone)]
@@ -64,9 +69,20 @@ identifer!(PageId);
 identifer!(BlockId);
 identifer!(UserId);
 identifer!(PropertyId);
+identifer!(FileUploadId);
+
+/// The id of blocks of an unknown type, which Notion doesn't send.
+pub(crate) static UNKNOWN_BLOCK_ID: BlockId = BlockId(String::new());
 
 impl From<PageId> for BlockId {
     fn from(page_id: PageId) -> Self {
         BlockId(page_id.0)
     }
 }
+
+/// Child page blocks share their id with the page they contain.
+impl From<BlockId> for PageId {
+    fn from(block_id: BlockId) -> Self {
+        PageId(block_id.0)
+    }
+}
@@ -1,21 +1,65 @@
-use crate::ids::{BlockId, DatabaseId};
+use crate::ids::{BlockId, DatabaseId, FileUploadId};
 use crate::models::error::ErrorResponse;
+use crate::models::file_uploads::{CreateFileUploadRequest, FileUpload, FileUploadPart};
 use crate::models::search::{DatabaseQuery, SearchRequest};
 use crate::models::{Database, ListResponse, Object, Page};
 use ids::{AsIdentifier, PageId};
-use models::block::Block;
+use models::block::{AppendBlockChildrenRequest, Block, CreateBlock};
+use models::limits::{self, DeferredChildren};
+use models::paging::Paging;
 use models::search::NotionSearch;
-use models::PageCreateRequest;
+use models::{PageCreateRequest, UpdatePageRequest};
 use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::multipart::{Form, Part};
 use reqwest::{header, Client, ClientBuilder, RequestBuilder};
 use tracing::Instrument;
 
+mod block_tree;
+pub mod duplicate;
+mod file_uploads;
+pub mod formula;
+pub mod html;
 pub mod ids;
+pub mod markdown;
 pub mod models;
+pub mod resumable;
+mod streams;
+pub mod sync;
 
 pub use chrono;
 
 const NOTION_API_VERSION: &str = "2022-02-22";
+/// The file upload endpoints are not available in [NOTION_API_VERSION], neither are pages and
+/// blocks referencing a file upload, see [json_body()].
+const FILE_UPLOAD_API_VERSION: &str = "2022-06-28";
+const NOTION_API_URL: &str = "https://api.notion.com/v1";
+
+/// Sets `body` as the JSON body of `request`. Bodies referencing a file upload are sent with
+/// [FILE_UPLOAD_API_VERSION], [NOTION_API_VERSION] doesn't know the `file_upload` file type.
+fn json_body<T: serde::Serialize>(
+    request: RequestBuilder,
+    body: &T,
+) -> RequestBuilder {
+    let body = serde_json::to_value(body).expect("request bodies serialize to JSON");
+    if references_file_upload(&body) {
+        request
+            .header("Notion-Version", FILE_UPLOAD_API_VERSION)
+            .json(&body)
+    } else {
+        request.json(&body)
+    }
+}
+
+fn references_file_upload(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Array(elements) => elements.iter().any(references_file_upload),
+        serde_json::Value::Object(fields) => {
+            fields.get("type").and_then(|kind| kind.as_str()) == Some("file_upload")
+                || fields.values().any(references_file_upload)
+        }
+        _ => false,
+    }
+}
 
 /// An wrapper Error type for all errors produced by the [`NotionApi`](NotionApi) client.
 #[derive(Debug, thiserror::Error)]
@@ -39,10 +83,33 @@ pub enum Error {
     JsonParseError { source: serde_json::Error },
 
     #[error("Unexpected API Response")]
-    UnexpectedResponse { response: Object },
+    UnexpectedResponse { response: Box<Object> },
 
     #[error("API Error {}({}): {}", .error.code, .error.status, .error.message)]
     ApiError { error: ErrorResponse },
+
+    #[error("Request exceeds Notion's size limits: {}", .errors.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    LimitsExceeded { errors: Vec<limits::LimitError> },
+
+    #[error("Error reading file to upload: {}", source)]
+    FileReadError { source: std::io::Error },
+
+    /// The multi part upload `file_upload_id` was created but not completed, it expires if it
+    /// isn't completed later.
+    #[error("File upload {} was created but not completed: {}", .file_upload_id, .source)]
+    FileUploadIncomplete {
+        file_upload_id: FileUploadId,
+        source: Box<Error>,
+    },
+
+    #[error("Error reading or writing checkpoint: {}", source)]
+    CheckpointIoError { source: std::io::Error },
+
+    #[error("Checkpoint {} was created for a different query", .path.display())]
+    CheckpointMismatch { path: std::path::PathBuf },
+
+    #[error("Paging cursor of checkpoint {} expired and the query's sort can't be resumed", .path.display())]
+    CursorExpired { path: std::path::PathBuf },
 }
 
 /// An API client for Notion.
@@ -50,6 +117,7 @@ pub enum Error {
 #[derive(Clone)]
 pub struct NotionApi {
     client: Client,
+    base_url: String,
 }
 
 impl NotionApi {
@@ -72,7 +140,29 @@ impl NotionApi {
             .build()
             .map_err(|source| Error::ErrorBuildingClient { source })?;
 
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            base_url: NOTION_API_URL.to_string(),
+        })
+    }
+
+    /// Sends all requests to `base_url` instead of `https://api.notion.com/v1`.
+    /// Mostly useful for pointing the client at a proxy or a mock server.
+    pub fn with_base_url<T: Into<String>>(
+        self,
+        base_url: T,
+    ) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            ..self
+        }
+    }
+
+    fn url(
+        &self,
+        path: &str,
+    ) -> String {
+        format!("{}/{}", self.base_url, path)
     }
 
     async fn make_json_request(
@@ -132,16 +222,14 @@ impl NotionApi {
         query: T,
     ) -> Result<ListResponse<Object>, Error> {
         let result = self
-            .make_json_request(
-                self.client
-                    .post("https://api.notion.com/v1/search")
-                    .json(&query.into()),
-            )
+            .make_json_request(self.client.post(self.url("search")).json(&query.into()))
             .await?;
 
         match result {
             Object::List { list } => Ok(list),
-            response => Err(Error::UnexpectedResponse { response }),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
         }
     }
 
@@ -151,15 +239,17 @@ impl NotionApi {
         database_id: T,
     ) -> Result<Database, Error> {
         let result = self
-            .make_json_request(self.client.get(format!(
-                "https://api.notion.com/v1/databases/{}",
-                database_id.as_id()
-            )))
+            .make_json_request(
+                self.client
+                    .get(self.url(&format!("databases/{}", database_id.as_id()))),
+            )
             .await?;
 
         match result {
             Object::Database { database } => Ok(database),
-            response => Err(Error::UnexpectedResponse { response }),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
         }
     }
 
@@ -169,15 +259,17 @@ impl NotionApi {
         page_id: T,
     ) -> Result<Page, Error> {
         let result = self
-            .make_json_request(self.client.get(format!(
-                "https://api.notion.com/v1/pages/{}",
-                page_id.as_id()
-            )))
+            .make_json_request(
+                self.client
+                    .get(self.url(&format!("pages/{}", page_id.as_id()))),
+            )
             .await?;
 
         match result {
             Object::Page { page } => Ok(page),
-            response => Err(Error::UnexpectedResponse { response }),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
         }
     }
 
@@ -187,16 +279,41 @@ impl NotionApi {
         page: T,
     ) -> Result<Page, Error> {
         let result = self
-            .make_json_request(
+            .make_json_request(json_body(self.client.post(self.url("pages")), &page.into()))
+            .await?;
+
+        match result {
+            Object::Page { page } => Ok(page),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Updates the properties, icon, cover or archived status of a page
+    /// and returns the updated page.
+    pub async fn update_page<P, T>(
+        &self,
+        page_id: P,
+        update: T,
+    ) -> Result<Page, Error>
+    where
+        P: AsIdentifier<PageId>,
+        T: Into<UpdatePageRequest>,
+    {
+        let result = self
+            .make_json_request(json_body(
                 self.client
-                    .post("https://api.notion.com/v1/pages")
-                    .json(&page.into()),
-            )
+                    .patch(self.url(&format!("pages/{}", page_id.as_id()))),
+                &update.into(),
+            ))
             .await?;
 
         match result {
             Object::Page { page } => Ok(page),
-            response => Err(Error::UnexpectedResponse { response }),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
         }
     }
 
@@ -213,33 +330,335 @@ impl NotionApi {
         let result = self
             .make_json_request(
                 self.client
-                    .post(&format!(
-                        "https://api.notion.com/v1/databases/{database_id}/query",
+                    .post(self.url(&format!(
+                        "databases/{database_id}/query",
                         database_id = database.as_id()
-                    ))
+                    )))
                     .json(&query.into()),
             )
             .await?;
         match result {
             Object::List { list } => Ok(list.expect_pages()?),
-            response => Err(Error::UnexpectedResponse { response }),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
         }
     }
 
+    /// Get the first page of children of a block.
+    /// Use [get_block_children_with_paging()](Self::get_block_children_with_paging())
+    /// to continue from `next_cursor`, or [get_all_block_children()](Self::get_all_block_children())
+    /// to fetch every child.
     pub async fn get_block_children<T: AsIdentifier<BlockId>>(
         &self,
         block_id: T,
+    ) -> Result<ListResponse<Block>, Error> {
+        self.get_block_children_with_paging(block_id, Paging::default())
+            .await
+    }
+
+    /// Get a single page of children of a block, starting at `paging.start_cursor`.
+    pub async fn get_block_children_with_paging<T: AsIdentifier<BlockId>>(
+        &self,
+        block_id: T,
+        paging: Paging,
     ) -> Result<ListResponse<Block>, Error> {
         let result = self
-            .make_json_request(self.client.get(&format!(
-                "https://api.notion.com/v1/blocks/{block_id}/children",
-                block_id = block_id.as_id()
-            )))
+            .make_json_request(
+                self.client
+                    .get(self.url(&format!(
+                        "blocks/{block_id}/children",
+                        block_id = block_id.as_id()
+                    )))
+                    .query(&paging),
+            )
             .await?;
 
         match result {
             Object::List { list } => Ok(list.expect_blocks()?),
-            response => Err(Error::UnexpectedResponse { response }),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Get all children of a block by following `next_cursor` until `has_more` is false.
+    /// Only the direct children are returned, nested children are not fetched.
+    pub async fn get_all_block_children<T: AsIdentifier<BlockId>>(
+        &self,
+        block_id: T,
+    ) -> Result<Vec<Block>, Error> {
+        let block_id = block_id.as_id();
+        let mut children = vec![];
+        let mut paging = Paging::default();
+
+        loop {
+            let response = self
+                .get_block_children_with_paging(block_id, paging.clone())
+                .await?;
+            children.extend(response.results);
+
+            match response.next_cursor {
+                Some(cursor) if response.has_more => paging.start_cursor = Some(cursor),
+                _ => break,
+            }
+        }
+
+        Ok(children)
+    }
+
+    /// Appends `children` to the end of a block or page and returns the created blocks.
+    /// Notion accepts at most 100 blocks per request.
+    pub async fn append_block_children<T: AsIdentifier<BlockId>>(
+        &self,
+        block_id: T,
+        children: Vec<CreateBlock>,
+    ) -> Result<ListResponse<Block>, Error> {
+        self.send_block_children(
+            block_id.as_id(),
+            AppendBlockChildrenRequest {
+                children,
+                after: None,
+            },
+        )
+        .await
+    }
+
+    /// Inserts `children` into a block or page right after its child `after`
+    /// and returns the created blocks.
+    /// Notion accepts at most 100 blocks per request.
+    pub async fn append_block_children_after<T, A>(
+        &self,
+        block_id: T,
+        after: A,
+        children: Vec<CreateBlock>,
+    ) -> Result<ListResponse<Block>, Error>
+    where
+        T: AsIdentifier<BlockId>,
+        A: AsIdentifier<BlockId>,
+    {
+        self.send_block_children(
+            block_id.as_id(),
+            AppendBlockChildrenRequest {
+                children,
+                after: Some(after.as_id().clone()),
+            },
+        )
+        .await
+    }
+
+    async fn send_block_children(
+        &self,
+        block_id: &BlockId,
+        request: AppendBlockChildrenRequest,
+    ) -> Result<ListResponse<Block>, Error> {
+        let result = self
+            .make_json_request(json_body(
+                self.client
+                    .patch(self.url(&format!("blocks/{block_id}/children", block_id = block_id))),
+                &request,
+            ))
+            .await?;
+
+        match result {
+            Object::List { list } => Ok(list.expect_blocks()?),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Replaces the content of a block with `block`, which has to be of the same type.
+    /// Nested children of `block` are ignored, the children of the block are kept.
+    pub async fn update_block<T: AsIdentifier<BlockId>>(
+        &self,
+        block_id: T,
+        block: CreateBlock,
+    ) -> Result<Block, Error> {
+        let result = self
+            .make_json_request(json_body(
+                self.client
+                    .patch(self.url(&format!("blocks/{block_id}", block_id = block_id.as_id()))),
+                &block.into_update(),
+            ))
+            .await?;
+
+        match result {
+            Object::Block { block } => Ok(block),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Deletes a block and its children, Notion keeps it in the trash.
+    pub async fn delete_block<T: AsIdentifier<BlockId>>(
+        &self,
+        block_id: T,
+    ) -> Result<Block, Error> {
+        let result = self
+            .make_json_request(
+                self.client
+                    .delete(self.url(&format!("blocks/{block_id}", block_id = block_id.as_id()))),
+            )
+            .await?;
+
+        match result {
+            Object::Block { block } => Ok(block),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Appends any number of `children` to the end of a block or page and returns all created
+    /// blocks.
+    ///
+    /// Long text is split and the blocks are sent in as many requests as Notion's
+    /// [limits](models::limits) require, nested children that don't fit are appended to their
+    /// parents afterwards.
+    pub async fn append_all_block_children<T: AsIdentifier<BlockId>>(
+        &self,
+        block_id: T,
+        mut children: Vec<CreateBlock>,
+    ) -> Result<Vec<Block>, Error> {
+        let block_id = block_id.as_id();
+        limits::fit_blocks(&mut children);
+        let mut created = vec![];
+        for batch in limits::split_blocks(children) {
+            limits::check_arrays(&batch.blocks)
+                .map_err(|errors| Error::LimitsExceeded { errors })?;
+            let results = self
+                .append_block_children(block_id, batch.blocks)
+                .await?
+                .results;
+            self.append_deferred_children(&results, batch.deferred)
+                .await?;
+            created.extend(results);
+        }
+
+        Ok(created)
+    }
+
+    /// Appends the children [split_blocks()](limits::split_blocks()) cut off from a batch,
+    /// `created` are the blocks Notion created for the batch.
+    pub async fn append_deferred_children(
+        &self,
+        created: &[Block],
+        deferred: Vec<DeferredChildren>,
+    ) -> Result<(), Error> {
+        for DeferredChildren { path, children } in deferred {
+            let mut parent = created[path[0]].as_id().clone();
+            for index in &path[1..] {
+                let siblings = self.get_all_block_children(&parent).await?;
+                parent = siblings[*index].as_id().clone();
+            }
+            Box::pin(self.append_all_block_children(&parent, children)).await?;
+        }
+        Ok(())
+    }
+
+    /// Creates a new file upload.
+    /// The content is sent afterwards with [send_file_upload()](Self::send_file_upload()),
+    /// see [upload_file()](Self::upload_file()) for doing all steps at once.
+    pub async fn create_file_upload(
+        &self,
+        request: CreateFileUploadRequest,
+    ) -> Result<FileUpload, Error> {
+        let result = self
+            .make_json_request(
+                self.client
+                    .post(self.url("file_uploads"))
+                    .header("Notion-Version", FILE_UPLOAD_API_VERSION)
+                    .json(&request),
+            )
+            .await?;
+
+        match result {
+            Object::FileUpload { file_upload } => Ok(file_upload),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Sends the content of a single part upload, or one part of a multi part upload.
+    pub async fn send_file_upload<T: AsIdentifier<FileUploadId>>(
+        &self,
+        file_upload: T,
+        part: FileUploadPart,
+    ) -> Result<FileUpload, Error> {
+        let mut file = Part::bytes(part.data).file_name(part.filename);
+        if let Some(content_type) = part.content_type {
+            file = file.mime_str(&content_type)?;
+        }
+        let mut form = Form::new().part("file", file);
+        if let Some(part_number) = part.part_number {
+            form = form.text("part_number", part_number.to_string());
+        }
+
+        let result = self
+            .make_json_request(
+                self.client
+                    .post(self.url(&format!(
+                        "file_uploads/{file_upload_id}/send",
+                        file_upload_id = file_upload.as_id()
+                    )))
+                    .header("Notion-Version", FILE_UPLOAD_API_VERSION)
+                    .multipart(form),
+            )
+            .await?;
+
+        match result {
+            Object::FileUpload { file_upload } => Ok(file_upload),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Completes a multi part upload once all parts have been sent.
+    pub async fn complete_file_upload<T: AsIdentifier<FileUploadId>>(
+        &self,
+        file_upload: T,
+    ) -> Result<FileUpload, Error> {
+        let result = self
+            .make_json_request(
+                self.client
+                    .post(self.url(&format!(
+                        "file_uploads/{file_upload_id}/complete",
+                        file_upload_id = file_upload.as_id()
+                    )))
+                    .header("Notion-Version", FILE_UPLOAD_API_VERSION),
+            )
+            .await?;
+
+        match result {
+            Object::FileUpload { file_upload } => Ok(file_upload),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    /// Get a file upload by [FileUploadId].
+    pub async fn get_file_upload<T: AsIdentifier<FileUploadId>>(
+        &self,
+        file_upload: T,
+    ) -> Result<FileUpload, Error> {
+        let result = self
+            .make_json_request(
+                self.client
+                    .get(self.url(&format!("file_uploads/{}", file_upload.as_id())))
+                    .header("Notion-Version", FILE_UPLOAD_API_VERSION),
+            )
+            .await?;
+
+        match result {
+            Object::FileUpload { file_upload } => Ok(file_upload),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
         }
     }
 }
@@ -0,0 +1,12 @@
+//! Conversion between Markdown documents and Notion blocks.
+//!
+//! [from_blocks()] renders blocks as GitHub flavored Markdown, [to_blocks()] parses Markdown
+//! and requires the `markdown` feature.
+
+mod export;
+#[cfg(feature = "markdown")]
+mod import;
+
+pub use export::from_blocks;
+#[cfg(feature = "markdown")]
+pub use import::to_blocks;
@@ -0,0 +1,566 @@
+//! Rendering of Notion blocks as GitHub flavored Markdown.
+
+use crate::ids::notion_url;
+use crate::models::block::{Block, CodeLanguage, FileObject, FileOrEmojiObject, LinkToPageFields};
+use crate::models::text::{Annotations, RichText};
+
+/// Renders blocks and their nested children as GitHub flavored Markdown.
+///
+/// Only children that were fetched are rendered, see
+/// [NotionApi::fetch_block_tree()](crate::NotionApi::fetch_block_tree()).
+/// Mentions become links where Notion provides a URL for them, blocks without a Markdown
+/// equivalent become HTML comments.
+pub fn from_blocks(blocks: &[Block]) -> String {
+    let mut markdown = render_blocks(blocks);
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+    markdown
+}
+
+fn render_blocks(blocks: &[Block]) -> String {
+    let mut markdown = String::new();
+    let mut number = 0;
+    let mut previous_was_item = false;
+    for block in blocks {
+        let is_item = matches!(
+            block,
+            Block::BulletedListItem { .. } | Block::NumberedListItem { .. } | Block::ToDo { .. }
+        );
+        number = match block {
+            Block::NumberedListItem { .. } => number + 1,
+            _ => 0,
+        };
+        if !markdown.is_empty() {
+            // Items of a list are kept together, everything else is separated by a blank line.
+            markdown.push_str(if is_item && previous_was_item {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        markdown.push_str(&render_block(block, number));
+        previous_was_item = is_item;
+    }
+    markdown
+}
+
+fn render_block(
+    block: &Block,
+    number: usize,
+) -> String {
+    match block {
+        Block::Paragraph { paragraph, .. } => {
+            let text = render_text(&paragraph.rich_text);
+            match paragraph.children.as_deref() {
+                Some(children) if !children.is_empty() => {
+                    format!(
+                        "{}\n\n{}",
+                        text,
+                        indent(&render_blocks(children), "  ", "  ")
+                    )
+                }
+                _ => text,
+            }
+        }
+        Block::Heading1 { heading_1, .. } => format!("# {}", render_text(&heading_1.rich_text)),
+        Block::Heading2 { heading_2, .. } => format!("## {}", render_text(&heading_2.rich_text)),
+        Block::Heading3 { heading_3, .. } => {
+            format!("### {}", render_text(&heading_3.rich_text))
+        }
+        Block::Callout { callout, .. } => {
+            let text = render_text(&callout.rich_text);
+            match &callout.icon {
+                FileOrEmojiObject::Emoji { emoji } => quote(&format!("{} {}", emoji, text)),
+                _ => quote(&text),
+            }
+        }
+        Block::Quote { quote: fields, .. } => quote(&with_children(
+            render_text(&fields.rich_text),
+            fields.children.as_deref(),
+        )),
+        Block::BulletedListItem {
+            bulleted_list_item, ..
+        } => list_item(
+            "- ",
+            2,
+            &bulleted_list_item.rich_text,
+            bulleted_list_item.children.as_deref(),
+        ),
+        Block::NumberedListItem {
+            numbered_list_item, ..
+        } => {
+            let marker = format!("{}. ", number);
+            list_item(
+                &marker,
+                marker.len(),
+                &numbered_list_item.rich_text,
+                numbered_list_item.children.as_deref(),
+            )
+        }
+        Block::ToDo { to_do, .. } => list_item(
+            if to_do.checked { "- [x] " } else { "- [ ] " },
+            2,
+            &to_do.rich_text,
+            to_do.children.as_deref(),
+        ),
+        Block::Toggle { toggle, .. } => {
+            let summary = format!(
+                "<details>\n<summary>{}</summary>",
+                render_text(&toggle.rich_text)
+            );
+            match toggle.children.as_deref() {
+                Some(children) if !children.is_empty() => {
+                    format!("{}\n\n{}\n\n</details>", summary, render_blocks(children))
+                }
+                _ => format!("{}\n</details>", summary),
+            }
+        }
+        Block::Code { code, .. } => {
+            let content: String = code.rich_text.iter().map(RichText::plain_text).collect();
+            let fence = "`".repeat(longest_run(&content, '`').max(2) + 1);
+            format!(
+                "{}{}\n{}\n{}",
+                fence,
+                language_name(&code.language),
+                content,
+                fence
+            )
+        }
+        Block::ChildPage { common, child_page } => {
+            format!(
+                "[{}]({})",
+                escape(&child_page.title),
+                notion_url(&common.id)
+            )
+        }
+        Block::ChildDatabase {
+            common,
+            child_database,
+        } => format!(
+            "[{}]({})",
+            escape(&child_database.title),
+            notion_url(&common.id)
+        ),
+        Block::Embed { embed, .. } => format!("<{}>", embed.url),
+        Block::Image { image, .. } => match image.url() {
+            Some(url) => format!("![]({})", url),
+            None => comment("image"),
+        },
+        Block::Video { video, .. } => file_link("video", video),
+        Block::Pdf { pdf, .. } => file_link("pdf", pdf),
+        Block::File { file, caption, .. } => {
+            let caption = render_text(&caption.rich_text);
+            file_link(if caption.is_empty() { "file" } else { &caption }, file)
+        }
+        Block::Bookmark { bookmark, .. } => {
+            let caption = render_text(&bookmark.caption);
+            if caption.is_empty() {
+                format!("<{}>", bookmark.url)
+            } else {
+                format!("[{}]({})", caption, bookmark.url)
+            }
+        }
+        Block::Equation { equation, .. } => format!("$$\n{}\n$$", equation.expression),
+        Block::Divider { .. } => "---".to_string(),
+        Block::TableOfContents { .. } => comment("table_of_contents"),
+        Block::Breadcrumb { .. } => comment("breadcrumb"),
+        Block::ColumnList { column_list, .. } => render_blocks(&column_list.children),
+        Block::Column { column, .. } => render_blocks(&column.children),
+        Block::LinkPreview { link_preview, .. } => format!("<{}>", link_preview.url),
+        Block::Template { template, .. } => {
+            with_children(render_text(&template.rich_text), Some(&template.children))
+        }
+        Block::LinkToPage { link_to_page, .. } => match link_to_page {
+            LinkToPageFields::PageId { page_id } => format!("<{}>", notion_url(page_id)),
+            LinkToPageFields::DatabaseId { database_id } => {
+                format!("<{}>", notion_url(database_id))
+            }
+        },
+        Block::Table { table, .. } => {
+            let mut rows: Vec<Vec<String>> = table
+                .children
+                .iter()
+                .filter_map(|row| match row {
+                    Block::TableRow { table_row, .. } => Some(
+                        table_row
+                            .cells
+                            .iter()
+                            .map(|cell| render_text(cell))
+                            .collect(),
+                    ),
+                    _ => None,
+                })
+                .collect();
+            let width = rows
+                .iter()
+                .map(Vec::len)
+                .max()
+                .unwrap_or_default()
+                .max(table.table_width as usize);
+            // Markdown tables always have a header, an empty one stands in if Notion's has none.
+            let header = if table.has_column_header && !rows.is_empty() {
+                rows.remove(0)
+            } else {
+                vec![]
+            };
+            let mut lines = vec![
+                pipe_row(&header, width),
+                pipe_row(&vec!["---".to_string(); width], width),
+            ];
+            lines.extend(rows.iter().map(|row| pipe_row(row, width)));
+            lines.join("\n")
+        }
+        Block::SyncedBlock { synced_block, .. } => render_blocks(&synced_block.children),
+        Block::TableRow { table_row, .. } => {
+            let cells: Vec<String> = table_row
+                .cells
+                .iter()
+                .map(|cell| render_text(cell))
+                .collect();
+            pipe_row(&cells, cells.len())
+        }
+        Block::Unsupported { common } => comment(&format!("unsupported block {}", common.id)),
+        Block::Unknown => comment("unknown block"),
+    }
+}
+
+fn with_children(
+    text: String,
+    children: Option<&[Block]>,
+) -> String {
+    match children {
+        Some(children) if !children.is_empty() => {
+            format!("{}\n\n{}", text, render_blocks(children))
+        }
+        _ => text,
+    }
+}
+
+fn list_item(
+    marker: &str,
+    width: usize,
+    text: &[RichText],
+    children: Option<&[Block]>,
+) -> String {
+    indent(
+        &with_children(render_text(text), children),
+        marker,
+        &" ".repeat(width),
+    )
+}
+
+/// Prefixes the first line with `first` and all following non-empty lines with `rest`.
+fn indent(
+    text: &str,
+    first: &str,
+    rest: &str,
+) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(index, line)| match index {
+            0 => format!("{}{}", first, line),
+            _ if line.is_empty() => String::new(),
+            _ => format!("{}{}", rest, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn quote(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn comment(text: &str) -> String {
+    format!("<!-- {} -->", text)
+}
+
+fn pipe_row(
+    cells: &[String],
+    width: usize,
+) -> String {
+    let cells: Vec<&str> = (0..width)
+        .map(|index| cells.get(index).map(String::as_str).unwrap_or_default())
+        .collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+fn file_link(
+    text: &str,
+    file: &FileObject,
+) -> String {
+    match file.url() {
+        Some(url) => format!("[{}]({})", text, url),
+        None => comment(text),
+    }
+}
+
+/// The name Notion uses for the language, which is also understood by most highlighters.
+fn language_name(language: &CodeLanguage) -> String {
+    match language {
+        CodeLanguage::PlainText => "text".to_string(),
+        language => serde_json::to_value(language)
+            .ok()
+            .and_then(|name| name.as_str().map(str::to_string))
+            .unwrap_or_default(),
+    }
+}
+
+fn longest_run(
+    text: &str,
+    character: char,
+) -> usize {
+    text.split(|c| c != character)
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
+
+fn render_text(text: &[RichText]) -> String {
+    text.iter().map(render_run).collect()
+}
+
+fn render_run(run: &RichText) -> String {
+    let (common, content) = match run {
+        RichText::Text { rich_text, text } => (rich_text, text.content.as_str()),
+        RichText::Mention { rich_text, .. } => (rich_text, rich_text.plain_text.as_str()),
+        RichText::Equation { equation, .. } => return format!("${}$", equation.expression),
+    };
+    let annotations = common.annotations.clone().unwrap_or_default();
+    let is_set = |flag: Option<bool>| flag == Some(true);
+
+    // Markup can't start or end with whitespace, so it is kept outside.
+    let start = content.len() - content.trim_start().len();
+    let end = content.trim_end().len();
+    if start >= end {
+        return escape(content);
+    }
+    let core = &content[start..end];
+    let mut markdown = if is_set(annotations.code) {
+        code_span(core)
+    } else {
+        escape(core)
+    };
+    markdown = style(markdown, &annotations);
+    if let Some(url) = &common.href {
+        markdown = format!("[{}]({})", markdown, link_destination(url));
+    }
+    format!(
+        "{}{}{}",
+        escape(&content[..start]),
+        markdown,
+        escape(&content[end..])
+    )
+}
+
+fn style(
+    mut markdown: String,
+    annotations: &Annotations,
+) -> String {
+    let is_set = |flag: Option<bool>| flag == Some(true);
+    if is_set(annotations.underline) {
+        markdown = format!("<ins>{}</ins>", markdown);
+    }
+    if is_set(annotations.strikethrough) {
+        markdown = format!("~~{}~~", markdown);
+    }
+    if is_set(annotations.italic) {
+        markdown = format!("*{}*", markdown);
+    }
+    if is_set(annotations.bold) {
+        markdown = format!("**{}**", markdown);
+    }
+    markdown
+}
+
+fn code_span(code: &str) -> String {
+    let fence = "`".repeat(longest_run(code, '`') + 1);
+    let code = code.replace('\n', " ");
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+fn link_destination(url: &str) -> String {
+    if url.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// Escapes characters that would otherwise be read as markup, line breaks become `<br>`
+/// so they also work in table cells.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\n' => escaped.push_str("<br>"),
+            '\\' | '`' | '*' | '_' | '[' | ']' | '~' | '<' | '>' | '|' | '#' | '&' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn block(
+        kind: &str,
+        fields: Value,
+    ) -> Block {
+        let mut block = json!({
+            "object": "block",
+            "id": "b8b944b5-cc3d-444e-a25c-a7ddacd528cb",
+            "created_time": "2022-05-13T20:08:00.000Z",
+            "last_edited_time": "2022-05-13T20:08:00.000Z",
+            "created_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+            "last_edited_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+            "has_children": false,
+            "type": kind,
+        });
+        block[kind] = fields;
+        serde_json::from_value(block).unwrap()
+    }
+
+    fn text(content: &str) -> Value {
+        json!([{ "type": "text", "text": { "content": content, "link": null }, "plain_text": content }])
+    }
+
+    fn item(
+        kind: &str,
+        content: &str,
+        children: Vec<Block>,
+    ) -> Block {
+        block(
+            kind,
+            json!({ "rich_text": text(content), "color": "default", "children": children }),
+        )
+    }
+
+    #[test]
+    fn renders_inline_markup() {
+        let paragraph = block(
+            "paragraph",
+            json!({
+                "rich_text": [
+                    { "type": "text", "text": { "content": "Bold ", "link": null }, "plain_text": "Bold ",
+                      "annotations": { "bold": true } },
+                    { "type": "text", "text": { "content": "code", "link": null }, "plain_text": "code",
+                      "annotations": { "code": true } },
+                    { "type": "text", "text": { "content": " 1*2 ", "link": null }, "plain_text": " 1*2 " },
+                    { "type": "text", "text": { "content": "docs", "link": { "url": "https://example.com" } },
+                      "plain_text": "docs", "href": "https://example.com", "annotations": { "italic": true } },
+                    { "type": "mention", "mention": { "type": "user", "user": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" } },
+                      "plain_text": "@Ben" },
+                    { "type": "mention", "mention": { "type": "page", "page": { "id": "b8b944b5-cc3d-444e-a25c-a7ddacd528cb" } },
+                      "plain_text": "Wiki", "href": "https://www.notion.so/b8b944b5cc3d444ea25ca7ddacd528cb" },
+                    { "type": "equation", "equation": { "expression": "x^2" }, "plain_text": "x^2" }
+                ],
+                "color": "default"
+            }),
+        );
+
+        assert_eq!(
+            from_blocks(&[paragraph]),
+            "**Bold** `code` 1\\*2 [*docs*](https://example.com)@Ben[Wiki](https://www.notion.so/b8b944b5cc3d444ea25ca7ddacd528cb)$x^2$\n"
+        );
+    }
+
+    #[test]
+    fn renders_nested_lists_and_tasks() {
+        let blocks = vec![
+            item(
+                "bulleted_list_item",
+                "one",
+                vec![
+                    item("numbered_list_item", "first", vec![]),
+                    item("numbered_list_item", "second", vec![]),
+                ],
+            ),
+            item("bulleted_list_item", "two", vec![]),
+            block(
+                "to_do",
+                json!({ "rich_text": text("done"), "checked": true, "color": "default" }),
+            ),
+        ];
+
+        assert_eq!(
+            from_blocks(&blocks),
+            "- one\n\n  1. first\n  2. second\n- two\n- [x] done\n"
+        );
+    }
+
+    #[test]
+    fn renders_code_quotes_and_equations() {
+        let blocks = vec![
+            block(
+                "code",
+                json!({ "rich_text": text("let a = 1;"), "caption": [], "language": "rust" }),
+            ),
+            item("quote", "quoted", vec![item("paragraph", "more", vec![])]),
+            block(
+                "callout",
+                json!({ "rich_text": text("Note"), "icon": { "type": "emoji", "emoji": "💡" }, "color": "default" }),
+            ),
+            block("equation", json!({ "expression": "E = mc^2" })),
+            block("divider", json!({})),
+        ];
+
+        assert_eq!(
+            from_blocks(&blocks),
+            "```rust\nlet a = 1;\n```\n\n> quoted\n>\n> more\n\n> 💡 Note\n\n$$\nE = mc^2\n$$\n\n---\n"
+        );
+    }
+
+    #[test]
+    fn renders_tables() {
+        let row = |cells: Vec<&str>| {
+            block(
+                "table_row",
+                json!({ "cells": cells.into_iter().map(text).collect::<Vec<_>>() }),
+            )
+        };
+        let table = block(
+            "table",
+            json!({
+                "table_width": 2,
+                "has_column_header": true,
+                "has_row_header": false,
+                "children": [row(vec!["a", "b"]), row(vec!["1", "x|y"])]
+            }),
+        );
+
+        assert_eq!(
+            from_blocks(&[table]),
+            "| a | b |\n| --- | --- |\n| 1 | x\\|y |\n"
+        );
+    }
+
+    #[test]
+    fn keeps_unsupported_blocks_as_comments() {
+        let blocks = vec![block("unsupported", json!({})), Block::Unknown];
+
+        assert_eq!(
+            from_blocks(&blocks),
+            "<!-- unsupported block b8b944b5-cc3d-444e-a25c-a7ddacd528cb -->\n\n<!-- unknown block -->\n"
+        );
+    }
+}
@@ -0,0 +1,541 @@
+//! Conversion of Markdown documents to Notion blocks.
+
+use crate::models::block::{
+    CodeFields, CodeLanguage, CreateBlock, Equation, ExternalFileObject, FileObject, TableFields,
+    TableRowFields, Text, TextAndChildren, ToDoFields,
+};
+use crate::models::text::{RichText, TextColor};
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// Converts a Markdown document to blocks that can be appended to a page, e.g. with
+/// [NotionApi::append_all_block_children()](crate::NotionApi::append_all_block_children()).
+///
+/// Understands CommonMark with the GitHub extensions for tables, task lists and
+/// strikethrough, plus `$…$` and `$$…$$` equations.
+/// Nested lists and quotes become nested children. Images are only supported with absolute
+/// URLs since Notion has to fetch them, raw HTML is kept as plain text.
+pub fn to_blocks(markdown: &str) -> Vec<CreateBlock> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_MATH;
+    let mut converter = Converter::default();
+    for event in Parser::new_ext(markdown, options) {
+        converter.handle(event);
+    }
+    converter.finish()
+}
+
+enum ContainerKind {
+    Root,
+    Quote,
+    Item {
+        ordered: bool,
+        checked: Option<bool>,
+    },
+}
+
+/// A block that holds the blocks converted while it is open.
+struct Container {
+    kind: ContainerKind,
+    /// The first paragraph of a list item or quote, which Notion shows as its text.
+    text: Option<Vec<RichText>>,
+    children: Vec<CreateBlock>,
+}
+
+impl Container {
+    fn new(kind: ContainerKind) -> Self {
+        Container {
+            kind,
+            text: None,
+            children: vec![],
+        }
+    }
+}
+
+#[derive(Default)]
+struct Table {
+    width: usize,
+    has_column_header: bool,
+    rows: Vec<CreateBlock>,
+    cells: Vec<Vec<RichText>>,
+}
+
+#[derive(Default)]
+struct Style {
+    bold: usize,
+    italic: usize,
+    strikethrough: usize,
+    link: Option<String>,
+}
+
+#[derive(Default)]
+struct Converter {
+    containers: Vec<Container>,
+    /// Whether the open lists are ordered, innermost last.
+    lists: Vec<bool>,
+    runs: Vec<RichText>,
+    style: Style,
+    code: Option<(CodeLanguage, String)>,
+    image: Option<String>,
+    table: Option<Table>,
+}
+
+impl Converter {
+    fn handle(
+        &mut self,
+        event: Event,
+    ) {
+        if let Some((_, code)) = &mut self.code {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => self.end_code(),
+                _ => {}
+            }
+            return;
+        }
+        if self.image.is_some() {
+            // The alternative text of an image has no place in an image block.
+            if let Event::End(TagEnd::Image) = event {
+                let url = self.image.take().unwrap_or_default();
+                self.push_block(CreateBlock::Image {
+                    image: FileObject::External {
+                        external: ExternalFileObject { url },
+                    },
+                });
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                self.push_run(RichText::text(text.to_string()))
+            }
+            Event::Code(code) => self.push_run(RichText::text(code.to_string()).code()),
+            Event::InlineMath(expression) => self.runs.push(RichText::equation(expression)),
+            Event::DisplayMath(expression) => self.push_block(CreateBlock::Equation {
+                equation: Equation {
+                    expression: expression.trim().to_string(),
+                },
+            }),
+            Event::SoftBreak => self.push_run(RichText::text(" ")),
+            Event::HardBreak => self.push_run(RichText::text("\n")),
+            Event::Rule => self.push_block(CreateBlock::Divider),
+            Event::TaskListMarker(done) => {
+                if let Some(Container {
+                    kind: ContainerKind::Item { checked, .. },
+                    ..
+                }) = self.containers.last_mut()
+                {
+                    *checked = Some(done);
+                }
+            }
+            Event::FootnoteReference(_) => {}
+        }
+    }
+
+    fn start(
+        &mut self,
+        tag: Tag,
+    ) {
+        match tag {
+            Tag::Heading { .. } | Tag::Paragraph | Tag::TableCell => self.flush_text(),
+            Tag::BlockQuote(_) => self.open(ContainerKind::Quote),
+            Tag::List(start) => {
+                self.flush_text();
+                self.lists.push(start.is_some());
+            }
+            Tag::Item => self.open(ContainerKind::Item {
+                ordered: self.lists.last().copied().unwrap_or_default(),
+                checked: None,
+            }),
+            Tag::CodeBlock(kind) => {
+                self.flush_text();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => code_language(&info),
+                    CodeBlockKind::Indented => CodeLanguage::PlainText,
+                };
+                self.code = Some((language, String::new()));
+            }
+            Tag::Table(alignments) => {
+                self.flush_text();
+                self.table = Some(Table {
+                    width: alignments.len(),
+                    ..Default::default()
+                });
+            }
+            Tag::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.has_column_header = true;
+                }
+            }
+            Tag::Emphasis => self.style.italic += 1,
+            Tag::Strong => self.style.bold += 1,
+            Tag::Strikethrough => self.style.strikethrough += 1,
+            Tag::Link { dest_url, .. } => self.style.link = Some(dest_url.to_string()),
+            Tag::Image { dest_url, .. } => self.image = Some(dest_url.to_string()),
+            _ => {}
+        }
+    }
+
+    fn end(
+        &mut self,
+        tag: TagEnd,
+    ) {
+        match tag {
+            TagEnd::Paragraph => self.flush_text(),
+            TagEnd::Heading(level) => {
+                let text = Text {
+                    rich_text: std::mem::take(&mut self.runs),
+                };
+                self.push_block(match level {
+                    HeadingLevel::H1 => CreateBlock::Heading1 { heading_1: text },
+                    HeadingLevel::H2 => CreateBlock::Heading2 { heading_2: text },
+                    _ => CreateBlock::Heading3 { heading_3: text },
+                });
+            }
+            TagEnd::BlockQuote(_) | TagEnd::Item => self.close(),
+            TagEnd::List(_) => {
+                self.lists.pop();
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.runs);
+                if let Some(table) = &mut self.table {
+                    table.cells.push(cell);
+                }
+            }
+            TagEnd::TableHead | TagEnd::TableRow => {
+                if let Some(table) = &mut self.table {
+                    let cells = std::mem::take(&mut table.cells);
+                    table.rows.push(CreateBlock::TableRow {
+                        table_row: TableRowFields { cells },
+                    });
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.push_block(CreateBlock::Table {
+                        table: TableFields {
+                            table_width: table.width as u64,
+                            has_column_header: table.has_column_header,
+                            has_row_header: false,
+                            children: table.rows,
+                        },
+                    });
+                }
+            }
+            TagEnd::Emphasis => self.style.italic -= 1,
+            TagEnd::Strong => self.style.bold -= 1,
+            TagEnd::Strikethrough => self.style.strikethrough -= 1,
+            TagEnd::Link => self.style.link = None,
+            _ => {}
+        }
+    }
+
+    fn end_code(&mut self) {
+        if let Some((language, mut code)) = self.code.take() {
+            if code.ends_with('\n') {
+                code.pop();
+            }
+            self.push_block(CreateBlock::Code {
+                code: CodeFields {
+                    rich_text: vec![RichText::text(code)],
+                    caption: vec![],
+                    language,
+                },
+            });
+        }
+    }
+
+    /// Adds text in the current style, merged into the previous run if it has the same style.
+    fn push_run(
+        &mut self,
+        run: RichText,
+    ) {
+        let mut run = run;
+        if self.style.bold > 0 {
+            run = run.bold();
+        }
+        if self.style.italic > 0 {
+            run = run.italic();
+        }
+        if self.style.strikethrough > 0 {
+            run = run.strikethrough();
+        }
+        if let Some(url) = &self.style.link {
+            run = run.link(url.clone());
+        }
+
+        if let (
+            Some(RichText::Text {
+                rich_text: last_common,
+                text: last,
+            }),
+            RichText::Text { rich_text, text },
+        ) = (self.runs.last_mut(), &run)
+        {
+            if last_common.annotations == rich_text.annotations && last.link == text.link {
+                last.content.push_str(&text.content);
+                last_common.plain_text.push_str(&text.content);
+                return;
+            }
+        }
+        self.runs.push(run);
+    }
+
+    /// Turns the text collected so far into the text of the open list item or quote, or into
+    /// a paragraph if that already has its text.
+    fn flush_text(&mut self) {
+        if self.runs.is_empty() {
+            return;
+        }
+        let runs = std::mem::take(&mut self.runs);
+        let container = self.container();
+        match container.kind {
+            ContainerKind::Quote | ContainerKind::Item { .. } if container.text.is_none() => {
+                container.text = Some(runs)
+            }
+            _ => container.children.push(paragraph(runs)),
+        }
+    }
+
+    fn push_block(
+        &mut self,
+        block: CreateBlock,
+    ) {
+        self.flush_text();
+        self.container().children.push(block);
+    }
+
+    fn container(&mut self) -> &mut Container {
+        if self.containers.is_empty() {
+            self.containers.push(Container::new(ContainerKind::Root));
+        }
+        self.containers
+            .last_mut()
+            .expect("The root container exists")
+    }
+
+    fn open(
+        &mut self,
+        kind: ContainerKind,
+    ) {
+        self.flush_text();
+        self.container();
+        self.containers.push(Container::new(kind));
+    }
+
+    fn close(&mut self) {
+        self.flush_text();
+        let container = match self.containers.pop() {
+            Some(container) => container,
+            None => return,
+        };
+        let rich_text = container.text.unwrap_or_default();
+        let children = Some(container.children).filter(|children| !children.is_empty());
+        let fields = TextAndChildren {
+            rich_text,
+            children,
+            color: TextColor::Default,
+        };
+        let block = match container.kind {
+            ContainerKind::Root => {
+                // Never opened explicitly, put it back.
+                self.containers.push(Container::new(ContainerKind::Root));
+                return;
+            }
+            ContainerKind::Quote => CreateBlock::Quote { quote: fields },
+            ContainerKind::Item {
+                checked: Some(checked),
+                ..
+            } => CreateBlock::ToDo {
+                to_do: ToDoFields {
+                    rich_text: fields.rich_text,
+                    checked,
+                    children: fields.children,
+                    color: fields.color,
+                },
+            },
+            ContainerKind::Item { ordered: true, .. } => CreateBlock::NumberedListItem {
+                numbered_list_item: fields,
+            },
+            ContainerKind::Item { ordered: false, .. } => CreateBlock::BulletedListItem {
+                bulleted_list_item: fields,
+            },
+        };
+        self.container().children.push(block);
+    }
+
+    fn finish(mut self) -> Vec<CreateBlock> {
+        self.flush_text();
+        while self.containers.len() > 1 {
+            self.close();
+        }
+        self.containers
+            .pop()
+            .map(|root| root.children)
+            .unwrap_or_default()
+    }
+}
+
+fn paragraph(rich_text: Vec<RichText>) -> CreateBlock {
+    CreateBlock::Paragraph {
+        paragraph: TextAndChildren {
+            rich_text,
+            children: None,
+            color: TextColor::Default,
+        },
+    }
+}
+
+/// Maps the info string of a fenced code block to a language, falling back to plain text.
+fn code_language(info: &str) -> CodeLanguage {
+    let name = info
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let name = match name.as_str() {
+        "" | "text" | "txt" => "plain text",
+        "rs" => "rust",
+        "js" | "jsx" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "py" => "python",
+        "rb" => "ruby",
+        "sh" | "zsh" => "shell",
+        "yml" => "yaml",
+        "md" => "markdown",
+        "cpp" | "cxx" => "c++",
+        "cs" | "csharp" => "c#",
+        "fs" | "fsharp" => "f#",
+        "kt" => "kotlin",
+        "hs" => "haskell",
+        "dockerfile" => "docker",
+        "tex" => "latex",
+        name => name,
+    };
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+        .unwrap_or(CodeLanguage::PlainText)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn to_json(markdown: &str) -> serde_json::Value {
+        serde_json::to_value(to_blocks(markdown)).unwrap()
+    }
+
+    #[test]
+    fn converts_headings_and_inline_styles() {
+        let blocks =
+            to_json("# Title\n\nSome **bold _and_** `code` with [a link](https://example.com).\n");
+
+        assert_eq!(blocks[0]["type"], "heading_1");
+        assert_eq!(
+            blocks[0]["heading_1"]["rich_text"][0]["text"]["content"],
+            "Title"
+        );
+        let runs = &blocks[1]["paragraph"]["rich_text"];
+        let contents: Vec<_> = runs
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|run| run["text"]["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            contents,
+            vec!["Some ", "bold ", "and", " ", "code", " with ", "a link", "."]
+        );
+        assert_eq!(runs[1]["annotations"], json!({ "bold": true }));
+        assert_eq!(
+            runs[2]["annotations"],
+            json!({ "bold": true, "italic": true })
+        );
+        assert_eq!(runs[4]["annotations"], json!({ "code": true }));
+        assert_eq!(
+            runs[6]["text"]["link"],
+            json!({ "url": "https://example.com" })
+        );
+    }
+
+    #[test]
+    fn nests_lists_and_tasks() {
+        let blocks = to_blocks("- one\n  1. nested\n- [x] done\n- [ ] open\n");
+
+        match &blocks[0] {
+            CreateBlock::BulletedListItem { bulleted_list_item } => {
+                assert_eq!(bulleted_list_item.rich_text[0].plain_text(), "one");
+                let children = bulleted_list_item.children.as_ref().unwrap();
+                assert!(matches!(
+                    &children[..],
+                    [CreateBlock::NumberedListItem { .. }]
+                ));
+            }
+            other => panic!("Unexpected block {:?}", other),
+        }
+        assert!(matches!(
+            &blocks[1],
+            CreateBlock::ToDo { to_do } if to_do.checked && to_do.rich_text[0].plain_text() == "done"
+        ));
+        assert!(matches!(&blocks[2], CreateBlock::ToDo { to_do } if !to_do.checked));
+    }
+
+    #[test]
+    fn converts_code_quotes_and_rules() {
+        let blocks = to_json("```rs\nfn main() {}\n```\n\n> quoted\n>\n> more\n\n---\n");
+
+        assert_eq!(
+            blocks[0],
+            json!({
+                "type": "code",
+                "code": {
+                    "rich_text": [{ "type": "text", "text": { "content": "fn main() {}", "link": null }, "plain_text": "fn main() {}" }],
+                    "caption": [],
+                    "language": "rust"
+                }
+            })
+        );
+        assert_eq!(blocks[1]["quote"]["rich_text"][0]["plain_text"], "quoted");
+        assert_eq!(
+            blocks[1]["quote"]["children"][0]["paragraph"]["rich_text"][0]["plain_text"],
+            "more"
+        );
+        assert_eq!(blocks[2], json!({ "type": "divider" }));
+    }
+
+    #[test]
+    fn converts_tables_images_and_equations() {
+        let blocks = to_json(
+            "| a | b |\n|---|---|\n| 1 | 2 |\n\n![alt](https://example.com/image.png)\n\n$$\nE = mc^2\n$$\n\nInline $x$.\n",
+        );
+
+        let table = &blocks[0]["table"];
+        assert_eq!(table["table_width"], 2);
+        assert_eq!(table["has_column_header"], true);
+        assert_eq!(
+            table["children"][1]["table_row"]["cells"][1][0]["plain_text"],
+            "2"
+        );
+        assert_eq!(
+            blocks[1]["image"],
+            json!({ "type": "external", "external": { "url": "https://example.com/image.png" } })
+        );
+        assert_eq!(blocks[2]["equation"]["expression"], "E = mc^2");
+        assert_eq!(
+            blocks[3]["paragraph"]["rich_text"][1]["equation"]["expression"],
+            "x"
+        );
+    }
+
+    #[test]
+    fn maps_code_languages() {
+        assert_eq!(code_language("rust"), CodeLanguage::Rust);
+        assert_eq!(code_language("C++ ignored"), CodeLanguage::CPlusPlus);
+        assert_eq!(code_language("unknown"), CodeLanguage::PlainText);
+        assert_eq!(code_language(""), CodeLanguage::PlainText);
+    }
+}
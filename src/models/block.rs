@@ -1,12 +1,16 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::ids::{AsIdentifier, BlockId, DatabaseId, PageId};
+use crate::ids::{AsIdentifier, BlockId, DatabaseId, PageId, UNKNOWN_BLOCK_ID};
+use crate::models::file_uploads::{FileUpload, FileUploadReference};
 use crate::models::text::{RichText, TextColor};
 use crate::models::users::UserCommon;
 
 #[cfg(test)]
 mod tests;
+mod visitor;
+
+pub use visitor::{map_blocks, walk_block, walk_block_mut, BlockVisitor, BlockVisitorMut};
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct BlockCommon {
@@ -19,10 +23,10 @@ pub struct BlockCommon {
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
-pub struct TextAndChildren {
+pub struct TextAndChildren<B = Block> {
     pub rich_text: Vec<RichText>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub children: Option<Vec<Block>>,
+    pub children: Option<Vec<B>>,
     pub color: TextColor,
 }
 
@@ -53,18 +57,66 @@ pub struct CustomEmojiObject {
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum FileOrEmojiObject {
-    Emoji { emoji: String },
-    CustomEmoji { custom_emoji: CustomEmojiObject },
-    File { file: InternalFileObject },
-    External { external: ExternalFileObject },
+    Emoji {
+        emoji: String,
+    },
+    CustomEmoji {
+        custom_emoji: CustomEmojiObject,
+    },
+    File {
+        file: InternalFileObject,
+    },
+    External {
+        external: ExternalFileObject,
+    },
+    /// Only used when setting an icon, Notion returns uploaded files as [FileOrEmojiObject::File].
+    FileUpload {
+        file_upload: FileUploadReference,
+    },
+}
+
+impl From<&FileUpload> for FileOrEmojiObject {
+    fn from(upload: &FileUpload) -> Self {
+        FileOrEmojiObject::FileUpload {
+            file_upload: upload.into(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum FileObject {
-    File { file: InternalFileObject },
-    External { external: ExternalFileObject },
+    File {
+        file: InternalFileObject,
+    },
+    External {
+        external: ExternalFileObject,
+    },
+    /// Only used when creating blocks or setting a cover,
+    /// Notion returns uploaded files as [FileObject::File].
+    FileUpload {
+        file_upload: FileUploadReference,
+    },
+}
+
+impl FileObject {
+    /// Where the file can be downloaded, `None` for files that are still being uploaded.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            FileObject::File { file } => Some(&file.url),
+            FileObject::External { external } => Some(&external.url),
+            FileObject::FileUpload { .. } => None,
+        }
+    }
+}
+
+impl From<&FileUpload> for FileObject {
+    fn from(upload: &FileUpload) -> Self {
+        FileObject::FileUpload {
+            file_upload: upload.into(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -75,11 +127,11 @@ pub struct Callout {
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
-pub struct ToDoFields {
+pub struct ToDoFields<B = Block> {
     pub rich_text: Vec<RichText>,
     pub checked: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub children: Option<Vec<Block>>,
+    pub children: Option<Vec<B>>,
     pub color: TextColor,
 }
 
@@ -207,13 +259,17 @@ pub struct TableOfContents {
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
-pub struct ColumnListFields {
-    pub children: Vec<Block>,
+#[serde(bound(deserialize = "B: Deserialize<'de>"))]
+pub struct ColumnListFields<B = Block> {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<B>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
-pub struct ColumnFields {
-    pub children: Vec<Block>,
+#[serde(bound(deserialize = "B: Deserialize<'de>"))]
+pub struct ColumnFields<B = Block> {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<B>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -222,9 +278,11 @@ pub struct LinkPreviewFields {
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
-pub struct TemplateFields {
+#[serde(bound(deserialize = "B: Deserialize<'de>"))]
+pub struct TemplateFields<B = Block> {
     pub rich_text: Vec<RichText>,
-    pub children: Vec<Block>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<B>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -241,22 +299,27 @@ pub struct SyncedFromObject {
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
-pub struct SyncedBlockFields {
+#[serde(bound(deserialize = "B: Deserialize<'de>"))]
+pub struct SyncedBlockFields<B = Block> {
     pub synced_from: Option<SyncedFromObject>,
-    pub children: Vec<Block>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<B>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
-pub struct TableFields {
+#[serde(bound(deserialize = "B: Deserialize<'de>"))]
+pub struct TableFields<B = Block> {
     pub table_width: u64,
     pub has_column_header: bool,
     pub has_row_header: bool,
-    pub children: Vec<Block>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<B>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct TableRowFields {
-    pub cells: Vec<RichText>,
+    /// The content of every cell of the row.
+    pub cells: Vec<Vec<RichText>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -428,8 +491,19 @@ pub enum Block {
     Unknown,
 }
 
+/// [Block::Unknown] has no id and returns an empty one, which Notion rejects.
+/// Use [Block::common()] to tell unknown blocks apart.
 impl AsIdentifier<BlockId> for Block {
     fn as_id(&self) -> &BlockId {
+        self.common()
+            .map(|common| &common.id)
+            .unwrap_or(&UNKNOWN_BLOCK_ID)
+    }
+}
+
+impl Block {
+    /// The fields shared by all blocks, `None` for [Block::Unknown].
+    pub fn common(&self) -> Option<&BlockCommon> {
         use Block::*;
         match self {
             Paragraph { common, .. }
@@ -463,31 +537,384 @@ impl AsIdentifier<BlockId> for Block {
             | SyncedBlock { common, .. }
             | Table { common, .. }
             | TableRow { common, .. }
-            | Unsupported { common, .. } => &common.id,
-            Unknown => {
-                panic!("Trying to reference identifier for unknown block!")
+            | Unsupported { common, .. } => Some(common),
+            Unknown => None,
+        }
+    }
+
+    /// The text of this block without its children, see [to_plain_text()] for a whole tree.
+    ///
+    /// Captions follow the text they belong to on a new line, the cells of a table row
+    /// are separated by tabs. Blocks without text, like dividers or images without a caption,
+    /// return an empty string.
+    pub fn plain_text(&self) -> String {
+        use Block::*;
+        let parts: Vec<String> = match self {
+            Paragraph {
+                paragraph: fields, ..
+            }
+            | Quote { quote: fields, .. }
+            | BulletedListItem {
+                bulleted_list_item: fields,
+                ..
+            }
+            | NumberedListItem {
+                numbered_list_item: fields,
+                ..
+            }
+            | Toggle { toggle: fields, .. } => vec![joined(&fields.rich_text)],
+            Heading1 {
+                heading_1: text, ..
+            }
+            | Heading2 {
+                heading_2: text, ..
+            }
+            | Heading3 {
+                heading_3: text, ..
+            }
+            | File { caption: text, .. } => vec![joined(&text.rich_text)],
+            Callout { callout, .. } => vec![joined(&callout.rich_text)],
+            ToDo { to_do, .. } => vec![joined(&to_do.rich_text)],
+            Code { code, .. } => vec![joined(&code.rich_text), joined(&code.caption)],
+            ChildPage { child_page, .. } => vec![child_page.title.clone()],
+            ChildDatabase { child_database, .. } => vec![child_database.title.clone()],
+            Bookmark { bookmark, .. } => vec![joined(&bookmark.caption), bookmark.url.clone()],
+            Equation { equation, .. } => vec![equation.expression.clone()],
+            Template { template, .. } => vec![joined(&template.rich_text)],
+            TableRow { table_row, .. } => vec![table_row
+                .cells
+                .iter()
+                .map(|cell| joined(cell))
+                .collect::<Vec<_>>()
+                .join("\t")],
+            _ => vec![],
+        };
+        parts
+            .into_iter()
+            .filter(|part| !part.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Whether this block type has a `children` field that can hold its nested blocks.
+    pub fn holds_children(&self) -> bool {
+        use Block::*;
+        matches!(
+            self,
+            Paragraph { .. }
+                | Quote { .. }
+                | BulletedListItem { .. }
+                | NumberedListItem { .. }
+                | ToDo { .. }
+                | Toggle { .. }
+                | ColumnList { .. }
+                | Column { .. }
+                | Template { .. }
+                | Table { .. }
+                | SyncedBlock { .. }
+        )
+    }
+
+    /// Replaces the nested blocks of this block.
+    /// Returns `false` and drops `children` if this block type can't hold them,
+    /// see [holds_children()](Self::holds_children()).
+    pub fn set_children(
+        &mut self,
+        children: Vec<Block>,
+    ) -> bool {
+        use Block::*;
+        match self {
+            Paragraph {
+                paragraph: fields, ..
+            }
+            | Quote { quote: fields, .. }
+            | BulletedListItem {
+                bulleted_list_item: fields,
+                ..
+            }
+            | NumberedListItem {
+                numbered_list_item: fields,
+                ..
+            }
+            | Toggle { toggle: fields, .. } => fields.children = Some(children),
+            ToDo { to_do, .. } => to_do.children = Some(children),
+            ColumnList { column_list, .. } => column_list.children = children,
+            Column { column, .. } => column.children = children,
+            Template { template, .. } => template.children = children,
+            Table { table, .. } => table.children = children,
+            SyncedBlock { synced_block, .. } => synced_block.children = children,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Every rich text field of a block: its text, captions and table cells.
+/// `$iter` is `iter` or `iter_mut`, followed by `mut` for mutable access.
+macro_rules! text_fields {
+    ($block:expr, $kind:ident, $iter:ident $(, $mut:tt)?) => {{
+        use $kind::*;
+        match $block {
+            Paragraph {
+                paragraph: fields, ..
+            }
+            | Quote { quote: fields, .. }
+            | BulletedListItem {
+                bulleted_list_item: fields,
+                ..
+            }
+            | NumberedListItem {
+                numbered_list_item: fields,
+                ..
+            }
+            | Toggle { toggle: fields, .. } => vec![&$($mut)? fields.rich_text],
+            Heading1 {
+                heading_1: text, ..
+            }
+            | Heading2 {
+                heading_2: text, ..
+            }
+            | Heading3 {
+                heading_3: text, ..
+            }
+            | File { caption: text, .. } => vec![&$($mut)? text.rich_text],
+            Callout { callout, .. } => vec![&$($mut)? callout.rich_text],
+            ToDo { to_do, .. } => vec![&$($mut)? to_do.rich_text],
+            Code { code, .. } => vec![&$($mut)? code.rich_text, &$($mut)? code.caption],
+            Bookmark { bookmark, .. } => vec![&$($mut)? bookmark.caption],
+            Template { template, .. } => vec![&$($mut)? template.rich_text],
+            TableRow { table_row, .. } => table_row.cells.$iter().collect(),
+            _ => vec![],
+        }
+    }};
+}
+
+/// The main text of a block, without captions or table cells.
+macro_rules! main_text {
+    ($block:expr $(, $mut:tt)?) => {{
+        use Block::*;
+        match $block {
+            Paragraph {
+                paragraph: fields, ..
+            }
+            | Quote { quote: fields, .. }
+            | BulletedListItem {
+                bulleted_list_item: fields,
+                ..
+            }
+            | NumberedListItem {
+                numbered_list_item: fields,
+                ..
+            }
+            | Toggle { toggle: fields, .. } => Some(&$($mut)? fields.rich_text),
+            Heading1 {
+                heading_1: text, ..
+            }
+            | Heading2 {
+                heading_2: text, ..
+            }
+            | Heading3 {
+                heading_3: text, ..
+            } => Some(&$($mut)? text.rich_text),
+            Callout { callout, .. } => Some(&$($mut)? callout.rich_text),
+            ToDo { to_do, .. } => Some(&$($mut)? to_do.rich_text),
+            Code { code, .. } => Some(&$($mut)? code.rich_text),
+            Template { template, .. } => Some(&$($mut)? template.rich_text),
+            _ => None,
+        }
+    }};
+}
+
+/// The nested blocks of a block, `None` if it has none or can't hold them.
+/// `$as` is `as_ref` or `as_mut`, followed by `mut` for mutable access.
+macro_rules! children {
+    ($block:expr, $kind:ident, $as:ident $(, $mut:tt)?) => {{
+        use $kind::*;
+        match $block {
+            Paragraph {
+                paragraph: fields, ..
+            }
+            | Quote { quote: fields, .. }
+            | BulletedListItem {
+                bulleted_list_item: fields,
+                ..
+            }
+            | NumberedListItem {
+                numbered_list_item: fields,
+                ..
+            }
+            | Toggle { toggle: fields, .. } => fields.children.$as(),
+            ToDo { to_do, .. } => to_do.children.$as(),
+            ColumnList { column_list, .. } => Some(&$($mut)? column_list.children),
+            Column { column, .. } => Some(&$($mut)? column.children),
+            Template { template, .. } => Some(&$($mut)? template.children),
+            Table { table, .. } => Some(&$($mut)? table.children),
+            SyncedBlock { synced_block, .. } => Some(&$($mut)? synced_block.children),
+            _ => None,
+        }
+    }};
+}
+
+impl CreateBlock {
+    pub(crate) fn text_fields_mut(&mut self) -> Vec<&mut Vec<RichText>> {
+        text_fields!(self, CreateBlock, iter_mut, mut)
+    }
+
+    pub(crate) fn nested(&self) -> Option<&Vec<CreateBlock>> {
+        children!(self, CreateBlock, as_ref)
+    }
+
+    pub(crate) fn nested_mut(&mut self) -> Option<&mut Vec<CreateBlock>> {
+        children!(self, CreateBlock, as_mut, mut)
+    }
+
+    /// The body of an update request: the block without its nested children,
+    /// which can't be changed through an update.
+    pub(crate) fn into_update(self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).expect("blocks serialize to JSON");
+        if let Some(kind) = value["type"].as_str().map(str::to_string) {
+            if let Some(fields) = value[&kind].as_object_mut() {
+                fields.remove("children");
             }
         }
+        value
+    }
+}
+
+impl Block {
+    /// The nested blocks of this block, empty if it has none or they were not fetched,
+    /// see [NotionApi::fetch_block_tree()](crate::NotionApi::fetch_block_tree()).
+    pub fn children(&self) -> &[Block] {
+        children!(self, Block, as_ref)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The nested blocks of this block, `None` if it can't hold children or, for blocks whose
+    /// `children` field is optional, none were fetched.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Block>> {
+        children!(self, Block, as_mut, mut)
+    }
+
+    /// The main text of this block, `None` for blocks without text.
+    /// Captions and table cells are not included, see [text_fields()](Self::text_fields()).
+    pub fn rich_text(&self) -> Option<&[RichText]> {
+        main_text!(self).map(Vec::as_slice)
+    }
+
+    /// The main text of this block, see [rich_text()](Self::rich_text()).
+    pub fn rich_text_mut(&mut self) -> Option<&mut Vec<RichText>> {
+        main_text!(self, mut)
+    }
+
+    /// Every rich text field of this block: its text, captions and table cells.
+    pub fn text_fields(&self) -> Vec<&Vec<RichText>> {
+        text_fields!(self, Block, iter)
+    }
+
+    /// Every rich text field of this block, see [text_fields()](Self::text_fields()).
+    pub fn text_fields_mut(&mut self) -> Vec<&mut Vec<RichText>> {
+        text_fields!(self, Block, iter_mut, mut)
+    }
+
+    /// The type of this block as Notion names it, e.g. `"heading_1"` or `"to_do"`.
+    pub fn block_type(&self) -> &'static str {
+        use Block::*;
+        match self {
+            Paragraph { .. } => "paragraph",
+            Heading1 { .. } => "heading_1",
+            Heading2 { .. } => "heading_2",
+            Heading3 { .. } => "heading_3",
+            Callout { .. } => "callout",
+            Quote { .. } => "quote",
+            BulletedListItem { .. } => "bulleted_list_item",
+            NumberedListItem { .. } => "numbered_list_item",
+            ToDo { .. } => "to_do",
+            Toggle { .. } => "toggle",
+            Code { .. } => "code",
+            ChildPage { .. } => "child_page",
+            ChildDatabase { .. } => "child_database",
+            Embed { .. } => "embed",
+            Image { .. } => "image",
+            Video { .. } => "video",
+            File { .. } => "file",
+            Pdf { .. } => "pdf",
+            Bookmark { .. } => "bookmark",
+            Equation { .. } => "equation",
+            Divider { .. } => "divider",
+            TableOfContents { .. } => "table_of_contents",
+            Breadcrumb { .. } => "breadcrumb",
+            ColumnList { .. } => "column_list",
+            Column { .. } => "column",
+            LinkPreview { .. } => "link_preview",
+            Template { .. } => "template",
+            LinkToPage { .. } => "link_to_page",
+            Table { .. } => "table",
+            SyncedBlock { .. } => "synced_block",
+            TableRow { .. } => "table_row",
+            Unsupported { .. } => "unsupported",
+            Unknown => "unknown",
+        }
+    }
+}
+
+fn joined(text: &[RichText]) -> String {
+    text.iter().map(RichText::plain_text).collect()
+}
+
+/// The text of `blocks` and all their nested children, one block per line.
+///
+/// Children are indented by two spaces below their parent, unless the parent only arranges
+/// them without text of its own, like columns, tables and synced blocks.
+pub fn to_plain_text(blocks: &[Block]) -> String {
+    let mut lines = vec![];
+    collect_lines(blocks, 0, &mut lines);
+    lines.join("\n")
+}
+
+fn collect_lines(
+    blocks: &[Block],
+    depth: usize,
+    lines: &mut Vec<String>,
+) {
+    for block in blocks {
+        let text = block.plain_text();
+        let indentation = "  ".repeat(depth);
+        lines.extend(text.lines().map(|line| format!("{}{}", indentation, line)));
+        let depth = if text.is_empty() { depth } else { depth + 1 };
+        collect_lines(block.children(), depth, lines);
     }
 }
 
 impl From<Block> for CreateBlock {
     fn from(val: Block) -> Self {
         match val {
-            Block::Paragraph { paragraph, .. } => CreateBlock::Paragraph { paragraph },
+            Block::Paragraph { paragraph, .. } => CreateBlock::Paragraph {
+                paragraph: paragraph.into(),
+            },
             Block::Heading1 { heading_1, .. } => CreateBlock::Heading1 { heading_1 },
             Block::Heading2 { heading_2, .. } => CreateBlock::Heading2 { heading_2 },
             Block::Heading3 { heading_3, .. } => CreateBlock::Heading3 { heading_3 },
             Block::Callout { callout, .. } => CreateBlock::Callout { callout },
-            Block::Quote { quote, .. } => CreateBlock::Quote { quote },
+            Block::Quote { quote, .. } => CreateBlock::Quote {
+                quote: quote.into(),
+            },
             Block::BulletedListItem {
                 bulleted_list_item, ..
-            } => CreateBlock::BulletedListItem { bulleted_list_item },
+            } => CreateBlock::BulletedListItem {
+                bulleted_list_item: bulleted_list_item.into(),
+            },
             Block::NumberedListItem {
                 numbered_list_item, ..
-            } => CreateBlock::NumberedListItem { numbered_list_item },
-            Block::ToDo { to_do, .. } => CreateBlock::ToDo { to_do },
-            Block::Toggle { toggle, .. } => CreateBlock::Toggle { toggle },
+            } => CreateBlock::NumberedListItem {
+                numbered_list_item: numbered_list_item.into(),
+            },
+            Block::ToDo { to_do, .. } => CreateBlock::ToDo {
+                to_do: to_do.into(),
+            },
+            Block::Toggle { toggle, .. } => CreateBlock::Toggle {
+                toggle: toggle.into(),
+            },
             Block::Code { code, .. } => CreateBlock::Code { code },
             Block::ChildPage { child_page, .. } => CreateBlock::ChildPage { child_page },
             Block::ChildDatabase { child_database, .. } => {
@@ -505,14 +932,24 @@ impl From<Block> for CreateBlock {
                 table_of_contents, ..
             } => CreateBlock::TableOfContents { table_of_contents },
             Block::Breadcrumb { .. } => CreateBlock::Breadcrumb {},
-            Block::ColumnList { column_list, .. } => CreateBlock::ColumnList { column_list },
-            Block::Column { column, .. } => CreateBlock::Column { column },
+            Block::ColumnList { column_list, .. } => CreateBlock::ColumnList {
+                column_list: column_list.into(),
+            },
+            Block::Column { column, .. } => CreateBlock::Column {
+                column: column.into(),
+            },
 
             Block::LinkPreview { link_preview, .. } => CreateBlock::LinkPreview { link_preview },
-            Block::Template { template, .. } => CreateBlock::Template { template },
+            Block::Template { template, .. } => CreateBlock::Template {
+                template: template.into(),
+            },
             Block::LinkToPage { link_to_page, .. } => CreateBlock::LinkToPage { link_to_page },
-            Block::Table { table, .. } => CreateBlock::Table { table },
-            Block::SyncedBlock { synced_block, .. } => CreateBlock::SyncedBlock { synced_block },
+            Block::Table { table, .. } => CreateBlock::Table {
+                table: table.into(),
+            },
+            Block::SyncedBlock { synced_block, .. } => CreateBlock::SyncedBlock {
+                synced_block: synced_block.into(),
+            },
             Block::TableRow { table_row, .. } => CreateBlock::TableRow { table_row },
             Block::Unsupported { .. } => CreateBlock::Unsupported,
             Block::Unknown => CreateBlock::Unknown,
@@ -520,12 +957,91 @@ impl From<Block> for CreateBlock {
     }
 }
 
+fn create_children(children: Vec<Block>) -> Vec<CreateBlock> {
+    children.into_iter().map(CreateBlock::from).collect()
+}
+
+impl From<TextAndChildren> for TextAndChildren<CreateBlock> {
+    fn from(fields: TextAndChildren) -> Self {
+        TextAndChildren {
+            rich_text: fields.rich_text,
+            children: fields.children.map(create_children),
+            color: fields.color,
+        }
+    }
+}
+
+impl From<ToDoFields> for ToDoFields<CreateBlock> {
+    fn from(fields: ToDoFields) -> Self {
+        ToDoFields {
+            rich_text: fields.rich_text,
+            checked: fields.checked,
+            children: fields.children.map(create_children),
+            color: fields.color,
+        }
+    }
+}
+
+impl From<ColumnListFields> for ColumnListFields<CreateBlock> {
+    fn from(fields: ColumnListFields) -> Self {
+        ColumnListFields {
+            children: create_children(fields.children),
+        }
+    }
+}
+
+impl From<ColumnFields> for ColumnFields<CreateBlock> {
+    fn from(fields: ColumnFields) -> Self {
+        ColumnFields {
+            children: create_children(fields.children),
+        }
+    }
+}
+
+impl From<TemplateFields> for TemplateFields<CreateBlock> {
+    fn from(fields: TemplateFields) -> Self {
+        TemplateFields {
+            rich_text: fields.rich_text,
+            children: create_children(fields.children),
+        }
+    }
+}
+
+impl From<SyncedBlockFields> for SyncedBlockFields<CreateBlock> {
+    fn from(fields: SyncedBlockFields) -> Self {
+        SyncedBlockFields {
+            synced_from: fields.synced_from,
+            children: create_children(fields.children),
+        }
+    }
+}
+
+impl From<TableFields> for TableFields<CreateBlock> {
+    fn from(fields: TableFields) -> Self {
+        TableFields {
+            table_width: fields.table_width,
+            has_column_header: fields.has_column_header,
+            has_row_header: fields.has_row_header,
+            children: create_children(fields.children),
+        }
+    }
+}
+
+/// <https://developers.notion.com/reference/patch-block-children>
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+pub struct AppendBlockChildrenRequest {
+    pub children: Vec<CreateBlock>,
+    /// Inserts the children after this child instead of at the end.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<BlockId>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum CreateBlock {
     Paragraph {
-        paragraph: TextAndChildren,
+        paragraph: TextAndChildren<CreateBlock>,
     },
     #[serde(rename = "heading_1")]
     Heading1 {
@@ -543,19 +1059,19 @@ pub enum CreateBlock {
         callout: Callout,
     },
     Quote {
-        quote: TextAndChildren,
+        quote: TextAndChildren<CreateBlock>,
     },
     BulletedListItem {
-        bulleted_list_item: TextAndChildren,
+        bulleted_list_item: TextAndChildren<CreateBlock>,
     },
     NumberedListItem {
-        numbered_list_item: TextAndChildren,
+        numbered_list_item: TextAndChildren<CreateBlock>,
     },
     ToDo {
-        to_do: ToDoFields,
+        to_do: ToDoFields<CreateBlock>,
     },
     Toggle {
-        toggle: TextAndChildren,
+        toggle: TextAndChildren<CreateBlock>,
     },
     Code {
         code: CodeFields,
@@ -594,25 +1110,25 @@ pub enum CreateBlock {
     },
     Breadcrumb,
     ColumnList {
-        column_list: ColumnListFields,
+        column_list: ColumnListFields<CreateBlock>,
     },
     Column {
-        column: ColumnFields,
+        column: ColumnFields<CreateBlock>,
     },
     LinkPreview {
         link_preview: LinkPreviewFields,
     },
     Template {
-        template: TemplateFields,
+        template: TemplateFields<CreateBlock>,
     },
     LinkToPage {
         link_to_page: LinkToPageFields,
     },
     Table {
-        table: TableFields,
+        table: TableFields<CreateBlock>,
     },
     SyncedBlock {
-        synced_block: SyncedBlockFields,
+        synced_block: SyncedBlockFields<CreateBlock>,
     },
     TableRow {
         table_row: TableRowFields,
@@ -1,8 +1,10 @@
-use crate::ids::{BlockId, UserId};
+use crate::ids::{AsIdentifier, BlockId, FileUploadId, Identifier, UserId};
 use crate::models::block::{
-    Block, BlockCommon, Callout, ExternalFileObject, FileOrEmojiObject, InternalFileObject,
-    Text as TextBlockModel,
+    map_blocks, to_plain_text, Block, BlockCommon, BlockVisitor, BlockVisitorMut, Callout,
+    CreateBlock, ExternalFileObject, FileObject, FileOrEmojiObject, InternalFileObject,
+    Text as TextBlockModel, TextAndChildren,
 };
+use crate::models::file_uploads::FileUploadReference;
 use crate::models::text::{Annotations, RichText, RichTextCommon, Text, TextColor};
 use crate::models::users::UserCommon;
 use crate::models::Object;
@@ -296,3 +298,184 @@ fn parse_file_or_emoji_object() {
     let _property: Vec<FileOrEmojiObject> =
         serde_json::from_str(include_str!("tests/file_or_emoji_object.json")).unwrap();
 }
+
+#[test]
+fn serialize_image_from_file_upload() {
+    let image = CreateBlock::Image {
+        image: FileObject::FileUpload {
+            file_upload: FileUploadReference {
+                id: FileUploadId::from_str("b52b8ed6-e029-4707-a671-832549c09de3").unwrap(),
+            },
+        },
+    };
+    assert_eq!(
+        serde_json::to_value(image).unwrap(),
+        serde_json::json!({
+            "type": "image",
+            "image": {
+                "type": "file_upload",
+                "file_upload": { "id": "b52b8ed6-e029-4707-a671-832549c09de3" }
+            }
+        })
+    );
+}
+
+fn block_json(
+    kind: &str,
+    fields: serde_json::Value,
+) -> serde_json::Value {
+    let mut block = serde_json::json!({
+        "object": "block",
+        "id": "b8b944b5-cc3d-444e-a25c-a7ddacd528cb",
+        "created_time": "2022-05-13T20:08:00.000Z",
+        "last_edited_time": "2022-05-13T20:08:00.000Z",
+        "created_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+        "last_edited_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+        "has_children": false,
+        "type": kind,
+    });
+    block[kind] = fields;
+    block
+}
+
+fn text_json(content: &str) -> serde_json::Value {
+    serde_json::json!([{ "type": "text", "text": { "content": content, "link": null }, "plain_text": content }])
+}
+
+#[test]
+fn plain_text_of_block_tree() {
+    let row = |cells: Vec<&str>| {
+        block_json(
+            "table_row",
+            serde_json::json!({ "cells": cells.into_iter().map(text_json).collect::<Vec<_>>() }),
+        )
+    };
+    let blocks: Vec<Block> = serde_json::from_value(serde_json::json!([
+        block_json("heading_1", serde_json::json!({ "rich_text": text_json("Title") })),
+        block_json("bulleted_list_item", serde_json::json!({
+            "rich_text": text_json("item"),
+            "color": "default",
+            "children": [
+                block_json("to_do", serde_json::json!({ "rich_text": text_json("task"), "checked": false, "color": "default" }))
+            ]
+        })),
+        block_json("divider", serde_json::json!({})),
+        block_json("code", serde_json::json!({ "rich_text": text_json("a\nb"), "caption": text_json("example"), "language": "rust" })),
+        block_json("table", serde_json::json!({
+            "table_width": 2,
+            "has_column_header": false,
+            "has_row_header": false,
+            "children": [row(vec!["1", "2"])]
+        })),
+        block_json("equation", serde_json::json!({ "expression": "x^2" })),
+    ]))
+    .unwrap();
+
+    assert_eq!(blocks[3].plain_text(), "a\nb\nexample");
+    assert_eq!(blocks[2].plain_text(), "");
+    assert_eq!(
+        to_plain_text(&blocks),
+        "Title\nitem\n  task\na\nb\nexample\n1\t2\nx^2"
+    );
+}
+
+fn nested_blocks() -> Vec<Block> {
+    serde_json::from_value(serde_json::json!([
+        block_json("heading_2", serde_json::json!({ "rich_text": text_json("Title") })),
+        block_json("toggle", serde_json::json!({
+            "rich_text": text_json("toggle"),
+            "color": "red",
+            "children": [
+                block_json("paragraph", serde_json::json!({ "rich_text": text_json("inner"), "color": "blue" }))
+            ]
+        })),
+    ]))
+    .unwrap()
+}
+
+#[test]
+fn generic_accessors() {
+    let mut blocks = nested_blocks();
+
+    assert_eq!(blocks[1].block_type(), "toggle");
+    assert_eq!(blocks[1].children().len(), 1);
+    assert!(blocks[0].children().is_empty());
+    assert!(blocks[0].children_mut().is_none());
+    assert_eq!(blocks[0].rich_text().unwrap()[0].plain_text(), "Title");
+    assert_eq!(Block::Unknown.block_type(), "unknown");
+    assert_eq!(Block::Unknown.as_id().value(), "");
+    assert!(Block::Unknown.children().is_empty());
+}
+
+#[test]
+fn visit_block_tree() {
+    #[derive(Default)]
+    struct Collect {
+        toggles: usize,
+        text: Vec<String>,
+    }
+
+    impl BlockVisitor for Collect {
+        fn visit_toggle(
+            &mut self,
+            _common: &BlockCommon,
+            _toggle: &TextAndChildren,
+        ) {
+            self.toggles += 1;
+        }
+
+        fn visit_rich_text(
+            &mut self,
+            text: &[RichText],
+        ) {
+            self.text
+                .extend(text.iter().map(|run| run.plain_text().to_string()));
+        }
+    }
+
+    let mut collect = Collect::default();
+    collect.visit_blocks(&nested_blocks());
+
+    assert_eq!(collect.toggles, 1);
+    assert_eq!(collect.text, vec!["Title", "toggle", "inner"]);
+}
+
+#[test]
+fn transform_block_tree() {
+    struct StripColors;
+
+    impl BlockVisitorMut for StripColors {
+        fn visit_toggle(
+            &mut self,
+            _common: &mut BlockCommon,
+            toggle: &mut TextAndChildren,
+        ) {
+            toggle.color = TextColor::Default;
+        }
+
+        fn visit_paragraph(
+            &mut self,
+            _common: &mut BlockCommon,
+            paragraph: &mut TextAndChildren,
+        ) {
+            paragraph.color = TextColor::Default;
+        }
+    }
+
+    let mut blocks = nested_blocks();
+    StripColors.visit_blocks_mut(&mut blocks);
+    let json = serde_json::to_value(&blocks).unwrap();
+    assert_eq!(json[1]["toggle"]["color"], "default");
+    assert_eq!(
+        json[1]["toggle"]["children"][0]["paragraph"]["color"],
+        "default"
+    );
+
+    let blocks = map_blocks(blocks, &mut |mut block| {
+        if let Some(text) = block.rich_text_mut() {
+            *text = vec![RichText::text("replaced")];
+        }
+        block
+    });
+    assert_eq!(to_plain_text(&blocks), "replaced\nreplaced\n  replaced");
+}
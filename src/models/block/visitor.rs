@@ -0,0 +1,157 @@
+//! Traversal of block trees without matching every block type by hand.
+
+use crate::models::block::{
+    Block, BlockCommon, BookmarkFields, Callout, ChildDatabaseFields, ChildPageFields, CodeFields,
+    ColumnFields, ColumnListFields, EmbedFields, Equation, FileObject, LinkPreviewFields,
+    LinkToPageFields, SyncedBlockFields, TableFields, TableOfContents, TableRowFields,
+    TemplateFields, Text, TextAndChildren, ToDoFields,
+};
+use crate::models::text::RichText;
+
+macro_rules! visitors {
+    ($($hook:ident => $variant:ident { $($field:ident: $fields:ty),* }),* $(,)?) => {
+        /// Visits the blocks of a tree, see [walk_block()] for the order of the calls.
+        ///
+        /// Every hook does nothing by default, override the ones for the blocks of interest.
+        /// Override [visit_block()](Self::visit_block()) to skip the children of some blocks.
+        pub trait BlockVisitor {
+            /// Visits `blocks` and their children in order.
+            fn visit_blocks(&mut self, blocks: &[Block]) {
+                for block in blocks {
+                    self.visit_block(block);
+                }
+            }
+
+            /// Visits a single block and its children, see [walk_block()].
+            fn visit_block(&mut self, block: &Block) {
+                walk_block(self, block);
+            }
+
+            /// Called for every rich text field of a block, after the hook of its type.
+            fn visit_rich_text(&mut self, _text: &[RichText]) {}
+
+            $(
+                #[doc = concat!("Called for [Block::", stringify!($variant), "].")]
+                #[allow(unused_variables)]
+                fn $hook(&mut self, common: &BlockCommon, $($field: &$fields),*) {}
+            )*
+
+            /// Called for [Block::Unknown].
+            fn visit_unknown(&mut self) {}
+        }
+
+        /// Like [BlockVisitor], but with mutable access to change blocks in place.
+        pub trait BlockVisitorMut {
+            /// Visits `blocks` and their children in order.
+            fn visit_blocks_mut(&mut self, blocks: &mut [Block]) {
+                for block in blocks {
+                    self.visit_block_mut(block);
+                }
+            }
+
+            /// Visits a single block and its children, see [walk_block_mut()].
+            fn visit_block_mut(&mut self, block: &mut Block) {
+                walk_block_mut(self, block);
+            }
+
+            /// Called for every rich text field of a block, after the hook of its type.
+            fn visit_rich_text_mut(&mut self, _text: &mut Vec<RichText>) {}
+
+            $(
+                #[doc = concat!("Called for [Block::", stringify!($variant), "].")]
+                #[allow(unused_variables)]
+                fn $hook(&mut self, common: &mut BlockCommon, $($field: &mut $fields),*) {}
+            )*
+
+            /// Called for [Block::Unknown].
+            fn visit_unknown(&mut self) {}
+        }
+
+        /// The default walk of [BlockVisitor::visit_block()]: calls the hook of the block's type,
+        /// then [visit_rich_text()](BlockVisitor::visit_rich_text()) for each of its rich text
+        /// fields and finally [visit_block()](BlockVisitor::visit_block()) for each child.
+        pub fn walk_block<V: BlockVisitor + ?Sized>(visitor: &mut V, block: &Block) {
+            match block {
+                $(Block::$variant { common, $($field),* } => {
+                    BlockVisitor::$hook(visitor, common, $($field),*)
+                })*
+                Block::Unknown => BlockVisitor::visit_unknown(visitor),
+            }
+            for text in block.text_fields() {
+                visitor.visit_rich_text(text);
+            }
+            visitor.visit_blocks(block.children());
+        }
+
+        /// The default walk of [BlockVisitorMut::visit_block_mut()], in the same order as
+        /// [walk_block()].
+        pub fn walk_block_mut<V: BlockVisitorMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+            match block {
+                $(Block::$variant { common, $($field),* } => {
+                    BlockVisitorMut::$hook(visitor, common, $($field),*)
+                })*
+                Block::Unknown => BlockVisitorMut::visit_unknown(visitor),
+            }
+            for text in block.text_fields_mut() {
+                visitor.visit_rich_text_mut(text);
+            }
+            if let Some(children) = block.children_mut() {
+                visitor.visit_blocks_mut(children);
+            }
+        }
+    };
+}
+
+visitors! {
+    visit_paragraph => Paragraph { paragraph: TextAndChildren },
+    visit_heading_1 => Heading1 { heading_1: Text },
+    visit_heading_2 => Heading2 { heading_2: Text },
+    visit_heading_3 => Heading3 { heading_3: Text },
+    visit_callout => Callout { callout: Callout },
+    visit_quote => Quote { quote: TextAndChildren },
+    visit_bulleted_list_item => BulletedListItem { bulleted_list_item: TextAndChildren },
+    visit_numbered_list_item => NumberedListItem { numbered_list_item: TextAndChildren },
+    visit_to_do => ToDo { to_do: ToDoFields },
+    visit_toggle => Toggle { toggle: TextAndChildren },
+    visit_code => Code { code: CodeFields },
+    visit_child_page => ChildPage { child_page: ChildPageFields },
+    visit_child_database => ChildDatabase { child_database: ChildDatabaseFields },
+    visit_embed => Embed { embed: EmbedFields },
+    visit_image => Image { image: FileObject },
+    visit_video => Video { video: FileObject },
+    visit_file => File { file: FileObject, caption: Text },
+    visit_pdf => Pdf { pdf: FileObject },
+    visit_bookmark => Bookmark { bookmark: BookmarkFields },
+    visit_equation => Equation { equation: Equation },
+    visit_divider => Divider {},
+    visit_table_of_contents => TableOfContents { table_of_contents: TableOfContents },
+    visit_breadcrumb => Breadcrumb {},
+    visit_column_list => ColumnList { column_list: ColumnListFields },
+    visit_column => Column { column: ColumnFields },
+    visit_link_preview => LinkPreview { link_preview: LinkPreviewFields },
+    visit_template => Template { template: TemplateFields },
+    visit_link_to_page => LinkToPage { link_to_page: LinkToPageFields },
+    visit_table => Table { table: TableFields },
+    visit_synced_block => SyncedBlock { synced_block: SyncedBlockFields },
+    visit_table_row => TableRow { table_row: TableRowFields },
+    visit_unsupported => Unsupported {},
+}
+
+/// Replaces every block of a tree with the result of `f`.
+///
+/// Children are mapped before their parent, so `f` sees the already mapped children of a
+/// block and can also replace a block with one of another type.
+pub fn map_blocks<F: FnMut(Block) -> Block>(
+    blocks: Vec<Block>,
+    f: &mut F,
+) -> Vec<Block> {
+    blocks
+        .into_iter()
+        .map(|mut block| {
+            if let Some(children) = block.children_mut() {
+                *children = map_blocks(std::mem::take(children), f);
+            }
+            f(block)
+        })
+        .collect()
+}
@@ -0,0 +1,353 @@
+//! Changes between two versions of a page, see [Page::diff()].
+
+use crate::models::block::FileOrEmojiObject;
+use crate::models::properties::{FileReference, PropertyValue, RelationValue, SelectedValue};
+use crate::models::text::RichText;
+use crate::models::users::User;
+use crate::models::Page;
+use std::collections::BTreeSet;
+
+/// A difference between two versions of a page.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum PropertyChange {
+    /// The value of a property changed. `old` or `new` is `None` if the property was added
+    /// or removed.
+    Property {
+        name: String,
+        old: Option<PropertyValue>,
+        new: Option<PropertyValue>,
+    },
+    Archived {
+        old: bool,
+        new: bool,
+    },
+    Icon {
+        old: Option<FileOrEmojiObject>,
+        new: Option<FileOrEmojiObject>,
+    },
+}
+
+impl Page {
+    /// The changes from this page to `newer`, a later fetch of the same page.
+    ///
+    /// Values are compared by what they mean rather than how Notion returned them: rich text
+    /// ignores `plain_text` and missing annotations, relations, people and multi-selects are
+    /// compared as sets and Notion-hosted files by their url without the expiring signature.
+    /// Property changes come first, sorted by name.
+    pub fn diff(
+        &self,
+        newer: &Page,
+    ) -> Vec<PropertyChange> {
+        let names: BTreeSet<&String> = self
+            .properties
+            .properties
+            .keys()
+            .chain(newer.properties.properties.keys())
+            .collect();
+        let mut changes: Vec<PropertyChange> = names
+            .into_iter()
+            .filter_map(|name| {
+                let old = self.properties.properties.get(name);
+                let new = newer.properties.properties.get(name);
+                let unchanged = match (old, new) {
+                    (Some(old), Some(new)) => same_value(old, new),
+                    (old, new) => old.is_none() && new.is_none(),
+                };
+                if unchanged {
+                    None
+                } else {
+                    Some(PropertyChange::Property {
+                        name: name.clone(),
+                        old: old.cloned(),
+                        new: new.cloned(),
+                    })
+                }
+            })
+            .collect();
+
+        if self.archived != newer.archived {
+            changes.push(PropertyChange::Archived {
+                old: self.archived,
+                new: newer.archived,
+            });
+        }
+        if !same_icon(&self.icon, &newer.icon) {
+            changes.push(PropertyChange::Icon {
+                old: self.icon.clone(),
+                new: newer.icon.clone(),
+            });
+        }
+        changes
+    }
+}
+
+fn same_value(
+    old: &PropertyValue,
+    new: &PropertyValue,
+) -> bool {
+    use PropertyValue::*;
+    match (old, new) {
+        (Title { title: old, .. }, Title { title: new, .. }) => same_text(old, new),
+        (Text { rich_text: old, .. }, Text { rich_text: new, .. }) => same_text(old, new),
+        (Select { select: old, .. }, Select { select: new, .. })
+        | (Status { status: old, .. }, Status { status: new, .. }) => {
+            old.as_ref().map(option_name) == new.as_ref().map(option_name)
+        }
+        (
+            MultiSelect {
+                multi_select: old, ..
+            },
+            MultiSelect {
+                multi_select: new, ..
+            },
+        ) => {
+            let names = |options: &Option<Vec<SelectedValue>>| -> BTreeSet<String> {
+                options.iter().flatten().map(option_name).collect()
+            };
+            names(old) == names(new)
+        }
+        (Relation { relation: old, .. }, Relation { relation: new, .. }) => {
+            let ids = |relation: &Option<Vec<RelationValue>>| -> BTreeSet<String> {
+                relation
+                    .iter()
+                    .flatten()
+                    .map(|page| page.id.to_string())
+                    .collect()
+            };
+            ids(old) == ids(new)
+        }
+        (People { people: old, .. }, People { people: new, .. }) => user_ids(old) == user_ids(new),
+        (
+            CreatedBy {
+                created_by: old, ..
+            },
+            CreatedBy {
+                created_by: new, ..
+            },
+        )
+        | (
+            LastEditedBy {
+                last_edited_by: old,
+                ..
+            },
+            LastEditedBy {
+                last_edited_by: new,
+                ..
+            },
+        ) => old.id() == new.id(),
+        (Files { files: old, .. }, Files { files: new, .. }) => {
+            let files = |files: &Option<Vec<FileReference>>| -> Vec<(String, String)> {
+                files.iter().flatten().map(file_key).collect()
+            };
+            files(old) == files(new)
+        }
+        (old, new) => old == new,
+    }
+}
+
+fn same_text(
+    old: &[RichText],
+    new: &[RichText],
+) -> bool {
+    merged_runs(old) == merged_runs(new)
+}
+
+/// The normalized runs of `text`, adjacent text runs with the same annotations and link merged
+/// into one, as Notion merges and splits them on its own.
+fn merged_runs(text: &[RichText]) -> Vec<RichText> {
+    let mut runs: Vec<RichText> = vec![];
+    for run in text.iter().map(RichText::normalized) {
+        if let (
+            Some(RichText::Text {
+                rich_text: previous_common,
+                text: previous,
+            }),
+            RichText::Text { rich_text, text },
+        ) = (runs.last_mut(), &run)
+        {
+            if previous_common == rich_text && previous.link == text.link {
+                previous.content.push_str(&text.content);
+                continue;
+            }
+        }
+        runs.push(run);
+    }
+    runs
+}
+
+/// Option names are unique within a property, unlike ids they are also set in requests.
+fn option_name(option: &SelectedValue) -> String {
+    option.name.clone().unwrap_or_default()
+}
+
+fn user_ids(users: &[User]) -> BTreeSet<String> {
+    users.iter().map(|user| user.id().to_string()).collect()
+}
+
+fn file_key(file: &FileReference) -> (String, String) {
+    match file {
+        FileReference::External { name, external } => (name.clone(), external.url.clone()),
+        FileReference::File { name, file } => (name.clone(), without_signature(&file.url)),
+        FileReference::FileUpload { name, file_upload } => {
+            (name.clone(), file_upload.id.to_string())
+        }
+    }
+}
+
+fn same_icon(
+    old: &Option<FileOrEmojiObject>,
+    new: &Option<FileOrEmojiObject>,
+) -> bool {
+    match (old, new) {
+        (
+            Some(FileOrEmojiObject::File { file: old }),
+            Some(FileOrEmojiObject::File { file: new }),
+        ) => without_signature(&old.url) == without_signature(&new.url),
+        (old, new) => old == new,
+    }
+}
+
+/// Notion-hosted files get a new signed url on every fetch, the path stays the same.
+fn without_signature(url: &str) -> String {
+    url.split('?').next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PropertyChange;
+    use crate::models::Page;
+    use serde_json::{json, Value};
+
+    fn page(
+        archived: bool,
+        properties: Value,
+    ) -> Page {
+        serde_json::from_value(json!({
+            "object": "page",
+            "id": "b8b944b5-cc3d-444e-a25c-a7ddacd528cb",
+            "created_time": "2022-05-13T20:08:00.000Z",
+            "last_edited_time": "2022-05-13T20:08:00.000Z",
+            "archived": archived,
+            "icon": null,
+            "cover": null,
+            "parent": { "type": "workspace", "workspace": true },
+            "properties": properties,
+        }))
+        .unwrap()
+    }
+
+    fn text(content: &str) -> Value {
+        json!({
+            "type": "text",
+            "text": { "content": content, "link": null },
+            "plain_text": content,
+        })
+    }
+
+    fn relation(ids: &[&str]) -> Value {
+        json!({
+            "id": "rel",
+            "type": "relation",
+            "relation": ids.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+        })
+    }
+
+    const A: &str = "00000000-0000-0000-0000-000000000001";
+    const B: &str = "00000000-0000-0000-0000-000000000002";
+
+    #[test]
+    fn ignores_representation_differences() {
+        let mut annotated = text("Title");
+        annotated["annotations"] = json!({
+            "bold": false, "italic": false, "strikethrough": false,
+            "underline": false, "code": false, "color": "default"
+        });
+        annotated["plain_text"] = json!("stale");
+        let old = page(
+            false,
+            json!({
+                "Name": { "id": "title", "type": "title", "title": [text("Title")] },
+                "Related": relation(&[A, B]),
+            }),
+        );
+        let new = page(
+            false,
+            json!({
+                "Name": { "id": "title", "type": "title", "title": [annotated] },
+                "Related": relation(&[B, A]),
+            }),
+        );
+
+        assert_eq!(old.diff(&new), vec![]);
+    }
+
+    #[test]
+    fn merges_split_text_runs() {
+        let title = |runs: Vec<Value>| {
+            page(
+                false,
+                json!({ "Name": { "id": "title", "type": "title", "title": runs } }),
+            )
+        };
+        let mut bold = text("tle");
+        bold["annotations"] = json!({ "bold": true });
+
+        assert_eq!(
+            title(vec![text("Ti"), text("tle")]).diff(&title(vec![text("Title")])),
+            vec![]
+        );
+        assert_eq!(
+            title(vec![text("Ti"), bold])
+                .diff(&title(vec![text("Title")]))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn reports_changes() {
+        let old = page(
+            false,
+            json!({
+                "Name": { "id": "title", "type": "title", "title": [text("Old")] },
+                "Related": relation(&[A]),
+                "Done": { "id": "done", "type": "checkbox", "checkbox": false },
+            }),
+        );
+        let new = page(
+            true,
+            json!({
+                "Name": { "id": "title", "type": "title", "title": [text("New")] },
+                "Related": relation(&[A, B]),
+                "Done": { "id": "done", "type": "checkbox", "checkbox": false },
+                "Added": { "id": "url", "type": "url", "url": "https://example.com" },
+            }),
+        );
+
+        let changes = old.diff(&new);
+        let names: Vec<&str> = changes
+            .iter()
+            .filter_map(|change| match change {
+                PropertyChange::Property { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, vec!["Added", "Name", "Related"]);
+        assert!(matches!(
+            &changes[0],
+            PropertyChange::Property {
+                old: None,
+                new: Some(_),
+                ..
+            }
+        ));
+        assert_eq!(
+            changes[3],
+            PropertyChange::Archived {
+                old: false,
+                new: true
+            }
+        );
+        assert_eq!(changes.len(), 4);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::ids::{AsIdentifier, FileUploadId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Files up to this size are sent in a single request.
+pub const SINGLE_PART_MAX_SIZE: u64 = 20 * 1024 * 1024;
+/// Size of every part of a multi part upload except the last one.
+/// Notion requires parts between 5MB and 20MB.
+pub const MULTI_PART_CHUNK_SIZE: u64 = 10 * 1024 * 1024;
+
+/// How the content of a file upload is sent to Notion.
+/// See <https://developers.notion.com/reference/create-a-file-upload>
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum FileUploadMode {
+    /// The whole file is sent in a single request (up to 20MB).
+    SinglePart,
+    /// The file is split into parts that are sent one after another.
+    /// The upload has to be completed once all parts are sent.
+    MultiPart,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum FileUploadStatus {
+    Pending,
+    Uploaded,
+    Expired,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub struct FileUploadParts {
+    pub total: u32,
+    pub sent: u32,
+}
+
+/// Represents a Notion File Upload
+/// See <https://developers.notion.com/reference/file-upload>
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct FileUpload {
+    pub id: FileUploadId,
+    pub created_time: DateTime<Utc>,
+    pub last_edited_time: DateTime<Utc>,
+    /// Uploads that are not attached to a block or page before this time are discarded.
+    pub expiry_time: Option<DateTime<Utc>>,
+    pub status: FileUploadStatus,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    pub upload_url: Option<String>,
+    /// Only present for [FileUploadMode::MultiPart] uploads.
+    pub number_of_parts: Option<FileUploadParts>,
+}
+
+impl AsIdentifier<FileUploadId> for FileUpload {
+    fn as_id(&self) -> &FileUploadId {
+        &self.id
+    }
+}
+
+/// <https://developers.notion.com/reference/create-a-file-upload>
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+pub struct CreateFileUploadRequest {
+    pub mode: FileUploadMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Required for [FileUploadMode::MultiPart] uploads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_of_parts: Option<u32>,
+}
+
+/// The content sent with a single [send_file_upload()](crate::NotionApi::send_file_upload()) request.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FileUploadPart {
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+    /// The 1-based index of this part, only set for [FileUploadMode::MultiPart] uploads.
+    pub part_number: Option<u32>,
+}
+
+/// References a completed [FileUpload] from a block, page icon, page cover or files property.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct FileUploadReference {
+    pub id: FileUploadId,
+}
+
+impl From<&FileUpload> for FileUploadReference {
+    fn from(upload: &FileUpload) -> Self {
+        FileUploadReference {
+            id: upload.id.clone(),
+        }
+    }
+}
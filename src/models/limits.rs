@@ -0,0 +1,535 @@
+//! Size limits Notion enforces on request bodies.
+//!
+//! Requests exceeding a limit are rejected with a validation error, see
+//! <https://developers.notion.com/reference/request-limits#size-limits>.
+//! [check_limits()] reports every violation of a request up front, while [split_long_text()],
+//! [fit_blocks()] and [split_blocks()] reshape content so that it fits.
+//!
+//! Lengths are counted in UTF-16 code units like Notion does, so a character outside the
+//! basic multilingual plane (e.g. most emoji) counts twice.
+
+use crate::models::block::CreateBlock;
+use crate::models::properties::{WriteProperties, WritePropertyValue};
+use crate::models::text::RichText;
+use crate::models::PageCreateRequest;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+/// Maximum length of the content of a text object.
+pub const MAX_TEXT_LENGTH: usize = 2000;
+/// Maximum length of any URL.
+pub const MAX_URL_LENGTH: usize = 2000;
+/// Maximum length of an equation expression.
+pub const MAX_EQUATION_LENGTH: usize = 1000;
+/// Maximum length of email addresses and phone numbers.
+pub const MAX_CONTACT_LENGTH: usize = 200;
+/// Maximum number of elements of any array, e.g. rich text runs or children of a block.
+pub const MAX_ARRAY_LENGTH: usize = 100;
+/// Maximum number of blocks in a request, counting nested children.
+pub const MAX_BLOCKS_PER_REQUEST: usize = 1000;
+/// Maximum size of the serialized request body in bytes.
+pub const MAX_PAYLOAD_SIZE: usize = 500_000;
+/// Maximum levels of nested children in a request: the blocks of a request can have children,
+/// which can have children of their own.
+pub const MAX_NESTING_DEPTH: usize = 2;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    #[error("{path} is {length} characters long, the limit is {limit}")]
+    TooLong {
+        path: String,
+        length: usize,
+        limit: usize,
+    },
+
+    #[error("{path} has {length} elements, the limit is {limit}")]
+    TooManyElements {
+        path: String,
+        length: usize,
+        limit: usize,
+    },
+
+    #[error("The request contains {count} blocks, the limit is {limit}")]
+    TooManyBlocks { count: usize, limit: usize },
+
+    #[error("The request body is {size} bytes, the limit is {limit}")]
+    PayloadTooLarge { size: usize, limit: usize },
+}
+
+/// Checks a request body against Notion's size limits and returns every violation.
+///
+/// Paths of violations follow the JSON body, e.g. `children[3].paragraph.rich_text[0].text.content`.
+pub fn check_limits<T: Serialize>(request: &T) -> Result<(), Vec<LimitError>> {
+    let value = serde_json::to_value(request).expect("request bodies serialize to JSON");
+    let mut errors = vec![];
+    check_value(&value, &mut String::new(), &mut errors);
+
+    let count = count_blocks(&value);
+    if count > MAX_BLOCKS_PER_REQUEST {
+        errors.push(LimitError::TooManyBlocks {
+            count,
+            limit: MAX_BLOCKS_PER_REQUEST,
+        });
+    }
+    let size = value.to_string().len();
+    if size > MAX_PAYLOAD_SIZE {
+        errors.push(LimitError::PayloadTooLarge {
+            size,
+            limit: MAX_PAYLOAD_SIZE,
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn string_limit(key: &str) -> Option<usize> {
+    match key {
+        "content" => Some(MAX_TEXT_LENGTH),
+        "url" => Some(MAX_URL_LENGTH),
+        "expression" => Some(MAX_EQUATION_LENGTH),
+        "email" | "phone_number" => Some(MAX_CONTACT_LENGTH),
+        _ => None,
+    }
+}
+
+fn check_value(
+    value: &Value,
+    path: &mut String,
+    errors: &mut Vec<LimitError>,
+) {
+    let prefix = path.len();
+    match value {
+        Value::Array(elements) => {
+            if elements.len() > MAX_ARRAY_LENGTH {
+                errors.push(LimitError::TooManyElements {
+                    path: path.clone(),
+                    length: elements.len(),
+                    limit: MAX_ARRAY_LENGTH,
+                });
+            }
+            for (index, element) in elements.iter().enumerate() {
+                path.push_str(&format!("[{}]", index));
+                check_value(element, path, errors);
+                path.truncate(prefix);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                match (string_limit(key), field) {
+                    (Some(limit), Value::String(string)) if utf16_len(string) > limit => errors
+                        .push(LimitError::TooLong {
+                            path: path.clone(),
+                            length: utf16_len(string),
+                            limit,
+                        }),
+                    _ => check_value(field, path, errors),
+                }
+                path.truncate(prefix);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Counts the blocks in all `children` arrays of a request body.
+fn count_blocks(value: &Value) -> usize {
+    match value {
+        Value::Array(elements) => elements.iter().map(count_blocks).sum(),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, field)| match field {
+                Value::Array(children) if key == "children" => children.len() + count_blocks(field),
+                _ => count_blocks(field),
+            })
+            .sum(),
+        _ => 0,
+    }
+}
+
+fn utf16_len(string: &str) -> usize {
+    string.encode_utf16().count()
+}
+
+/// Splits text runs longer than [MAX_TEXT_LENGTH] into several runs with the same annotations
+/// and link. Mentions and equations are kept as they are.
+pub fn split_long_text(text: &mut Vec<RichText>) {
+    if !text.iter().any(is_too_long) {
+        return;
+    }
+    *text = std::mem::take(text)
+        .into_iter()
+        .flat_map(|run| match run {
+            RichText::Text {
+                rich_text,
+                text: content,
+            } if utf16_len(&content.content) > MAX_TEXT_LENGTH => chunks(&content.content)
+                .into_iter()
+                .map(|chunk| {
+                    let mut rich_text = rich_text.clone();
+                    rich_text.plain_text = chunk.clone();
+                    let mut text = content.clone();
+                    text.content = chunk;
+                    RichText::Text { rich_text, text }
+                })
+                .collect(),
+            run => vec![run],
+        })
+        .collect();
+}
+
+fn is_too_long(run: &RichText) -> bool {
+    matches!(run, RichText::Text { text, .. } if utf16_len(&text.content) > MAX_TEXT_LENGTH)
+}
+
+/// Splits `content` on character boundaries into chunks of at most [MAX_TEXT_LENGTH].
+fn chunks(content: &str) -> Vec<String> {
+    let mut chunks = vec![];
+    let mut chunk = String::new();
+    let mut length = 0;
+    for character in content.chars() {
+        if length + character.len_utf16() > MAX_TEXT_LENGTH {
+            chunks.push(std::mem::take(&mut chunk));
+            length = 0;
+        }
+        chunk.push(character);
+        length += character.len_utf16();
+    }
+    chunks.push(chunk);
+    chunks
+}
+
+/// Splits long text in `blocks` and all their nested children, see [split_long_text()].
+pub fn fit_blocks(blocks: &mut [CreateBlock]) {
+    for block in blocks {
+        block
+            .text_fields_mut()
+            .into_iter()
+            .for_each(split_long_text);
+        if let Some(children) = block.nested_mut() {
+            fit_blocks(children);
+        }
+    }
+}
+
+/// Splits long text in title and text properties, see [split_long_text()].
+pub fn fit_properties(properties: &mut WriteProperties) {
+    for value in properties.properties.values_mut() {
+        match value {
+            WritePropertyValue::Title { title: text }
+            | WritePropertyValue::Text { rich_text: text } => split_long_text(text),
+            _ => {}
+        }
+    }
+}
+
+/// Children [split_blocks()] cut off from a block of a batch, to be appended to the block once
+/// it was created.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DeferredChildren {
+    /// The indices leading from the blocks of the batch down to the parent of `children`,
+    /// e.g. `[3, 0]` for the first child of the fourth block.
+    pub path: Vec<usize>,
+    pub children: Vec<CreateBlock>,
+}
+
+/// Blocks that can be sent in a single request, see [split_blocks()].
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct BlockBatch {
+    pub blocks: Vec<CreateBlock>,
+    /// Nested children that didn't fit into the request,
+    /// see [NotionApi::append_deferred_children()](crate::NotionApi::append_deferred_children()).
+    pub deferred: Vec<DeferredChildren>,
+}
+
+/// Splits `blocks` into batches that can each be sent in a single request: at most
+/// [MAX_ARRAY_LENGTH] blocks with at most [MAX_BLOCKS_PER_REQUEST] blocks including their
+/// nested children.
+///
+/// Nested children beyond [MAX_ARRAY_LENGTH] per block, [MAX_NESTING_DEPTH] levels or
+/// [MAX_BLOCKS_PER_REQUEST] per block are cut off and listed in [BlockBatch::deferred].
+pub fn split_blocks(blocks: Vec<CreateBlock>) -> Vec<BlockBatch> {
+    let mut batches = vec![];
+    let mut batch = BlockBatch::default();
+    let mut count = 0;
+    for mut block in blocks {
+        let mut deferred = vec![];
+        let mut budget = MAX_BLOCKS_PER_REQUEST - 1;
+        defer_children(&mut block, &mut vec![0], &mut budget, &mut deferred);
+        let size = MAX_BLOCKS_PER_REQUEST - budget;
+        if !batch.blocks.is_empty()
+            && (batch.blocks.len() == MAX_ARRAY_LENGTH || count + size > MAX_BLOCKS_PER_REQUEST)
+        {
+            batches.push(std::mem::take(&mut batch));
+            count = 0;
+        }
+        let index = batch.blocks.len();
+        batch
+            .deferred
+            .extend(deferred.into_iter().map(|mut deferred| {
+                deferred.path[0] = index;
+                deferred
+            }));
+        batch.blocks.push(block);
+        count += size;
+    }
+    if !batch.blocks.is_empty() {
+        batches.push(batch);
+    }
+    batches
+}
+
+/// Keeps as many nested children of `block` as a request allows and moves the rest into
+/// `deferred`. `path` leads to `block`, `budget` is the number of nested blocks still allowed.
+fn defer_children(
+    block: &mut CreateBlock,
+    path: &mut Vec<usize>,
+    budget: &mut usize,
+    deferred: &mut Vec<DeferredChildren>,
+) {
+    let children = match block.nested_mut() {
+        Some(children) => children,
+        None => return,
+    };
+    let mut kept = 0;
+    while kept < children.len()
+        && kept < MAX_ARRAY_LENGTH
+        && *budget > 0
+        && path.len() <= MAX_NESTING_DEPTH
+    {
+        *budget -= 1;
+        path.push(kept);
+        defer_children(&mut children[kept], path, budget, deferred);
+        path.pop();
+        kept += 1;
+    }
+    if kept < children.len() {
+        deferred.push(DeferredChildren {
+            path: path.clone(),
+            children: children.split_off(kept),
+        });
+    }
+}
+
+/// Checks a batch of [split_blocks()] for arrays longer than [MAX_ARRAY_LENGTH].
+///
+/// Batches keep to the limits of children, but splitting long text can push a rich text array
+/// past the limit, which has to be reported before sending the batch.
+pub(crate) fn check_arrays(blocks: &[CreateBlock]) -> Result<(), Vec<LimitError>> {
+    let errors: Vec<LimitError> = check_limits(&blocks)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|error| matches!(error, LimitError::TooManyElements { .. }))
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Children left out of a page by [PageCreateRequest::fit_limits()].
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct RemainingChildren {
+    /// Children cut off from the blocks sent with the page, the paths start at the children
+    /// of the page.
+    pub deferred: Vec<DeferredChildren>,
+    /// Further batches, to be appended to the page in order.
+    pub batches: Vec<BlockBatch>,
+}
+
+impl PageCreateRequest {
+    /// Splits long text in the properties and children of the page and keeps only the first
+    /// batch of children, see [split_blocks()].
+    ///
+    /// Returns what was left out, to be appended after the page was created.
+    pub fn fit_limits(&mut self) -> RemainingChildren {
+        fit_properties(&mut self.properties);
+        let mut children = match self.children.take() {
+            Some(children) => children,
+            None => return RemainingChildren::default(),
+        };
+        fit_blocks(&mut children);
+        let mut batches = split_blocks(children).into_iter();
+        let first = batches.next().unwrap_or_default();
+        self.children = Some(first.blocks);
+        RemainingChildren {
+            deferred: first.deferred,
+            batches: batches.collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::block::TextAndChildren;
+    use crate::models::text::TextColor;
+
+    fn paragraph(content: &str) -> CreateBlock {
+        CreateBlock::Paragraph {
+            paragraph: TextAndChildren {
+                rich_text: vec![RichText::text(content)],
+                children: None,
+                color: TextColor::Default,
+            },
+        }
+    }
+
+    #[test]
+    fn reports_paths_of_violations() {
+        let request = crate::models::block::AppendBlockChildrenRequest {
+            children: vec![paragraph("short"), paragraph(&"a".repeat(2001))],
+            after: None,
+        };
+
+        assert_eq!(
+            check_limits(&request),
+            Err(vec![LimitError::TooLong {
+                path: "children[1].paragraph.rich_text[0].text.content".to_string(),
+                length: 2001,
+                limit: MAX_TEXT_LENGTH,
+            }])
+        );
+    }
+
+    #[test]
+    fn splits_long_text_keeping_annotations() {
+        let mut text = vec![RichText::text("😀".repeat(2500))
+            .bold()
+            .link("https://example.com")];
+        split_long_text(&mut text);
+
+        assert_eq!(text.len(), 3);
+        for run in &text {
+            match run {
+                RichText::Text { rich_text, text } => {
+                    assert!(utf16_len(&text.content) <= MAX_TEXT_LENGTH);
+                    assert_eq!(rich_text.plain_text, text.content);
+                    assert_eq!(rich_text.annotations.as_ref().unwrap().bold, Some(true));
+                    assert!(text.link.is_some());
+                }
+                other => panic!("Unexpected rich text {:?}", other),
+            }
+        }
+        assert_eq!(
+            text.iter().map(RichText::plain_text).collect::<String>(),
+            "😀".repeat(2500)
+        );
+    }
+
+    #[test]
+    fn splits_blocks_into_batches() {
+        let blocks: Vec<_> = (0..250).map(|n| paragraph(&n.to_string())).collect();
+        let batches = split_blocks(blocks);
+
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| batch.blocks.len())
+                .collect::<Vec<_>>(),
+            vec![100, 100, 50]
+        );
+        assert!(batches
+            .iter()
+            .all(|batch| check_limits(&batch.blocks).is_ok()));
+    }
+
+    #[test]
+    fn counts_nested_blocks() {
+        let nested = || {
+            let mut block = paragraph("parent");
+            if let CreateBlock::Paragraph { paragraph } = &mut block {
+                paragraph.children = Some(vec![CreateBlock::Divider; 99]);
+            }
+            block
+        };
+        let batches = split_blocks((0..20).map(|_| nested()).collect());
+
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| batch.blocks.len())
+                .collect::<Vec<_>>(),
+            vec![10, 10]
+        );
+    }
+
+    fn toggle(children: Vec<CreateBlock>) -> CreateBlock {
+        CreateBlock::Toggle {
+            toggle: TextAndChildren {
+                rich_text: vec![RichText::text("toggle")],
+                children: Some(children),
+                color: TextColor::Default,
+            },
+        }
+    }
+
+    #[test]
+    fn defers_nested_children_beyond_the_limits() {
+        let children = (0..150).map(|n| paragraph(&n.to_string())).collect();
+        let batches = split_blocks(vec![paragraph("before"), toggle(children)]);
+
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert!(check_limits(&batch.blocks).is_ok());
+        assert_eq!(batch.blocks[1].nested().map(Vec::len), Some(100));
+        assert_eq!(batch.deferred.len(), 1);
+        assert_eq!(batch.deferred[0].path, vec![1]);
+        assert_eq!(
+            batch.deferred[0].children,
+            (100..150)
+                .map(|n| paragraph(&n.to_string()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reports_too_many_text_runs() {
+        let mut blocks = vec![paragraph(&"a".repeat(MAX_TEXT_LENGTH * 101))];
+        fit_blocks(&mut blocks);
+
+        assert_eq!(
+            check_arrays(&blocks),
+            Err(vec![LimitError::TooManyElements {
+                path: "[0].paragraph.rich_text".to_string(),
+                length: 101,
+                limit: MAX_ARRAY_LENGTH,
+            }])
+        );
+    }
+
+    #[test]
+    fn defers_deep_and_large_subtrees() {
+        let deep = toggle(vec![toggle(vec![toggle(vec![paragraph("deep")])])]);
+        let batches = split_blocks(vec![deep]);
+        assert_eq!(batches[0].deferred[0].path, vec![0, 0, 0]);
+        assert_eq!(batches[0].deferred[0].children, vec![paragraph("deep")]);
+
+        let large = toggle(
+            (0..20)
+                .map(|_| toggle(vec![CreateBlock::Divider; 60]))
+                .collect(),
+        );
+        let batches = split_blocks(vec![large]);
+        assert!(check_limits(&batches[0].blocks).is_ok());
+        let deferred: usize = batches[0]
+            .deferred
+            .iter()
+            .map(|deferred| {
+                let value = serde_json::to_value(&deferred.children).unwrap();
+                deferred.children.len() + count_blocks(&value)
+            })
+            .sum();
+        assert_eq!(deferred, 20 * 61 - (MAX_BLOCKS_PER_REQUEST - 1));
+    }
+}
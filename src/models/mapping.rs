@@ -0,0 +1,618 @@
+//! Mapping between Rust structs and database pages.
+//!
+//! Implement [NotionPage] with `#[derive(NotionPage)]` from the `derive` feature:
+//!
+//! ```ignore
+//! #[derive(NotionPage)]
+//! struct Task {
+//!     #[notion(name = "Name", kind = "title")]
+//!     name: String,
+//!     #[notion(name = "Due", kind = "date")]
+//!     due: Option<NaiveDate>,
+//!     #[notion(kind = "checkbox")]
+//!     done: bool,
+//! }
+//! ```
+//!
+//! `name` defaults to the name of the field. Fields marked with `#[notion(skip)]`
+//! are not mapped and set to their default value when reading a page.
+
+use crate::ids::{PageId, PropertyId};
+use crate::models::properties::{
+    number_as_i64, Color, DateOrDateTime, DateValue, FormulaResultValue, PropertyConfiguration,
+    PropertyError, PropertyValue, RelationValue, SelectedValue,
+};
+use crate::models::text::RichText;
+use crate::models::users::User;
+use crate::models::{Database, Number, Page, PageCreateRequest, Parent, Properties};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+
+pub use crate::models::properties::PropertyKind;
+#[cfg(feature = "derive")]
+pub use rusticnotion_derive::NotionPage;
+
+/// A property a [NotionPage] is mapped to.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct PropertySchema {
+    pub name: &'static str,
+    pub kind: PropertyKind,
+}
+
+/// A struct that is stored as a database page, usually implemented with `#[derive(NotionPage)]`.
+pub trait NotionPage: Sized {
+    /// The properties the fields are mapped to.
+    fn schema() -> Vec<PropertySchema>;
+
+    fn from_properties(properties: &Properties) -> Result<Self, PropertyError>;
+
+    /// The writable properties of this struct, ready to create or update a page in `database`.
+    fn to_properties(
+        &self,
+        database: &Database,
+    ) -> Result<Properties, PropertyError>;
+
+    fn from_page(page: &Page) -> Result<Self, PropertyError> {
+        Self::from_properties(&page.properties)
+    }
+
+    fn to_create_request(
+        &self,
+        database: &Database,
+    ) -> Result<PageCreateRequest, PropertyError> {
+        Ok(PageCreateRequest {
+            parent: Parent::Database {
+                database_id: database.id.clone(),
+            },
+            properties: self.to_properties(database)?.into(),
+            children: None,
+            icon: None,
+            cover: None,
+        })
+    }
+
+    /// Checks that `database` has every property of the [schema()](Self::schema()) with the expected type.
+    fn check_schema(database: &Database) -> Result<(), PropertyError> {
+        for property in Self::schema() {
+            let configuration =
+                database
+                    .properties
+                    .get(property.name)
+                    .ok_or_else(|| PropertyError::Missing {
+                        property: describe(property.name),
+                    })?;
+            if configuration.type_name() != property.kind.name() {
+                return Err(PropertyError::WrongType {
+                    property: describe(property.name),
+                    expected: property.kind.name(),
+                    actual: configuration.type_name(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A field type of a [NotionPage].
+pub trait PropertyField: Sized {
+    /// Converts a property value, `None` if the value can't be represented by this type.
+    fn read(value: &PropertyValue) -> Option<Self>;
+
+    /// Converts this field into a value of the property described by `configuration`,
+    /// `None` if this type can't be written to that kind of property.
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue>;
+
+    /// The value of the field when the page doesn't have the property.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+#[doc(hidden)]
+pub fn read_field<T: PropertyField>(
+    properties: &Properties,
+    name: &str,
+    kind: PropertyKind,
+) -> Result<T, PropertyError> {
+    let value = match properties.properties.get(name) {
+        Some(value) => value,
+        None => {
+            return T::missing().ok_or_else(|| PropertyError::Missing {
+                property: describe(name),
+            })
+        }
+    };
+    // Types like `String` can be read from many kinds of properties, only the declared one
+    // is accepted so reading agrees with `check_schema` and writing.
+    // A `formula` field reads the result of the formula, whatever its type.
+    if value.type_name() != kind.name() {
+        return Err(PropertyError::WrongType {
+            property: describe(name),
+            expected: kind.name(),
+            actual: value.type_name(),
+        });
+    }
+    if let Some(field) = T::read(value) {
+        return Ok(field);
+    }
+
+    Err(if value.is_null() {
+        PropertyError::Null {
+            property: describe(name),
+        }
+    } else {
+        unsupported::<T>(name, kind)
+    })
+}
+
+#[doc(hidden)]
+pub fn write_field<T: PropertyField>(
+    properties: &mut HashMap<String, PropertyValue>,
+    database: &Database,
+    name: &str,
+    kind: PropertyKind,
+    field: &T,
+) -> Result<(), PropertyError> {
+    if !kind.is_writable() {
+        return Ok(());
+    }
+    let configuration = database
+        .properties
+        .get(name)
+        .ok_or_else(|| PropertyError::Missing {
+            property: describe(name),
+        })?;
+    if configuration.type_name() != kind.name() {
+        return Err(PropertyError::WrongType {
+            property: describe(name),
+            expected: kind.name(),
+            actual: configuration.type_name(),
+        });
+    }
+
+    let value = field
+        .write(kind, configuration)
+        .ok_or_else(|| unsupported::<T>(name, kind))?;
+    properties.insert(name.to_string(), value);
+    Ok(())
+}
+
+pub(crate) fn describe(name: &str) -> String {
+    format!("'{}'", name)
+}
+
+fn unsupported<T>(
+    name: &str,
+    kind: PropertyKind,
+) -> PropertyError {
+    PropertyError::Unsupported {
+        property: describe(name),
+        kind: kind.name(),
+        type_name: std::any::type_name::<T>(),
+    }
+}
+
+pub(crate) fn rich_text(content: &str) -> Vec<RichText> {
+    vec![RichText::text(content)]
+}
+
+/// Selects the option named `name`, Notion creates the option if it doesn't exist yet.
+pub(crate) fn selected(
+    name: &str,
+    configuration: &PropertyConfiguration,
+) -> SelectedValue {
+    match configuration
+        .options()
+        .iter()
+        .find(|option| option.name == name)
+    {
+        Some(option) => SelectedValue {
+            id: Some(option.id.clone()),
+            name: Some(option.name.clone()),
+            color: option.color,
+        },
+        None => SelectedValue {
+            id: None,
+            name: Some(name.to_string()),
+            color: Color::Default,
+        },
+    }
+}
+
+impl PropertyField for String {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::Title { title: text, .. }
+            | PropertyValue::Text {
+                rich_text: text, ..
+            } => Some(text.iter().map(|text| text.plain_text()).collect()),
+            PropertyValue::Select {
+                select: Some(selected),
+                ..
+            }
+            | PropertyValue::Status {
+                status: Some(selected),
+                ..
+            } => selected.name.clone(),
+            PropertyValue::Url { url: text, .. }
+            | PropertyValue::Email { email: text, .. }
+            | PropertyValue::PhoneNumber {
+                phone_number: text, ..
+            } => text.clone(),
+            PropertyValue::Formula {
+                formula: FormulaResultValue::String { string },
+                ..
+            } => string.clone(),
+            _ => None,
+        }
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        let id = configuration.id().clone();
+        Some(match kind {
+            PropertyKind::Title => PropertyValue::Title {
+                id,
+                title: rich_text(self),
+            },
+            PropertyKind::RichText => PropertyValue::Text {
+                id,
+                rich_text: rich_text(self),
+            },
+            PropertyKind::Select => PropertyValue::Select {
+                id,
+                select: Some(selected(self, configuration)),
+            },
+            PropertyKind::Status => PropertyValue::Status {
+                id,
+                status: Some(selected(self, configuration)),
+            },
+            PropertyKind::Url => PropertyValue::Url {
+                id,
+                url: Some(self.clone()),
+            },
+            PropertyKind::Email => PropertyValue::Email {
+                id,
+                email: Some(self.clone()),
+            },
+            PropertyKind::PhoneNumber => PropertyValue::PhoneNumber {
+                id,
+                phone_number: Some(self.clone()),
+            },
+            _ => return None,
+        })
+    }
+}
+
+fn read_number(value: &PropertyValue) -> Option<&Number> {
+    match value {
+        PropertyValue::Number {
+            number: Some(number),
+            ..
+        }
+        | PropertyValue::Formula {
+            formula:
+                FormulaResultValue::Number {
+                    number: Some(number),
+                },
+            ..
+        } => Some(number),
+        _ => None,
+    }
+}
+
+fn write_number(
+    number: Option<Number>,
+    kind: PropertyKind,
+    configuration: &PropertyConfiguration,
+) -> Option<PropertyValue> {
+    match kind {
+        PropertyKind::Number => Some(PropertyValue::Number {
+            id: configuration.id().clone(),
+            number: Some(number?),
+        }),
+        _ => None,
+    }
+}
+
+impl PropertyField for f64 {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        read_number(value)?.as_f64()
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        write_number(Number::from_f64(*self), kind, configuration)
+    }
+}
+
+impl PropertyField for i64 {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        number_as_i64(read_number(value)?)
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        write_number(Some(Number::from(*self)), kind, configuration)
+    }
+}
+
+impl PropertyField for bool {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::Checkbox { checkbox, .. } => Some(*checkbox),
+            PropertyValue::Formula {
+                formula: FormulaResultValue::Boolean { boolean },
+                ..
+            } => *boolean,
+            _ => None,
+        }
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        match kind {
+            PropertyKind::Checkbox => Some(PropertyValue::Checkbox {
+                id: configuration.id().clone(),
+                checkbox: *self,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyField for DateValue {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::Date { date, .. }
+            | PropertyValue::Formula {
+                formula: FormulaResultValue::Date { date },
+                ..
+            } => date.clone(),
+            PropertyValue::CreatedTime {
+                created_time: time, ..
+            }
+            | PropertyValue::LastEditedTime {
+                last_edited_time: time,
+                ..
+            } => Some(DateOrDateTime::DateTime(*time).into()),
+            _ => None,
+        }
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        match kind {
+            PropertyKind::Date => Some(PropertyValue::Date {
+                id: configuration.id().clone(),
+                date: Some(self.clone()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyField for NaiveDate {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match DateValue::read(value)?.start {
+            DateOrDateTime::Date(date) => Some(date),
+            DateOrDateTime::DateTime(time) => Some(time.date_naive()),
+        }
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        DateValue::from(DateOrDateTime::Date(*self)).write(kind, configuration)
+    }
+}
+
+impl PropertyField for DateTime<Utc> {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match DateValue::read(value)?.start {
+            DateOrDateTime::Date(date) => Some(date.and_hms_opt(0, 0, 0)?.and_utc()),
+            DateOrDateTime::DateTime(time) => Some(time),
+        }
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        DateValue::from(DateOrDateTime::DateTime(*self)).write(kind, configuration)
+    }
+}
+
+/// The names of the selected options of a multi select property.
+impl PropertyField for Vec<String> {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::MultiSelect { multi_select, .. } => Some(
+                multi_select
+                    .iter()
+                    .flatten()
+                    .filter_map(|selected| selected.name.clone())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        match kind {
+            PropertyKind::MultiSelect => Some(PropertyValue::MultiSelect {
+                id: configuration.id().clone(),
+                multi_select: Some(
+                    self.iter()
+                        .map(|name| selected(name, configuration))
+                        .collect(),
+                ),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The pages a relation property points to.
+impl PropertyField for Vec<PageId> {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::Relation { relation, .. } => Some(
+                relation
+                    .iter()
+                    .flatten()
+                    .map(|relation| relation.id.clone())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        match kind {
+            PropertyKind::Relation => Some(PropertyValue::Relation {
+                id: configuration.id().clone(),
+                relation: Some(
+                    self.iter()
+                        .map(|id| RelationValue { id: id.clone() })
+                        .collect(),
+                ),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyField for Vec<User> {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::People { people, .. } => Some(people.clone()),
+            _ => None,
+        }
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        match kind {
+            PropertyKind::People => Some(PropertyValue::People {
+                id: configuration.id().clone(),
+                people: self.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl PropertyField for User {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        match value {
+            PropertyValue::CreatedBy {
+                created_by: user, ..
+            }
+            | PropertyValue::LastEditedBy {
+                last_edited_by: user,
+                ..
+            } => Some(user.clone()),
+            _ => None,
+        }
+    }
+
+    fn write(
+        &self,
+        _kind: PropertyKind,
+        _configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        None
+    }
+}
+
+/// Empty and missing properties are read as `None`, `None` clears the property when writing.
+impl<T: PropertyField> PropertyField for Option<T> {
+    fn read(value: &PropertyValue) -> Option<Self> {
+        if value.is_null() {
+            return Some(None);
+        }
+        T::read(value).map(Some)
+    }
+
+    fn write(
+        &self,
+        kind: PropertyKind,
+        configuration: &PropertyConfiguration,
+    ) -> Option<PropertyValue> {
+        match self {
+            Some(field) => field.write(kind, configuration),
+            None => empty_value(kind, configuration.id().clone()),
+        }
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+fn empty_value(
+    kind: PropertyKind,
+    id: PropertyId,
+) -> Option<PropertyValue> {
+    Some(match kind {
+        PropertyKind::Title => PropertyValue::Title { id, title: vec![] },
+        PropertyKind::RichText => PropertyValue::Text {
+            id,
+            rich_text: vec![],
+        },
+        PropertyKind::Number => PropertyValue::Number { id, number: None },
+        PropertyKind::Select => PropertyValue::Select { id, select: None },
+        PropertyKind::Status => PropertyValue::Status { id, status: None },
+        PropertyKind::MultiSelect => PropertyValue::MultiSelect {
+            id,
+            multi_select: Some(vec![]),
+        },
+        PropertyKind::Date => PropertyValue::Date { id, date: None },
+        PropertyKind::People => PropertyValue::People { id, people: vec![] },
+        PropertyKind::Relation => PropertyValue::Relation {
+            id,
+            relation: Some(vec![]),
+        },
+        PropertyKind::Url => PropertyValue::Url { id, url: None },
+        PropertyKind::Email => PropertyValue::Email { id, email: None },
+        PropertyKind::PhoneNumber => PropertyValue::PhoneNumber {
+            id,
+            phone_number: None,
+        },
+        _ => return None,
+    })
+}
@@ -1,33 +1,35 @@
 pub mod block;
+pub mod changes;
 pub mod error;
+pub mod file_uploads;
+pub mod limits;
+pub mod mapping;
 pub mod paging;
 pub mod properties;
 pub mod search;
 pub mod text;
 pub mod users;
+pub mod validation;
 
-use crate::models::properties::{PropertyConfiguration, PropertyValue};
+use crate::models::properties::{
+    DateValue, FormulaResultValue, PropertyConfiguration, PropertyError, PropertyKey,
+    PropertyValue, SelectedValue, WriteProperties,
+};
 use crate::models::text::RichText;
 use crate::Error;
-use block::FileOrEmojiObject;
+use block::{FileObject, FileOrEmojiObject};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::ids::{AsIdentifier, DatabaseId, PageId};
 use crate::models::block::{Block, CreateBlock};
 use crate::models::error::ErrorResponse;
+use crate::models::file_uploads::FileUpload;
 use crate::models::paging::PagingCursor;
 use crate::models::users::User;
 pub use chrono::{DateTime, Utc};
 pub use serde_json::value::Number;
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
-#[serde(rename_all = "snake_case")]
-enum ObjectType {
-    Database,
-    List,
-}
-
 /// Represents a Notion Database
 /// See <https://developers.notion.com/reference/database>
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -104,7 +106,9 @@ impl ListResponse<Object> {
             .into_iter()
             .map(|object| match object {
                 Object::Page { page } => Ok(page),
-                response => Err(Error::UnexpectedResponse { response }),
+                response => Err(Error::UnexpectedResponse {
+                    response: Box::new(response),
+                }),
             })
             .collect();
 
@@ -121,7 +125,9 @@ impl ListResponse<Object> {
             .into_iter()
             .map(|object| match object {
                 Object::Block { block } => Ok(block),
-                response => Err(Error::UnexpectedResponse { response }),
+                response => Err(Error::UnexpectedResponse {
+                    response: Box::new(response),
+                }),
             })
             .collect();
 
@@ -168,9 +174,34 @@ impl Properties {
 #[derive(Serialize, Debug, Eq, PartialEq)]
 pub struct PageCreateRequest {
     pub parent: Parent,
-    pub properties: Properties,
+    pub properties: WriteProperties,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub children: Option<Vec<CreateBlock>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<FileOrEmojiObject>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<FileObject>,
+}
+
+/// <https://developers.notion.com/reference/patch-page>
+#[derive(Serialize, Debug, Eq, PartialEq, Clone, Default)]
+pub struct UpdatePageRequest {
+    pub properties: WriteProperties,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<FileOrEmojiObject>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<FileObject>,
+}
+
+impl From<WriteProperties> for UpdatePageRequest {
+    fn from(properties: WriteProperties) -> Self {
+        UpdatePageRequest {
+            properties,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -183,6 +214,7 @@ pub struct Page {
     /// The archived status of the page.
     pub archived: bool,
     pub icon: Option<FileOrEmojiObject>,
+    pub cover: Option<FileObject>,
     pub properties: Properties,
     pub parent: Parent,
 }
@@ -193,6 +225,39 @@ impl Page {
     }
 }
 
+/// Forwards the typed property accessors of [Properties] to [Page].
+macro_rules! page_property_accessors {
+    ($($name:ident -> $value:ty;)+) => {
+        impl Page {
+            $(
+                #[doc = concat!("See [Properties::", stringify!($name), "()].")]
+                pub fn $name<K: PropertyKey>(
+                    &self,
+                    key: K,
+                ) -> Result<$value, PropertyError> {
+                    self.properties.$name(key)
+                }
+            )+
+        }
+    };
+}
+
+page_property_accessors! {
+    text -> String;
+    number -> Option<f64>;
+    number_i64 -> Option<i64>;
+    select -> Option<&SelectedValue>;
+    multi_select -> &[SelectedValue];
+    status -> Option<&SelectedValue>;
+    date -> Option<&DateValue>;
+    checkbox -> bool;
+    url -> Option<&str>;
+    email -> Option<&str>;
+    people -> &[User];
+    relation_ids -> Vec<&PageId>;
+    formula -> &FormulaResultValue;
+}
+
 impl AsIdentifier<PageId> for Page {
     fn as_id(&self) -> &PageId {
         &self.id
@@ -227,6 +292,10 @@ pub enum Object {
         #[serde(flatten)]
         error: ErrorResponse,
     },
+    FileUpload {
+        #[serde(flatten)]
+        file_upload: FileUpload,
+    },
 }
 
 impl Object {
@@ -238,6 +307,7 @@ impl Object {
 #[cfg(test)]
 mod tests {
     use super::{ListResponse, Object, Page};
+    use crate::models::file_uploads::{FileUploadParts, FileUploadStatus};
 
     #[test]
     fn deserialize_page() {
@@ -255,4 +325,19 @@ mod tests {
         let _search_results: ListResponse<Object> =
             serde_json::from_str(include_str!("tests/issue_15.json")).unwrap();
     }
+
+    #[test]
+    fn deserialize_file_upload() {
+        let object: Object = serde_json::from_str(include_str!("tests/file_upload.json")).unwrap();
+        match object {
+            Object::FileUpload { file_upload } => {
+                assert_eq!(file_upload.status, FileUploadStatus::Pending);
+                assert_eq!(
+                    file_upload.number_of_parts,
+                    Some(FileUploadParts { total: 3, sent: 0 })
+                );
+            }
+            object => panic!("Expected a file upload, got {:?}", object),
+        }
+    }
 }
@@ -18,3 +18,15 @@ pub trait Pageable {
         starting_point: Option<PagingCursor>,
     ) -> Self;
 }
+
+impl Pageable for Paging {
+    fn start_from(
+        self,
+        starting_point: Option<PagingCursor>,
+    ) -> Self {
+        Paging {
+            start_cursor: starting_point,
+            ..self
+        }
+    }
+}
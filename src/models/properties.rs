@@ -1,13 +1,22 @@
 use crate::models::text::RichText;
 use crate::models::users::User;
 
-use crate::ids::{DatabaseId, PageId, PropertyId};
-use crate::models::{DateTime, Number, Utc};
+use crate::ids::{DatabaseId, Identifier, PageId, PropertyId};
+use crate::models::file_uploads::{FileUpload, FileUploadReference};
+use crate::models::{DateTime, Number, Properties, Utc};
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
 
+mod bridge;
+mod rollup;
 #[cfg(test)]
 mod tests;
+mod write;
+
+pub use bridge::{from_properties, to_properties};
+pub use write::{SelectReference, UserReference, WriteProperties, WritePropertyValue};
 
 /// How the number is displayed in Notion.
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone, Hash)]
@@ -261,6 +270,145 @@ pub enum PropertyConfiguration {
     },
 }
 
+/// The types of Notion properties.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum PropertyKind {
+    Title,
+    RichText,
+    Number,
+    Select,
+    MultiSelect,
+    Status,
+    Date,
+    People,
+    Files,
+    Checkbox,
+    Url,
+    Email,
+    PhoneNumber,
+    Formula,
+    Relation,
+    Rollup,
+    CreatedTime,
+    CreatedBy,
+    LastEditedTime,
+    LastEditedBy,
+    Button,
+}
+
+impl PropertyKind {
+    /// The name Notion uses for this type, e.g. `rich_text`.
+    pub fn name(self) -> &'static str {
+        use PropertyKind::*;
+        match self {
+            Title => "title",
+            RichText => "rich_text",
+            Number => "number",
+            Select => "select",
+            MultiSelect => "multi_select",
+            Status => "status",
+            Date => "date",
+            People => "people",
+            Files => "files",
+            Checkbox => "checkbox",
+            Url => "url",
+            Email => "email",
+            PhoneNumber => "phone_number",
+            Formula => "formula",
+            Relation => "relation",
+            Rollup => "rollup",
+            CreatedTime => "created_time",
+            CreatedBy => "created_by",
+            LastEditedTime => "last_edited_time",
+            LastEditedBy => "last_edited_by",
+            Button => "button",
+        }
+    }
+
+    /// Computed properties are only read, they are left out when writing a page.
+    pub fn is_writable(self) -> bool {
+        use PropertyKind::*;
+        !matches!(
+            self,
+            Formula | Rollup | CreatedTime | CreatedBy | LastEditedTime | LastEditedBy | Button
+        )
+    }
+}
+
+impl PropertyConfiguration {
+    pub fn id(&self) -> &PropertyId {
+        use PropertyConfiguration::*;
+        match self {
+            Title { id }
+            | Text { id }
+            | Number { id, .. }
+            | Select { id, .. }
+            | Status { id, .. }
+            | MultiSelect { id, .. }
+            | Date { id }
+            | People { id }
+            | Files { id }
+            | Checkbox { id }
+            | Url { id }
+            | Email { id }
+            | PhoneNumber { id }
+            | Formula { id, .. }
+            | Relation { id, .. }
+            | Rollup { id, .. }
+            | CreatedTime { id }
+            | CreatedBy { id }
+            | LastEditedTime { id }
+            | LastEditBy { id }
+            | Button { id } => id,
+        }
+    }
+
+    pub fn kind(&self) -> PropertyKind {
+        use PropertyConfiguration::*;
+        match self {
+            Title { .. } => PropertyKind::Title,
+            Text { .. } => PropertyKind::RichText,
+            Number { .. } => PropertyKind::Number,
+            Select { .. } => PropertyKind::Select,
+            Status { .. } => PropertyKind::Status,
+            MultiSelect { .. } => PropertyKind::MultiSelect,
+            Date { .. } => PropertyKind::Date,
+            People { .. } => PropertyKind::People,
+            Files { .. } => PropertyKind::Files,
+            Checkbox { .. } => PropertyKind::Checkbox,
+            Url { .. } => PropertyKind::Url,
+            Email { .. } => PropertyKind::Email,
+            PhoneNumber { .. } => PropertyKind::PhoneNumber,
+            Formula { .. } => PropertyKind::Formula,
+            Relation { .. } => PropertyKind::Relation,
+            Rollup { .. } => PropertyKind::Rollup,
+            CreatedTime { .. } => PropertyKind::CreatedTime,
+            CreatedBy { .. } => PropertyKind::CreatedBy,
+            LastEditedTime { .. } => PropertyKind::LastEditedTime,
+            LastEditBy { .. } => PropertyKind::LastEditedBy,
+            Button { .. } => PropertyKind::Button,
+        }
+    }
+
+    /// The name Notion uses for the type of this property, e.g. `rich_text`.
+    pub fn type_name(&self) -> &'static str {
+        self.kind().name()
+    }
+
+    /// The options of a select, multi select or status property.
+    pub fn options(&self) -> &[SelectOption] {
+        match self {
+            PropertyConfiguration::Select { select, .. }
+            | PropertyConfiguration::MultiSelect {
+                multi_select: select,
+                ..
+            } => &select.options,
+            PropertyConfiguration::Status { status, .. } => &status.options,
+            _ => &[],
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct SelectedValue {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -278,12 +426,66 @@ pub enum DateOrDateTime {
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(from = "DateValueRepr")]
 pub struct DateValue {
     pub start: DateOrDateTime,
     pub end: Option<DateOrDateTime>,
     pub time_zone: Option<String>,
 }
 
+/// Besides the object Notion uses, a plain ISO 8601 string is read as a date without an end.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DateValueRepr {
+    Start(DateOrDateTime),
+    Full {
+        start: DateOrDateTime,
+        end: Option<DateOrDateTime>,
+        time_zone: Option<String>,
+    },
+}
+
+impl DateOrDateTime {
+    /// The point in time, midnight UTC for a date without a time.
+    pub(crate) fn to_utc(&self) -> DateTime<Utc> {
+        match self {
+            DateOrDateTime::Date(date) => date
+                .and_hms_opt(0, 0, 0)
+                .expect("midnight exists")
+                .and_utc(),
+            DateOrDateTime::DateTime(time) => *time,
+        }
+    }
+}
+
+/// A single date or time, without an end or time zone.
+impl From<DateOrDateTime> for DateValue {
+    fn from(start: DateOrDateTime) -> Self {
+        DateValue {
+            start,
+            end: None,
+            time_zone: None,
+        }
+    }
+}
+
+impl From<DateValueRepr> for DateValue {
+    fn from(date: DateValueRepr) -> Self {
+        match date {
+            DateValueRepr::Start(start) => start.into(),
+            DateValueRepr::Full {
+                start,
+                end,
+                time_zone,
+            } => DateValue {
+                start,
+                end,
+                time_zone,
+            },
+        }
+    }
+}
+
 /// Formula property value objects represent the result of evaluating a formula
 /// described in the database's properties.
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -307,17 +509,48 @@ pub struct RelationValue {
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum RollupValue {
-    Number { number: Option<Number> },
-    Date { date: Option<DateTime<Utc>> },
-    Array { array: Vec<RollupPropertyValue> },
+    Number {
+        number: Option<Number>,
+    },
+    /// A plain date string as well as a range, `date_range` rollups have an end.
+    Date {
+        date: Option<DateValue>,
+    },
+    Array {
+        array: Vec<RollupPropertyValue>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum FileReference {
-    External { name: String, external: External },
-    File { name: String, file: File },
+    External {
+        name: String,
+        external: External,
+    },
+    File {
+        name: String,
+        file: File,
+    },
+    /// Only used when writing a files property, Notion returns uploaded files as [FileReference::File].
+    FileUpload {
+        name: String,
+        file_upload: FileUploadReference,
+    },
+}
+
+impl FileReference {
+    /// References a completed [FileUpload] by the given display name.
+    pub fn from_upload<T: Into<String>>(
+        name: T,
+        upload: &FileUpload,
+    ) -> Self {
+        FileReference::FileUpload {
+            name: name.into(),
+            file_upload: upload.into(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -334,7 +567,6 @@ pub struct File {
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
-
 pub enum PropertyValue {
     // <https://developers.notion.com/reference/property-object#title-configuration>
     Title {
@@ -514,3 +746,397 @@ pub enum RollupPropertyValue {
         last_edited_by: User,
     },
 }
+
+impl PropertyValue {
+    pub fn id(&self) -> &PropertyId {
+        use PropertyValue::*;
+        match self {
+            Title { id, .. }
+            | Text { id, .. }
+            | Number { id, .. }
+            | Select { id, .. }
+            | Status { id, .. }
+            | MultiSelect { id, .. }
+            | Date { id, .. }
+            | Formula { id, .. }
+            | Relation { id, .. }
+            | Rollup { id, .. }
+            | People { id, .. }
+            | Files { id, .. }
+            | Checkbox { id, .. }
+            | Url { id, .. }
+            | Email { id, .. }
+            | PhoneNumber { id, .. }
+            | CreatedTime { id, .. }
+            | CreatedBy { id, .. }
+            | LastEditedTime { id, .. }
+            | LastEditedBy { id, .. }
+            | Button { id } => id,
+        }
+    }
+
+    /// Whether the property has no value, e.g. an empty select or a number that was never set.
+    pub fn is_null(&self) -> bool {
+        use PropertyValue::*;
+        match self {
+            Number { number, .. } => number.is_none(),
+            Select { select, .. } => select.is_none(),
+            Status { status, .. } => status.is_none(),
+            MultiSelect { multi_select, .. } => multi_select.is_none(),
+            Date { date, .. } => date.is_none(),
+            Relation { relation, .. } => relation.is_none(),
+            Rollup { rollup, .. } => rollup.is_none(),
+            Files { files, .. } => files.is_none(),
+            Url { url, .. } => url.is_none(),
+            Email { email, .. } => email.is_none(),
+            PhoneNumber { phone_number, .. } => phone_number.is_none(),
+            Formula { formula, .. } => match formula {
+                FormulaResultValue::String { string } => string.is_none(),
+                FormulaResultValue::Number { number } => number.is_none(),
+                FormulaResultValue::Boolean { boolean } => boolean.is_none(),
+                FormulaResultValue::Date { date } => date.is_none(),
+            },
+            _ => false,
+        }
+    }
+
+    pub fn kind(&self) -> PropertyKind {
+        use PropertyValue::*;
+        match self {
+            Title { .. } => PropertyKind::Title,
+            Text { .. } => PropertyKind::RichText,
+            Number { .. } => PropertyKind::Number,
+            Select { .. } => PropertyKind::Select,
+            Status { .. } => PropertyKind::Status,
+            MultiSelect { .. } => PropertyKind::MultiSelect,
+            Date { .. } => PropertyKind::Date,
+            Formula { .. } => PropertyKind::Formula,
+            Relation { .. } => PropertyKind::Relation,
+            Rollup { .. } => PropertyKind::Rollup,
+            People { .. } => PropertyKind::People,
+            Files { .. } => PropertyKind::Files,
+            Checkbox { .. } => PropertyKind::Checkbox,
+            Url { .. } => PropertyKind::Url,
+            Email { .. } => PropertyKind::Email,
+            PhoneNumber { .. } => PropertyKind::PhoneNumber,
+            CreatedTime { .. } => PropertyKind::CreatedTime,
+            CreatedBy { .. } => PropertyKind::CreatedBy,
+            LastEditedTime { .. } => PropertyKind::LastEditedTime,
+            LastEditedBy { .. } => PropertyKind::LastEditedBy,
+            Button { .. } => PropertyKind::Button,
+        }
+    }
+
+    /// The name Notion uses for the type of this property, e.g. `rich_text`.
+    pub fn type_name(&self) -> &'static str {
+        self.kind().name()
+    }
+}
+
+/// The number as an integer, `None` if it has a fractional part or is out of range.
+pub(crate) fn number_as_i64(number: &Number) -> Option<i64> {
+    number.as_i64().or_else(|| {
+        number
+            .as_f64()
+            .filter(|value| value.fract() == 0.0 && value.abs() < i64::MAX as f64)
+            .map(|value| value as i64)
+    })
+}
+
+/// Whole numbers are returned as integers, like Notion does.
+pub(crate) fn number_from_f64(number: f64) -> Option<Number> {
+    if number.fract() == 0.0 && number.abs() < 2f64.powi(53) {
+        Some(Number::from(number as i64))
+    } else {
+        Number::from_f64(number)
+    }
+}
+
+/// Returned by the typed property accessors of [Properties].
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PropertyError {
+    #[error("Property {} does not exist", .property)]
+    Missing { property: String },
+
+    #[error("Property {} is a {} property, expected {}", .property, .actual, .expected)]
+    WrongType {
+        property: String,
+        expected: &'static str,
+        actual: &'static str,
+    },
+
+    #[error("Property {} is {}, expected an integer", .property, .number)]
+    NotAnInteger { property: String, number: Number },
+
+    #[error("Property {} is empty", .property)]
+    Null { property: String },
+
+    #[error("Property {} of type {} can't be converted from or to {}", .property, .kind, .type_name)]
+    Unsupported {
+        property: String,
+        kind: &'static str,
+        type_name: &'static str,
+    },
+
+    #[error("Property {} has an invalid value: {}", .property, .message)]
+    Invalid { property: String, message: String },
+
+    #[error("{}", .message)]
+    Custom { message: String },
+}
+
+/// Looks up `key` in properties or property configurations keyed by name, by name first, then
+/// by id.
+pub(crate) fn find_by_name_or_id<'a, T>(
+    properties: &'a HashMap<String, T>,
+    key: &str,
+    id: impl Fn(&T) -> &PropertyId,
+) -> Option<(&'a String, &'a T)> {
+    properties.get_key_value(key).or_else(|| {
+        properties
+            .iter()
+            .find(|(_, value)| id(value).value() == key)
+    })
+}
+
+pub(crate) fn plain_text(text: &[RichText]) -> String {
+    text.iter().map(|text| text.plain_text()).collect()
+}
+
+/// Looks up a property of [Properties] either by its name or by its [PropertyId].
+pub trait PropertyKey {
+    fn find<'a>(
+        &self,
+        properties: &'a Properties,
+    ) -> Option<&'a PropertyValue>;
+
+    /// How the property is referred to in a [PropertyError].
+    fn describe(&self) -> String;
+}
+
+impl PropertyKey for &str {
+    fn find<'a>(
+        &self,
+        properties: &'a Properties,
+    ) -> Option<&'a PropertyValue> {
+        properties.properties.get(*self)
+    }
+
+    fn describe(&self) -> String {
+        format!("'{}'", self)
+    }
+}
+
+impl PropertyKey for &String {
+    fn find<'a>(
+        &self,
+        properties: &'a Properties,
+    ) -> Option<&'a PropertyValue> {
+        properties.properties.get(*self)
+    }
+
+    fn describe(&self) -> String {
+        format!("'{}'", self)
+    }
+}
+
+impl PropertyKey for &PropertyId {
+    fn find<'a>(
+        &self,
+        properties: &'a Properties,
+    ) -> Option<&'a PropertyValue> {
+        properties
+            .properties
+            .values()
+            .find(|value| value.id() == *self)
+    }
+
+    fn describe(&self) -> String {
+        format!("with id '{}'", self)
+    }
+}
+
+/// Extracts the value of a property with the given variant,
+/// or returns [PropertyError::WrongType] naming `$expected`.
+macro_rules! expect_property {
+    ($properties:expr, $key:expr, $expected:literal, $($pattern:pat => $value:expr),+ $(,)?) => {{
+        match $properties.lookup($key)? {
+            $($pattern => Ok($value),)+
+            other => Err(PropertyError::WrongType {
+                property: $key.describe(),
+                expected: $expected,
+                actual: other.type_name(),
+            }),
+        }
+    }};
+}
+
+impl Properties {
+    /// The property with the given name or id, see [PropertyKey].
+    pub fn get<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<&PropertyValue, PropertyError> {
+        self.lookup(&key)
+    }
+
+    /// The property named `key`, or else the property with the id `key`. Filters, sorts and
+    /// formulas refer to properties either way.
+    pub fn get_by_name_or_id(
+        &self,
+        key: &str,
+    ) -> Option<&PropertyValue> {
+        find_by_name_or_id(&self.properties, key, PropertyValue::id).map(|(_, value)| value)
+    }
+
+    fn lookup<K: PropertyKey>(
+        &self,
+        key: &K,
+    ) -> Result<&PropertyValue, PropertyError> {
+        key.find(self).ok_or_else(|| PropertyError::Missing {
+            property: key.describe(),
+        })
+    }
+
+    /// The plain text of a text or title property.
+    pub fn text<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<String, PropertyError> {
+        expect_property!(self, &key, "rich_text",
+            PropertyValue::Text { rich_text, .. } => plain_text(rich_text),
+            PropertyValue::Title { title, .. } => plain_text(title),
+        )
+    }
+
+    pub fn number<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<Option<f64>, PropertyError> {
+        expect_property!(self, &key, "number",
+            PropertyValue::Number { number, .. } => {
+                number.as_ref().and_then(|number| number.as_f64())
+            }
+        )
+    }
+
+    /// The value of a number property, failing with [PropertyError::NotAnInteger]
+    /// if it has a fractional part or doesn't fit into an `i64`.
+    pub fn number_i64<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<Option<i64>, PropertyError> {
+        let number = expect_property!(self, &key, "number",
+            PropertyValue::Number { number, .. } => number.as_ref(),
+        )?;
+        match number {
+            None => Ok(None),
+            Some(number) => {
+                number_as_i64(number)
+                    .map(Some)
+                    .ok_or_else(|| PropertyError::NotAnInteger {
+                        property: key.describe(),
+                        number: number.clone(),
+                    })
+            }
+        }
+    }
+
+    pub fn select<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<Option<&SelectedValue>, PropertyError> {
+        expect_property!(self, &key, "select",
+            PropertyValue::Select { select, .. } => select.as_ref(),
+        )
+    }
+
+    pub fn multi_select<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<&[SelectedValue], PropertyError> {
+        expect_property!(self, &key, "multi_select",
+            PropertyValue::MultiSelect { multi_select, .. } => {
+                multi_select.as_deref().unwrap_or_default()
+            }
+        )
+    }
+
+    pub fn status<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<Option<&SelectedValue>, PropertyError> {
+        expect_property!(self, &key, "status",
+            PropertyValue::Status { status, .. } => status.as_ref(),
+        )
+    }
+
+    pub fn date<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<Option<&DateValue>, PropertyError> {
+        expect_property!(self, &key, "date",
+            PropertyValue::Date { date, .. } => date.as_ref(),
+        )
+    }
+
+    pub fn checkbox<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<bool, PropertyError> {
+        expect_property!(self, &key, "checkbox",
+            PropertyValue::Checkbox { checkbox, .. } => *checkbox,
+        )
+    }
+
+    pub fn url<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<Option<&str>, PropertyError> {
+        expect_property!(self, &key, "url",
+            PropertyValue::Url { url, .. } => url.as_deref(),
+        )
+    }
+
+    pub fn email<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<Option<&str>, PropertyError> {
+        expect_property!(self, &key, "email",
+            PropertyValue::Email { email, .. } => email.as_deref(),
+        )
+    }
+
+    pub fn people<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<&[User], PropertyError> {
+        expect_property!(self, &key, "people",
+            PropertyValue::People { people, .. } => people.as_slice(),
+        )
+    }
+
+    /// The ids of the pages a relation property points to.
+    pub fn relation_ids<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<Vec<&PageId>, PropertyError> {
+        expect_property!(self, &key, "relation",
+            PropertyValue::Relation { relation, .. } => relation
+                .iter()
+                .flatten()
+                .map(|relation| &relation.id)
+                .collect(),
+        )
+    }
+
+    /// The result Notion computed for a formula property.
+    pub fn formula<K: PropertyKey>(
+        &self,
+        key: K,
+    ) -> Result<&FormulaResultValue, PropertyError> {
+        expect_property!(self, &key, "formula",
+            PropertyValue::Formula { formula, .. } => formula,
+        )
+    }
+}
@@ -0,0 +1,519 @@
+//! Reads and writes [Properties] with serde, see [from_properties()] and [to_properties()].
+
+use super::{
+    DateOrDateTime, DateValue, External, FileReference, FormulaResultValue, PropertyConfiguration,
+    PropertyError, PropertyValue, RelationValue, RollupPropertyValue, RollupValue, SelectedValue,
+};
+use crate::ids::{FileUploadId, PageId, UserId};
+use crate::models::file_uploads::FileUploadReference;
+use crate::models::mapping::{describe, rich_text, selected};
+use crate::models::text::RichText;
+use crate::models::users::{User, UserCommon};
+use crate::models::{Database, Properties};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use serde::{forward_to_deserialize_any, ser, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Deserializes the properties of a page, e.g. into a struct deriving `Deserialize`.
+///
+/// Struct fields are matched by property name, use `#[serde(rename = "...")]` where they differ.
+/// Every property is presented as a plain value:
+///
+/// | Property | Value |
+/// |---|---|
+/// | title, text | string |
+/// | number | number |
+/// | select, status | option name |
+/// | multi select | sequence of option names |
+/// | date | start as ISO 8601 string, readable as [NaiveDate] or [DateTime] |
+/// | date range or date with time zone | [DateValue] |
+/// | checkbox | bool |
+/// | url, email, phone number | string |
+/// | relation | sequence of page ids |
+/// | people | sequence of user ids |
+/// | files | sequence of urls, or ids of file uploads |
+/// | formula | its result |
+/// | rollup | number, date or sequence |
+///
+/// Empty properties are `null`, so they can be read into an [Option].
+/// A date range fails to read into a [NaiveDate] instead of losing its end,
+/// read dates that can be ranges into a [DateValue], it accepts both forms.
+pub fn from_properties<T: DeserializeOwned>(properties: &Properties) -> Result<T, PropertyError> {
+    T::deserialize(PropertiesDeserializer {
+        properties: properties
+            .properties
+            .iter()
+            .map(|(name, value)| (name.clone(), plain_value(value)))
+            .collect::<Vec<_>>()
+            .into_iter(),
+    })
+}
+
+/// Serializes `value` into the properties of a page in `database`,
+/// the inverse of [from_properties()].
+///
+/// Every field has to match a property of `database` by name.
+/// Fields of computed properties like formulas and rollups are left out.
+///
+/// This is not a [Serializer](serde::Serializer) of its own, `value` is serialized into a
+/// [serde_json::Value] first and each field is converted by the type of its property.
+///
+/// Files are written as external files, or as file uploads if the value isn't a url.
+/// Files hosted by Notion are read as their temporary url and can't be written back
+/// once it expired, upload them again instead.
+pub fn to_properties<T: Serialize>(
+    value: &T,
+    database: &Database,
+) -> Result<Properties, PropertyError> {
+    let fields = match serde_json::to_value(value).map_err(ser::Error::custom)? {
+        Value::Object(fields) => fields,
+        value => {
+            return Err(PropertyError::Custom {
+                message: format!("expected a struct or map, found {}", value),
+            })
+        }
+    };
+
+    let mut properties = HashMap::new();
+    for (name, value) in fields {
+        let configuration =
+            database
+                .properties
+                .get(&name)
+                .ok_or_else(|| PropertyError::Missing {
+                    property: describe(&name),
+                })?;
+        if let Some(value) = property_value(&name, value, configuration)? {
+            properties.insert(name, value);
+        }
+    }
+
+    Ok(Properties { properties })
+}
+
+impl de::Error for PropertyError {
+    fn custom<T: Display>(message: T) -> Self {
+        PropertyError::Custom {
+            message: message.to_string(),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        PropertyError::Missing {
+            property: describe(field),
+        }
+    }
+}
+
+impl ser::Error for PropertyError {
+    fn custom<T: Display>(message: T) -> Self {
+        PropertyError::Custom {
+            message: message.to_string(),
+        }
+    }
+}
+
+struct PropertiesDeserializer {
+    properties: std::vec::IntoIter<(String, Value)>,
+}
+
+impl<'de> de::Deserializer<'de> for PropertiesDeserializer {
+    type Error = PropertyError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(PropertiesAccess {
+            properties: self.properties,
+            current: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct PropertiesAccess {
+    properties: std::vec::IntoIter<(String, Value)>,
+    current: Option<(String, Value)>,
+}
+
+impl<'de> MapAccess<'de> for PropertiesAccess {
+    type Error = PropertyError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.properties.next() {
+            Some((name, value)) => {
+                let key = seed.deserialize(name.as_str().into_deserializer())?;
+                self.current = Some((name, value));
+                Ok(Some(key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (name, value) = self
+            .current
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before its key"))?;
+        seed.deserialize(value)
+            .map_err(|error| PropertyError::Invalid {
+                property: describe(&name),
+                message: error.to_string(),
+            })
+    }
+}
+
+fn plain_text(text: &[RichText]) -> Value {
+    Value::String(super::plain_text(text))
+}
+
+fn option_name(selected: &Option<SelectedValue>) -> Value {
+    selected
+        .as_ref()
+        .and_then(|selected| selected.name.clone())
+        .map_or(Value::Null, Value::String)
+}
+
+fn option_names(selected: &Option<Vec<SelectedValue>>) -> Value {
+    selected
+        .iter()
+        .flatten()
+        .filter_map(|selected| selected.name.clone())
+        .map(Value::String)
+        .collect()
+}
+
+fn date(date: &Option<DateValue>) -> Value {
+    match date {
+        Some(DateValue {
+            start,
+            end: None,
+            time_zone: None,
+        }) => date_or_date_time(start),
+        Some(date) => serde_json::to_value(date).expect("Dates serialize to JSON"),
+        None => Value::Null,
+    }
+}
+
+fn date_or_date_time(date: &DateOrDateTime) -> Value {
+    match date {
+        DateOrDateTime::Date(date) => Value::String(date.to_string()),
+        DateOrDateTime::DateTime(time) => time_value(time),
+    }
+}
+
+fn time_value(time: &DateTime<Utc>) -> Value {
+    Value::String(time.to_rfc3339())
+}
+
+fn formula(formula: &FormulaResultValue) -> Value {
+    match formula {
+        FormulaResultValue::String { string } => string.clone().into(),
+        FormulaResultValue::Number { number } => number.clone().map_or(Value::Null, Value::Number),
+        FormulaResultValue::Boolean { boolean } => (*boolean).into(),
+        FormulaResultValue::Date { date: value } => date(value),
+    }
+}
+
+fn relations(relation: &Option<Vec<RelationValue>>) -> Value {
+    relation
+        .iter()
+        .flatten()
+        .map(|relation| Value::String(relation.id.to_string()))
+        .collect()
+}
+
+fn user_id(user: &User) -> Value {
+    match user {
+        User::Person { common, .. } | User::Bot { common, .. } | User::User { common, .. } => {
+            Value::String(common.id.to_string())
+        }
+    }
+}
+
+fn files(files: &Option<Vec<FileReference>>) -> Value {
+    files
+        .iter()
+        .flatten()
+        .map(|file| match file {
+            FileReference::External { external, .. } => Value::String(external.url.clone()),
+            FileReference::File { file, .. } => Value::String(file.url.clone()),
+            FileReference::FileUpload { file_upload, .. } => {
+                Value::String(file_upload.id.to_string())
+            }
+        })
+        .collect()
+}
+
+fn rollup(rollup: &Option<RollupValue>) -> Value {
+    match rollup {
+        Some(RollupValue::Number { number }) => number.clone().map_or(Value::Null, Value::Number),
+        Some(RollupValue::Date { date: value }) => date(value),
+        Some(RollupValue::Array { array }) => array.iter().map(rollup_element).collect(),
+        None => Value::Null,
+    }
+}
+
+fn rollup_element(value: &RollupPropertyValue) -> Value {
+    use RollupPropertyValue::*;
+    match value {
+        Text { rich_text } => plain_text(rich_text),
+        Number { number } => number.clone().map_or(Value::Null, Value::Number),
+        Select { select } => option_name(select),
+        Status { status } => option_name(status),
+        MultiSelect { multi_select } => option_names(multi_select),
+        Date { date: value } => date(value),
+        Formula { formula: value } => formula(value),
+        Relation { relation } => relations(relation),
+        Rollup { rollup: value } => rollup(value),
+        People { people } => people.iter().map(user_id).collect(),
+        Files { files: value } => files(value),
+        Checkbox { checkbox } => (*checkbox).into(),
+        Url { url } => url.clone().into(),
+        Email { email } => email.clone().into(),
+        PhoneNumber { phone_number } => phone_number.clone().into(),
+        CreatedTime { created_time } => time_value(created_time),
+        CreatedBy { created_by } => user_id(created_by),
+        LastEditedTime { last_edited_time } => time_value(last_edited_time),
+        LastEditedBy { last_edited_by } => user_id(last_edited_by),
+    }
+}
+
+fn plain_value(value: &PropertyValue) -> Value {
+    use PropertyValue::*;
+    match value {
+        Title { title, .. } => plain_text(title),
+        Text { rich_text, .. } => plain_text(rich_text),
+        Number { number, .. } => number.clone().map_or(Value::Null, Value::Number),
+        Select { select, .. } => option_name(select),
+        Status { status, .. } => option_name(status),
+        MultiSelect { multi_select, .. } => option_names(multi_select),
+        Date { date: value, .. } => date(value),
+        Formula { formula: value, .. } => formula(value),
+        Relation { relation, .. } => relations(relation),
+        Rollup { rollup: value, .. } => rollup(value),
+        People { people, .. } => people.iter().map(user_id).collect(),
+        Files { files: value, .. } => files(value),
+        Checkbox { checkbox, .. } => (*checkbox).into(),
+        Url { url, .. } => url.clone().into(),
+        Email { email, .. } => email.clone().into(),
+        PhoneNumber { phone_number, .. } => phone_number.clone().into(),
+        CreatedTime { created_time, .. } => time_value(created_time),
+        CreatedBy { created_by, .. } => user_id(created_by),
+        LastEditedTime {
+            last_edited_time, ..
+        } => time_value(last_edited_time),
+        LastEditedBy { last_edited_by, .. } => user_id(last_edited_by),
+        Button { .. } => Value::Null,
+    }
+}
+
+fn invalid(
+    name: &str,
+    expected: &str,
+    value: &Value,
+) -> PropertyError {
+    PropertyError::Invalid {
+        property: describe(name),
+        message: format!("expected {}, found {}", expected, value),
+    }
+}
+
+fn optional_string(
+    name: &str,
+    value: Value,
+) -> Result<Option<String>, PropertyError> {
+    match value {
+        Value::String(string) => Ok(Some(string)),
+        Value::Null => Ok(None),
+        value => Err(invalid(name, "a string", &value)),
+    }
+}
+
+fn strings(
+    name: &str,
+    value: Value,
+) -> Result<Vec<String>, PropertyError> {
+    match value {
+        Value::Array(values) => values
+            .into_iter()
+            .map(|value| match value {
+                Value::String(string) => Ok(string),
+                value => Err(invalid(name, "a string", &value)),
+            })
+            .collect(),
+        Value::Null => Ok(vec![]),
+        value => Err(invalid(name, "a sequence of strings", &value)),
+    }
+}
+
+fn parse_date(
+    name: &str,
+    value: Value,
+) -> Result<Option<DateValue>, PropertyError> {
+    let start = match optional_string(name, value.clone()) {
+        Ok(Some(start)) => start,
+        Ok(None) => return Ok(None),
+        // A serialized DateValue with an end or time zone.
+        Err(error) => return serde_json::from_value(value).map_err(|_| error),
+    };
+    let start = if let Ok(date) = NaiveDate::from_str(&start) {
+        DateOrDateTime::Date(date)
+    } else if let Ok(time) = DateTime::parse_from_rfc3339(&start) {
+        DateOrDateTime::DateTime(time.with_timezone(&Utc))
+    } else {
+        return Err(invalid(name, "an ISO 8601 date", &Value::String(start)));
+    };
+
+    Ok(Some(start.into()))
+}
+
+fn file_reference(file: String) -> FileReference {
+    if !file.contains("://") {
+        return FileReference::FileUpload {
+            name: file.clone(),
+            file_upload: FileUploadReference {
+                id: FileUploadId::from_str(&file).expect("FileUploadId parsing is infallible"),
+            },
+        };
+    }
+    let name = file
+        .split(['?', '#'])
+        .next()
+        .and_then(|url| url.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&file)
+        .to_string();
+    FileReference::External {
+        name,
+        external: External { url: file },
+    }
+}
+
+fn property_value(
+    name: &str,
+    value: Value,
+    configuration: &PropertyConfiguration,
+) -> Result<Option<PropertyValue>, PropertyError> {
+    use PropertyConfiguration as C;
+    let id = configuration.id().clone();
+    let text = |value| {
+        optional_string(name, value).map(|text| text.as_deref().map(rich_text).unwrap_or_default())
+    };
+
+    Ok(Some(match configuration {
+        C::Title { .. } => PropertyValue::Title {
+            id,
+            title: text(value)?,
+        },
+        C::Text { .. } => PropertyValue::Text {
+            id,
+            rich_text: text(value)?,
+        },
+        C::Number { .. } => PropertyValue::Number {
+            id,
+            number: match value {
+                Value::Number(number) => Some(number),
+                Value::Null => None,
+                value => return Err(invalid(name, "a number", &value)),
+            },
+        },
+        C::Select { .. } => PropertyValue::Select {
+            id,
+            select: optional_string(name, value)?.map(|option| selected(&option, configuration)),
+        },
+        C::Status { .. } => PropertyValue::Status {
+            id,
+            status: optional_string(name, value)?.map(|option| selected(&option, configuration)),
+        },
+        C::MultiSelect { .. } => PropertyValue::MultiSelect {
+            id,
+            multi_select: Some(
+                strings(name, value)?
+                    .iter()
+                    .map(|option| selected(option, configuration))
+                    .collect(),
+            ),
+        },
+        C::Date { .. } => PropertyValue::Date {
+            id,
+            date: parse_date(name, value)?,
+        },
+        C::Relation { .. } => PropertyValue::Relation {
+            id,
+            relation: Some(
+                strings(name, value)?
+                    .iter()
+                    .map(|id| RelationValue {
+                        id: PageId::from_str(id).expect("PageId parsing is infallible"),
+                    })
+                    .collect(),
+            ),
+        },
+        C::People { .. } => PropertyValue::People {
+            id,
+            people: strings(name, value)?
+                .iter()
+                .map(|id| User::User {
+                    common: UserCommon {
+                        id: UserId::from_str(id).expect("UserId parsing is infallible"),
+                        name: None,
+                        avatar_url: None,
+                    },
+                    person: None,
+                })
+                .collect(),
+        },
+        C::Checkbox { .. } => PropertyValue::Checkbox {
+            id,
+            checkbox: match value {
+                Value::Bool(checkbox) => checkbox,
+                value => return Err(invalid(name, "a bool", &value)),
+            },
+        },
+        C::Url { .. } => PropertyValue::Url {
+            id,
+            url: optional_string(name, value)?,
+        },
+        C::Email { .. } => PropertyValue::Email {
+            id,
+            email: optional_string(name, value)?,
+        },
+        C::PhoneNumber { .. } => PropertyValue::PhoneNumber {
+            id,
+            phone_number: optional_string(name, value)?,
+        },
+        C::Files { .. } => PropertyValue::Files {
+            id,
+            files: Some(
+                strings(name, value)?
+                    .into_iter()
+                    .map(file_reference)
+                    .collect(),
+            ),
+        },
+        C::Formula { .. }
+        | C::Rollup { .. }
+        | C::CreatedTime { .. }
+        | C::CreatedBy { .. }
+        | C::LastEditedTime { .. }
+        | C::LastEditBy { .. }
+        | C::Button { .. } => return Ok(None),
+    }))
+}
@@ -0,0 +1,334 @@
+//! Computes rollups from the related pages without asking Notion, see [Rollup::compute()].
+
+use super::{
+    number_from_f64, plain_text, DateOrDateTime, DateValue, FileReference, FormulaResultValue,
+    PropertyValue, Rollup, RollupFunction, RollupPropertyValue, RollupValue,
+};
+use crate::models::{Number, Page};
+use std::collections::HashSet;
+
+impl Rollup {
+    /// The value of this rollup for a page related to `pages`, as Notion would return it.
+    ///
+    /// The rolled up property is looked up by `rollup_property_id`, then by name. A page without
+    /// it counts as empty. Multi-selects, relations, people and files count each of their
+    /// entries for `count_values` and `unique`, unchecked checkboxes are empty. Percentages are
+    /// fractions between 0 and 1.
+    ///
+    /// Returns `None` for `count_per_group` and `percent_per_group`,
+    /// the API doesn't support their results either.
+    pub fn compute(
+        &self,
+        pages: &[Page],
+    ) -> Option<RollupValue> {
+        let values: Vec<Option<&PropertyValue>> = pages
+            .iter()
+            .map(|page| {
+                let properties = &page.properties;
+                properties
+                    .get_by_name_or_id(&self.rollup_property_id)
+                    .or_else(|| properties.get_by_name_or_id(&self.rollup_property_name))
+            })
+            .collect();
+        let present = || values.iter().flatten().copied();
+
+        use RollupFunction::*;
+        let result = match self.function {
+            Count => count(values.len()),
+            CountValues => count(present().map(|value| entries(value).len()).sum()),
+            Unique => count(present().flat_map(entries).collect::<HashSet<_>>().len()),
+            Empty => count(values.len() - not_empty(&values)),
+            NotEmpty => count(not_empty(&values)),
+            PercentEmpty => percent(values.len() - not_empty(&values), values.len()),
+            PercentNotEmpty => percent(not_empty(&values), values.len()),
+            Checked => count(checked(&values)),
+            Unchecked => count(values.len() - checked(&values)),
+            PercentChecked => percent(checked(&values), values.len()),
+            PercentUnchecked => percent(values.len() - checked(&values), values.len()),
+            Sum => number(Some(present().filter_map(as_f64).sum())),
+            Average => {
+                let numbers: Vec<f64> = present().filter_map(as_f64).collect();
+                let average = (!numbers.is_empty())
+                    .then(|| numbers.iter().sum::<f64>() / numbers.len() as f64);
+                number(average)
+            }
+            Median => number(median(present().filter_map(as_f64).collect())),
+            Min => number(present().filter_map(as_f64).reduce(f64::min)),
+            Max => number(present().filter_map(as_f64).reduce(f64::max)),
+            Range => {
+                let numbers: Vec<f64> = present().filter_map(as_f64).collect();
+                let min = numbers.iter().copied().reduce(f64::min);
+                let max = numbers.iter().copied().reduce(f64::max);
+                number(min.zip(max).map(|(min, max)| max - min))
+            }
+            EarliestDate => date(
+                present()
+                    .filter_map(as_date_range)
+                    .map(|(start, _)| start)
+                    .min_by_key(DateOrDateTime::to_utc),
+                None,
+            ),
+            LatestDate => date(
+                present()
+                    .filter_map(as_date_range)
+                    .map(|(_, end)| end)
+                    .max_by_key(DateOrDateTime::to_utc),
+                None,
+            ),
+            DateRange => {
+                let ranges: Vec<_> = present().filter_map(as_date_range).collect();
+                let start = ranges
+                    .iter()
+                    .map(|(start, _)| start.clone())
+                    .min_by_key(DateOrDateTime::to_utc);
+                let end = ranges
+                    .iter()
+                    .map(|(_, end)| end.clone())
+                    .max_by_key(DateOrDateTime::to_utc);
+                date(start, end)
+            }
+            ShowOriginal => RollupValue::Array {
+                array: present().filter_map(to_rollup_element).collect(),
+            },
+            ShowUnique => {
+                let mut seen = HashSet::new();
+                RollupValue::Array {
+                    array: present()
+                        .filter(|value| !entries(value).is_empty())
+                        .filter(|value| seen.insert(entries(value)))
+                        .filter_map(to_rollup_element)
+                        .collect(),
+                }
+            }
+            CountPerGroup | PercentPerGroup => return None,
+        };
+        Some(result)
+    }
+}
+
+fn count(count: usize) -> RollupValue {
+    RollupValue::Number {
+        number: Some(Number::from(count)),
+    }
+}
+
+fn percent(
+    count: usize,
+    total: usize,
+) -> RollupValue {
+    if total == 0 {
+        return self::count(0);
+    }
+    number(Some(count as f64 / total as f64))
+}
+
+fn number(number: Option<f64>) -> RollupValue {
+    RollupValue::Number {
+        number: number.and_then(number_from_f64),
+    }
+}
+
+fn date(
+    start: Option<DateOrDateTime>,
+    end: Option<DateOrDateTime>,
+) -> RollupValue {
+    RollupValue::Date {
+        date: start.map(|start| DateValue {
+            start,
+            end,
+            time_zone: None,
+        }),
+    }
+}
+
+fn median(mut numbers: Vec<f64>) -> Option<f64> {
+    numbers.sort_by(f64::total_cmp);
+    let middle = numbers.len() / 2;
+    match numbers.len() {
+        0 => None,
+        len if len.is_multiple_of(2) => Some((numbers[middle - 1] + numbers[middle]) / 2.0),
+        _ => Some(numbers[middle]),
+    }
+}
+
+fn not_empty(values: &[Option<&PropertyValue>]) -> usize {
+    values
+        .iter()
+        .flatten()
+        .filter(|value| !entries(value).is_empty())
+        .count()
+}
+
+fn checked(values: &[Option<&PropertyValue>]) -> usize {
+    values
+        .iter()
+        .flatten()
+        .filter(|value| {
+            matches!(
+                value,
+                PropertyValue::Checkbox { checkbox: true, .. }
+                    | PropertyValue::Formula {
+                        formula: FormulaResultValue::Boolean {
+                            boolean: Some(true)
+                        },
+                        ..
+                    }
+            )
+        })
+        .count()
+}
+
+/// The values of a property as Notion counts them, one per option, page, person or file.
+fn entries(value: &PropertyValue) -> Vec<String> {
+    use PropertyValue::*;
+    let entries: Vec<String> = match value {
+        Title { title: text, .. }
+        | Text {
+            rich_text: text, ..
+        } => vec![plain_text(text)],
+        Number { number, .. } => number.iter().map(|number| number.to_string()).collect(),
+        Select { select, .. } | Status { status: select, .. } => select
+            .iter()
+            .flat_map(|option| option.name.clone())
+            .collect(),
+        MultiSelect { multi_select, .. } => multi_select
+            .iter()
+            .flatten()
+            .flat_map(|option| option.name.clone())
+            .collect(),
+        Date { date, .. } => date.iter().map(date_key).collect(),
+        Formula { formula, .. } => match formula {
+            FormulaResultValue::String { string } => string.iter().cloned().collect(),
+            FormulaResultValue::Number { number } => {
+                number.iter().map(|number| number.to_string()).collect()
+            }
+            FormulaResultValue::Boolean { boolean } => boolean
+                .filter(|checked| *checked)
+                .map(|checked| checked.to_string())
+                .into_iter()
+                .collect(),
+            FormulaResultValue::Date { date } => date.iter().map(date_key).collect(),
+        },
+        Relation { relation, .. } => relation
+            .iter()
+            .flatten()
+            .map(|page| page.id.to_string())
+            .collect(),
+        Rollup { rollup, .. } => match rollup {
+            Some(RollupValue::Number { number }) => {
+                number.iter().map(|number| number.to_string()).collect()
+            }
+            Some(RollupValue::Date { date }) => date.iter().map(date_key).collect(),
+            Some(RollupValue::Array { array }) => array
+                .iter()
+                .map(|element| serde_json::to_string(element).expect("Rollups serialize to JSON"))
+                .collect(),
+            None => vec![],
+        },
+        People { people, .. } => people.iter().map(|user| user.id().to_string()).collect(),
+        Files { files, .. } => files
+            .iter()
+            .flatten()
+            .map(|file| match file {
+                FileReference::External { name, .. }
+                | FileReference::File { name, .. }
+                | FileReference::FileUpload { name, .. } => name.clone(),
+            })
+            .collect(),
+        Checkbox { checkbox, .. } => checkbox.then(|| checkbox.to_string()).into_iter().collect(),
+        Url { url: text, .. }
+        | Email { email: text, .. }
+        | PhoneNumber {
+            phone_number: text, ..
+        } => text.iter().cloned().collect(),
+        CreatedTime { created_time, .. } => vec![created_time.to_rfc3339()],
+        CreatedBy { created_by, .. } => vec![created_by.id().to_string()],
+        LastEditedTime {
+            last_edited_time, ..
+        } => vec![last_edited_time.to_rfc3339()],
+        LastEditedBy { last_edited_by, .. } => vec![last_edited_by.id().to_string()],
+        Button { .. } => vec![],
+    };
+    entries
+        .into_iter()
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+fn date_key(date: &DateValue) -> String {
+    serde_json::to_string(date).expect("Dates serialize to JSON")
+}
+
+fn as_f64(value: &PropertyValue) -> Option<f64> {
+    match value {
+        PropertyValue::Number { number, .. }
+        | PropertyValue::Formula {
+            formula: FormulaResultValue::Number { number },
+            ..
+        }
+        | PropertyValue::Rollup {
+            rollup: Some(RollupValue::Number { number }),
+            ..
+        } => number.as_ref().and_then(Number::as_f64),
+        _ => None,
+    }
+}
+
+/// The start and the end of a date, which are the same unless it is a range.
+fn as_date_range(value: &PropertyValue) -> Option<(DateOrDateTime, DateOrDateTime)> {
+    let date = match value {
+        PropertyValue::Date { date, .. }
+        | PropertyValue::Formula {
+            formula: FormulaResultValue::Date { date },
+            ..
+        }
+        | PropertyValue::Rollup {
+            rollup: Some(RollupValue::Date { date }),
+            ..
+        } => date.clone()?,
+        PropertyValue::CreatedTime { created_time, .. } => {
+            DateOrDateTime::DateTime(*created_time).into()
+        }
+        PropertyValue::LastEditedTime {
+            last_edited_time, ..
+        } => DateOrDateTime::DateTime(*last_edited_time).into(),
+        _ => return None,
+    };
+    let DateValue { start, end, .. } = date;
+    let end = end.unwrap_or_else(|| start.clone());
+    Some((start, end))
+}
+
+/// The value as Notion lists it in an array rollup, titles become rich text.
+fn to_rollup_element(value: &PropertyValue) -> Option<RollupPropertyValue> {
+    use RollupPropertyValue::*;
+    let element = match value.clone() {
+        PropertyValue::Title { title, .. } => Text { rich_text: title },
+        PropertyValue::Text { rich_text, .. } => Text { rich_text },
+        PropertyValue::Number { number, .. } => Number { number },
+        PropertyValue::Select { select, .. } => Select { select },
+        PropertyValue::Status { status, .. } => Status { status },
+        PropertyValue::MultiSelect { multi_select, .. } => MultiSelect { multi_select },
+        PropertyValue::Date { date, .. } => Date { date },
+        PropertyValue::Formula { formula, .. } => Formula { formula },
+        PropertyValue::Relation { relation, .. } => Relation { relation },
+        PropertyValue::Rollup { rollup, .. } => Rollup { rollup },
+        PropertyValue::People { people, .. } => People { people },
+        PropertyValue::Files { files, .. } => Files { files },
+        PropertyValue::Checkbox { checkbox, .. } => Checkbox { checkbox },
+        PropertyValue::Url { url, .. } => Url { url },
+        PropertyValue::Email { email, .. } => Email { email },
+        // The element has no room for a missing number, it stays in the array as an empty one.
+        PropertyValue::PhoneNumber { phone_number, .. } => PhoneNumber {
+            phone_number: phone_number.unwrap_or_default(),
+        },
+        PropertyValue::CreatedTime { created_time, .. } => CreatedTime { created_time },
+        PropertyValue::CreatedBy { created_by, .. } => CreatedBy { created_by },
+        PropertyValue::LastEditedTime {
+            last_edited_time, ..
+        } => LastEditedTime { last_edited_time },
+        PropertyValue::LastEditedBy { last_edited_by, .. } => LastEditedBy { last_edited_by },
+        PropertyValue::Button { .. } => return None,
+    };
+    Some(element)
+}
@@ -1,6 +1,14 @@
-use crate::models::properties::{DateOrDateTime, RollupPropertyValue, RollupValue};
-use crate::models::properties::{FormulaResultValue, PropertyValue};
+use crate::ids::{PageId, PropertyId, UserId};
+use crate::models::properties::{
+    from_properties, to_properties, FormulaResultValue, PropertyError, PropertyValue,
+    WriteProperties, WritePropertyValue,
+};
+use crate::models::properties::{DateOrDateTime, DateValue, RollupPropertyValue, RollupValue};
+use crate::models::properties::{Rollup, RollupFunction};
+use crate::models::{Database, Number, Page, Properties};
 use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 #[test]
 fn verify_date_parsing() {
@@ -84,3 +92,494 @@ fn parse_phone_number_values() {
     let _property: Vec<PropertyValue> =
         serde_json::from_str(include_str!("tests/phone_number_property_value.json")).unwrap();
 }
+
+#[test]
+fn typed_property_accessors() {
+    let properties: Properties =
+        serde_json::from_str(include_str!("tests/page_properties.json")).unwrap();
+
+    assert_eq!(properties.text("Name"), Ok("Tuscan kale".to_string()));
+    assert_eq!(properties.number("Price"), Ok(Some(2.5)));
+    assert_eq!(properties.number_i64("Stock"), Ok(Some(12)));
+    assert_eq!(properties.number("Discount"), Ok(None));
+    assert_eq!(
+        properties
+            .multi_select("Store availability")
+            .unwrap()
+            .iter()
+            .map(|value| value.name.as_deref().unwrap())
+            .collect::<Vec<_>>(),
+        vec!["Gus's Community Market", "Rainbow Grocery"]
+    );
+    assert_eq!(
+        properties.select("Food group").unwrap().unwrap().name,
+        Some("Vegetable".to_string())
+    );
+    assert_eq!(properties.status("State"), Ok(None));
+    assert_eq!(
+        properties.date("Last ordered").unwrap().unwrap().start,
+        DateOrDateTime::Date(NaiveDate::from_ymd_opt(2022, 2, 22).unwrap())
+    );
+    assert_eq!(properties.checkbox("In stock"), Ok(true));
+    assert_eq!(
+        properties.url("Photo"),
+        Ok(Some("https://example.com/kale.jpg"))
+    );
+    assert_eq!(properties.email("Supplier email"), Ok(None));
+    assert_eq!(properties.people("Buyer").unwrap().len(), 1);
+    assert_eq!(
+        properties
+            .relation_ids("Recipes")
+            .unwrap()
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            "90eeeed8-2cdd-4af4-9cc1-3d24aff5f63c",
+            "a2da43ee-d43c-4285-8ae2-6d811f12629a"
+        ]
+    );
+    assert!(matches!(
+        properties.formula("Cost of next trip"),
+        Ok(FormulaResultValue::Number { number: Some(_) })
+    ));
+}
+
+#[test]
+fn typed_property_accessors_by_id() {
+    let properties: Properties =
+        serde_json::from_str(include_str!("tests/page_properties.json")).unwrap();
+    let id = PropertyId::from_str("O%5Bf").unwrap();
+
+    assert_eq!(properties.checkbox(&id), Ok(true));
+}
+
+#[test]
+fn typed_property_accessor_errors() {
+    let properties: Properties =
+        serde_json::from_str(include_str!("tests/page_properties.json")).unwrap();
+
+    assert_eq!(
+        properties.number("Missing"),
+        Err(PropertyError::Missing {
+            property: "'Missing'".to_string()
+        })
+    );
+    assert_eq!(
+        properties.checkbox("Price"),
+        Err(PropertyError::WrongType {
+            property: "'Price'".to_string(),
+            expected: "checkbox",
+            actual: "number",
+        })
+    );
+    assert!(matches!(
+        properties.number_i64("Price"),
+        Err(PropertyError::NotAnInteger { .. })
+    ));
+    assert_eq!(
+        properties.checkbox("Price").unwrap_err().to_string(),
+        "Property 'Price' is a number property, expected checkbox"
+    );
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+struct Product {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Price")]
+    price: f64,
+    #[serde(rename = "Discount")]
+    discount: Option<f64>,
+    #[serde(rename = "Store availability")]
+    stores: Vec<String>,
+    #[serde(rename = "Food group")]
+    food_group: Option<String>,
+    #[serde(rename = "Last ordered")]
+    last_ordered: NaiveDate,
+    #[serde(rename = "In stock")]
+    in_stock: bool,
+    #[serde(rename = "Recipes")]
+    recipes: Vec<String>,
+    #[serde(rename = "Cost of next trip", skip_serializing, default)]
+    cost_of_next_trip: u32,
+}
+
+fn product() -> Product {
+    Product {
+        name: "Tuscan kale".to_string(),
+        price: 2.5,
+        discount: None,
+        stores: vec![
+            "Gus's Community Market".to_string(),
+            "Rainbow Grocery".to_string(),
+        ],
+        food_group: Some("Vegetable".to_string()),
+        last_ordered: NaiveDate::from_ymd_opt(2022, 2, 22).unwrap(),
+        in_stock: true,
+        recipes: vec![
+            "90eeeed8-2cdd-4af4-9cc1-3d24aff5f63c".to_string(),
+            "a2da43ee-d43c-4285-8ae2-6d811f12629a".to_string(),
+        ],
+        cost_of_next_trip: 5,
+    }
+}
+
+#[test]
+fn deserialize_struct_from_properties() {
+    let properties: Properties =
+        serde_json::from_str(include_str!("tests/page_properties.json")).unwrap();
+
+    assert_eq!(from_properties::<Product>(&properties), Ok(product()));
+}
+
+#[test]
+fn deserialize_errors_name_the_property() {
+    let mut properties: Properties =
+        serde_json::from_str(include_str!("tests/page_properties.json")).unwrap();
+    properties.properties.remove("In stock");
+    assert_eq!(
+        from_properties::<Product>(&properties),
+        Err(PropertyError::Missing {
+            property: "'In stock'".to_string()
+        })
+    );
+
+    #[derive(Deserialize, Debug)]
+    struct WrongType {
+        #[serde(rename = "Price")]
+        _price: bool,
+    }
+    match from_properties::<WrongType>(&properties) {
+        Err(PropertyError::Invalid { property, .. }) => assert_eq!(property, "'Price'"),
+        result => panic!("Expected an invalid property, got {:?}", result),
+    }
+}
+
+#[test]
+fn serialize_struct_to_properties() {
+    let database: Database = serde_json::from_value(serde_json::json!({
+        "object": "database",
+        "id": "a1d8501e-1ac1-43e9-a6bd-ea9fe6c8822b",
+        "created_time": "2022-05-13T20:08:00.000Z",
+        "last_edited_time": "2022-05-13T20:08:00.000Z",
+        "title": [],
+        "icon": null,
+        "properties": {
+            "Name": { "id": "title", "type": "title", "title": {} },
+            "Price": { "id": "BJXS", "type": "number", "number": { "format": "dollar" } },
+            "Discount": { "id": "EPfe", "type": "number", "number": { "format": "percent" } },
+            "Store availability": {
+                "id": "F%5D",
+                "type": "multi_select",
+                "multi_select": { "options": [
+                    { "id": "t|O@", "name": "Gus's Community Market", "color": "yellow" }
+                ] }
+            },
+            "Food group": {
+                "id": "A%40Hk",
+                "type": "select",
+                "select": { "options": [] }
+            },
+            "Last ordered": { "id": "Jsfb", "type": "date", "date": {} },
+            "In stock": { "id": "O%5Bf", "type": "checkbox", "checkbox": {} },
+            "Recipes": {
+                "id": "YfIu",
+                "type": "relation",
+                "relation": {
+                    "database_id": "a1d8501e-1ac1-43e9-a6bd-ea9fe6c8822b",
+                    "type": "single_property",
+                    "single_property": {}
+                }
+            }
+        }
+    }))
+    .unwrap();
+
+    let properties = to_properties(&product(), &database).unwrap();
+    assert_eq!(properties.text("Name"), Ok("Tuscan kale".to_string()));
+    assert_eq!(properties.number("Discount"), Ok(None));
+    assert_eq!(
+        serde_json::to_value(properties.get("Store availability").unwrap()).unwrap(),
+        serde_json::json!({
+            "id": "F%5D",
+            "type": "multi_select",
+            "multi_select": [
+                { "id": "t|O@", "name": "Gus's Community Market", "color": "yellow" },
+                { "name": "Rainbow Grocery", "color": "default" }
+            ]
+        })
+    );
+    assert_eq!(
+        properties.date("Last ordered").unwrap().unwrap().start,
+        DateOrDateTime::Date(NaiveDate::from_ymd_opt(2022, 2, 22).unwrap())
+    );
+
+    let mut read_back: Product = from_properties(&properties).unwrap_or_else(|error| {
+        panic!("{}", error);
+    });
+    read_back.cost_of_next_trip = 5;
+    assert_eq!(read_back, product());
+}
+
+#[test]
+fn date_ranges_and_files_round_trip() {
+    #[derive(Deserialize, Serialize, Debug, PartialEq)]
+    struct Event {
+        #[serde(rename = "When")]
+        when: DateValue,
+        #[serde(rename = "Attachments")]
+        attachments: Vec<String>,
+    }
+
+    let database: Database = serde_json::from_value(serde_json::json!({
+        "object": "database",
+        "id": "a1d8501e-1ac1-43e9-a6bd-ea9fe6c8822b",
+        "created_time": "2022-05-13T20:08:00.000Z",
+        "last_edited_time": "2022-05-13T20:08:00.000Z",
+        "title": [],
+        "icon": null,
+        "properties": {
+            "When": { "id": "wh", "type": "date", "date": {} },
+            "Attachments": { "id": "at", "type": "files", "files": {} }
+        }
+    }))
+    .unwrap();
+    let event = Event {
+        when: DateValue {
+            start: DateOrDateTime::Date(NaiveDate::from_ymd_opt(2022, 2, 22).unwrap()),
+            end: Some(DateOrDateTime::Date(
+                NaiveDate::from_ymd_opt(2022, 2, 24).unwrap(),
+            )),
+            time_zone: None,
+        },
+        attachments: vec![
+            "https://example.com/agenda.pdf?version=2".to_string(),
+            "b52b8ed6-e029-4707-a671-832549c09de3".to_string(),
+        ],
+    };
+
+    let properties = to_properties(&event, &database).unwrap();
+    assert_eq!(properties.date("When"), Ok(Some(&event.when)));
+    assert_eq!(
+        serde_json::to_value(properties.get("Attachments").unwrap()).unwrap()["files"],
+        serde_json::json!([
+            {
+                "type": "external",
+                "name": "agenda.pdf",
+                "external": { "url": "https://example.com/agenda.pdf?version=2" }
+            },
+            {
+                "type": "file_upload",
+                "name": "b52b8ed6-e029-4707-a671-832549c09de3",
+                "file_upload": { "id": "b52b8ed6-e029-4707-a671-832549c09de3" }
+            }
+        ])
+    );
+    assert_eq!(from_properties::<Event>(&properties), Ok(event));
+
+    // A range can't be read into a single date without losing its end.
+    #[derive(Deserialize, Debug)]
+    struct Day {
+        #[serde(rename = "When")]
+        _when: NaiveDate,
+    }
+    assert!(matches!(
+        from_properties::<Day>(&properties),
+        Err(PropertyError::Invalid { .. })
+    ));
+
+    // Single dates can be read into a DateValue too.
+    let mut single = properties.clone();
+    if let Some(PropertyValue::Date {
+        date: Some(date), ..
+    }) = single.properties.get_mut("When")
+    {
+        date.end = None;
+    }
+    let single: Event = from_properties(&single).unwrap();
+    assert_eq!(single.when.end, None);
+}
+
+#[test]
+fn serialize_write_properties() {
+    let properties = WriteProperties::new()
+        .with("Name", WritePropertyValue::title("Tuscan kale"))
+        .with("Price", WritePropertyValue::number(3.5))
+        .with(
+            "Food group",
+            WritePropertyValue::select_by_name("Vegetable"),
+        )
+        .with(
+            "Last ordered",
+            WritePropertyValue::date(NaiveDate::from_ymd_opt(2022, 2, 22).unwrap()),
+        )
+        .with(
+            "Recipes",
+            WritePropertyValue::relation([PageId::from_str(
+                "90eeeed8-2cdd-4af4-9cc1-3d24aff5f63c",
+            )
+            .unwrap()]),
+        )
+        .with(
+            "Buyer",
+            WritePropertyValue::people([
+                UserId::from_str("71e95936-2737-4e11-b03d-f174f6f13087").unwrap()
+            ]),
+        )
+        .with("In stock", WritePropertyValue::checkbox(true));
+
+    assert_eq!(
+        serde_json::to_value(&properties).unwrap(),
+        serde_json::json!({
+            "Name": { "title": [{ "type": "text", "text": { "content": "Tuscan kale", "link": null }, "plain_text": "Tuscan kale" }] },
+            "Price": { "number": 3.5 },
+            "Food group": { "select": { "name": "Vegetable" } },
+            "Last ordered": { "date": { "start": "2022-02-22", "end": null, "time_zone": null } },
+            "Recipes": { "relation": [{ "id": "90eeeed8-2cdd-4af4-9cc1-3d24aff5f63c" }] },
+            "Buyer": { "people": [{ "object": "user", "id": "71e95936-2737-4e11-b03d-f174f6f13087" }] },
+            "In stock": { "checkbox": true }
+        })
+    );
+}
+
+#[test]
+fn write_properties_from_read_properties() {
+    let properties: Properties =
+        serde_json::from_str(include_str!("tests/page_properties.json")).unwrap();
+    let written = serde_json::to_value(WriteProperties::from(properties)).unwrap();
+
+    assert!(written.get("Cost of next trip").is_none());
+    assert_eq!(
+        written["Food group"],
+        serde_json::json!({ "select": { "id": "5e8e7e8f", "name": "Vegetable" } })
+    );
+    assert_eq!(
+        written["Buyer"],
+        serde_json::json!({ "people": [{ "object": "user", "id": "71e95936-2737-4e11-b03d-f174f6f13087" }] })
+    );
+}
+
+fn rollup(
+    property_id: &str,
+    property_name: &str,
+    function: RollupFunction,
+) -> Option<RollupValue> {
+    let pages: Vec<Page> = serde_json::from_str(include_str!("tests/rollup_pages.json")).unwrap();
+    let rollup = Rollup {
+        relation_property_name: "Tasks".to_string(),
+        relation_property_id: PropertyId::from_str("t%3Dsk").unwrap(),
+        rollup_property_name: property_name.to_string(),
+        rollup_property_id: property_id.to_string(),
+        function,
+    };
+    rollup.compute(&pages)
+}
+
+fn rollup_number(
+    property: &str,
+    function: RollupFunction,
+) -> Option<f64> {
+    match rollup("", property, function) {
+        Some(RollupValue::Number { number }) => number.as_ref().and_then(Number::as_f64),
+        other => panic!("Expected a number, got {:?}", other),
+    }
+}
+
+#[test]
+fn compute_counting_rollups() {
+    use RollupFunction::*;
+    assert_eq!(rollup_number("Name", Count), Some(4.0));
+    assert_eq!(rollup_number("Tags", CountValues), Some(4.0));
+    assert_eq!(rollup_number("Tags", Unique), Some(2.0));
+    assert_eq!(rollup_number("Tags", Empty), Some(1.0));
+    assert_eq!(rollup_number("Estimate", NotEmpty), Some(3.0));
+    assert_eq!(rollup_number("Estimate", PercentEmpty), Some(0.25));
+    assert_eq!(rollup_number("Done", Checked), Some(2.0));
+    assert_eq!(rollup_number("Done", Unchecked), Some(2.0));
+    assert_eq!(rollup_number("Done", PercentChecked), Some(0.5));
+    assert_eq!(rollup("", "Tags", CountPerGroup), None);
+}
+
+#[test]
+fn compute_number_rollups() {
+    use RollupFunction::*;
+    assert_eq!(rollup_number("Estimate", Sum), Some(13.0));
+    assert_eq!(rollup_number("Estimate", Average), Some(13.0 / 3.0));
+    assert_eq!(rollup_number("Estimate", Median), Some(3.0));
+    assert_eq!(rollup_number("Estimate", Min), Some(2.0));
+    assert_eq!(rollup_number("Estimate", Max), Some(8.0));
+    assert_eq!(rollup_number("Estimate", Range), Some(6.0));
+    assert_eq!(rollup_number("Missing", Sum), Some(0.0));
+    assert_eq!(rollup_number("Missing", Average), None);
+    // Whole numbers are integers, like Notion returns them.
+    assert_eq!(
+        rollup("", "Estimate", Sum),
+        Some(RollupValue::Number {
+            number: Some(Number::from(13))
+        })
+    );
+}
+
+#[test]
+fn compute_date_rollups() {
+    let date = |day| DateOrDateTime::Date(NaiveDate::from_ymd_opt(2024, 3, day).unwrap());
+
+    assert_eq!(
+        rollup("D%7Cue", "Renamed", RollupFunction::EarliestDate),
+        Some(RollupValue::Date {
+            date: Some(date(4).into())
+        })
+    );
+    assert_eq!(
+        rollup("D%7Cue", "Renamed", RollupFunction::LatestDate),
+        Some(RollupValue::Date {
+            date: Some(date(12).into())
+        })
+    );
+    assert_eq!(
+        rollup("D%7Cue", "Renamed", RollupFunction::DateRange),
+        Some(RollupValue::Date {
+            date: Some(DateValue {
+                start: date(4),
+                end: Some(date(12)),
+                time_zone: None,
+            })
+        })
+    );
+}
+
+#[test]
+fn compute_array_rollups() {
+    let array = |property, function| match rollup("", property, function) {
+        Some(RollupValue::Array { array }) => array,
+        other => panic!("Expected an array, got {:?}", other),
+    };
+
+    let names: Vec<String> = array("Name", RollupFunction::ShowOriginal)
+        .into_iter()
+        .map(|element| match element {
+            RollupPropertyValue::Text { rich_text } => rich_text[0].plain_text().to_string(),
+            other => panic!("Expected rich text, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(names, vec!["Design", "Build", "Test", "Release"]);
+
+    let phones: Vec<String> = array("Phone", RollupFunction::ShowOriginal)
+        .into_iter()
+        .map(|element| match element {
+            RollupPropertyValue::PhoneNumber { phone_number } => phone_number,
+            other => panic!("Expected a phone number, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        phones,
+        vec!["+1 555 0100", "", "+1 555 0102", "+1 555 0103"]
+    );
+
+    let tags = array("Tags", RollupFunction::ShowUnique);
+    assert_eq!(tags.len(), 3);
+    assert!(tags
+        .iter()
+        .all(|element| matches!(element, RollupPropertyValue::MultiSelect { .. })));
+}
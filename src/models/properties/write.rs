@@ -0,0 +1,316 @@
+use super::{
+    DateOrDateTime, DateValue, FileReference, PropertyKind, PropertyValue, RelationValue,
+    SelectOptionId, SelectedValue,
+};
+use crate::ids::{PageId, UserId};
+use crate::models::mapping::rich_text;
+use crate::models::text::RichText;
+use crate::models::users::User;
+use crate::models::{Number, Properties};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+/// The properties sent when creating or updating a page.
+///
+/// Keys are property names or [PropertyId](crate::ids::PropertyId)s,
+/// only the properties that should change have to be present.
+#[derive(Serialize, Debug, Eq, PartialEq, Clone, Default)]
+pub struct WriteProperties {
+    #[serde(flatten)]
+    pub properties: HashMap<String, WritePropertyValue>,
+}
+
+impl WriteProperties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the property `name` to `value`.
+    pub fn with<T: Into<String>>(
+        mut self,
+        name: T,
+        value: WritePropertyValue,
+    ) -> Self {
+        self.properties.insert(name.into(), value);
+        self
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, WritePropertyValue)> for WriteProperties {
+    fn from_iter<T: IntoIterator<Item = (K, WritePropertyValue)>>(iter: T) -> Self {
+        WriteProperties {
+            properties: iter
+                .into_iter()
+                .map(|(name, value)| (name.into(), value))
+                .collect(),
+        }
+    }
+}
+
+/// Keeps the values that can be written, computed values like formulas are left out.
+impl From<Properties> for WriteProperties {
+    fn from(properties: Properties) -> Self {
+        properties
+            .properties
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, WritePropertyValue::from_value(value)?)))
+            .collect()
+    }
+}
+
+/// A select, multi select or status option referenced by its id or name.
+/// Notion creates a select option if no option has the given name.
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+pub struct SelectReference {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<SelectOptionId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl From<SelectedValue> for SelectReference {
+    fn from(selected: SelectedValue) -> Self {
+        SelectReference {
+            id: selected.id,
+            name: selected.name,
+        }
+    }
+}
+
+/// A user, only the id is sent.
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(tag = "object", rename = "user")]
+pub struct UserReference {
+    pub id: UserId,
+}
+
+impl From<User> for UserReference {
+    fn from(user: User) -> Self {
+        let (User::Person { common, .. } | User::Bot { common, .. } | User::User { common, .. }) =
+            user;
+        UserReference { id: common.id }
+    }
+}
+
+/// The value of a property as the create and update page endpoints accept it.
+/// See <https://developers.notion.com/reference/page-property-values>
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum WritePropertyValue {
+    Title { title: Vec<RichText> },
+    Text { rich_text: Vec<RichText> },
+    Number { number: Option<Number> },
+    Select { select: Option<SelectReference> },
+    Status { status: Option<SelectReference> },
+    MultiSelect { multi_select: Vec<SelectReference> },
+    Date { date: Option<DateValue> },
+    Relation { relation: Vec<RelationValue> },
+    People { people: Vec<UserReference> },
+    Files { files: Vec<FileReference> },
+    Checkbox { checkbox: bool },
+    Url { url: Option<String> },
+    Email { email: Option<String> },
+    PhoneNumber { phone_number: Option<String> },
+}
+
+fn by_name<T: Into<String>>(name: T) -> SelectReference {
+    SelectReference {
+        id: None,
+        name: Some(name.into()),
+    }
+}
+
+fn by_id(id: SelectOptionId) -> SelectReference {
+    SelectReference {
+        id: Some(id),
+        name: None,
+    }
+}
+
+impl WritePropertyValue {
+    pub fn title<T: Into<String>>(text: T) -> Self {
+        WritePropertyValue::Title {
+            title: rich_text(&text.into()),
+        }
+    }
+
+    pub fn rich_text<T: Into<String>>(text: T) -> Self {
+        WritePropertyValue::Text {
+            rich_text: rich_text(&text.into()),
+        }
+    }
+
+    /// A number, infinite numbers and `NaN` clear the property.
+    pub fn number(number: f64) -> Self {
+        WritePropertyValue::Number {
+            number: Number::from_f64(number),
+        }
+    }
+
+    pub fn integer(number: i64) -> Self {
+        WritePropertyValue::Number {
+            number: Some(number.into()),
+        }
+    }
+
+    pub fn select_by_name<T: Into<String>>(name: T) -> Self {
+        WritePropertyValue::Select {
+            select: Some(by_name(name)),
+        }
+    }
+
+    pub fn select_by_id(id: SelectOptionId) -> Self {
+        WritePropertyValue::Select {
+            select: Some(by_id(id)),
+        }
+    }
+
+    pub fn status_by_name<T: Into<String>>(name: T) -> Self {
+        WritePropertyValue::Status {
+            status: Some(by_name(name)),
+        }
+    }
+
+    pub fn multi_select_by_name<I, T>(names: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        WritePropertyValue::MultiSelect {
+            multi_select: names.into_iter().map(by_name).collect(),
+        }
+    }
+
+    pub fn date(date: NaiveDate) -> Self {
+        WritePropertyValue::Date {
+            date: Some(DateOrDateTime::Date(date).into()),
+        }
+    }
+
+    pub fn date_time(time: DateTime<Utc>) -> Self {
+        WritePropertyValue::Date {
+            date: Some(DateOrDateTime::DateTime(time).into()),
+        }
+    }
+
+    pub fn date_range(
+        start: DateOrDateTime,
+        end: DateOrDateTime,
+    ) -> Self {
+        WritePropertyValue::Date {
+            date: Some(DateValue {
+                start,
+                end: Some(end),
+                time_zone: None,
+            }),
+        }
+    }
+
+    pub fn relation<I: IntoIterator<Item = PageId>>(pages: I) -> Self {
+        WritePropertyValue::Relation {
+            relation: pages.into_iter().map(|id| RelationValue { id }).collect(),
+        }
+    }
+
+    pub fn people<I: IntoIterator<Item = UserId>>(users: I) -> Self {
+        WritePropertyValue::People {
+            people: users.into_iter().map(|id| UserReference { id }).collect(),
+        }
+    }
+
+    pub fn files<I: IntoIterator<Item = FileReference>>(files: I) -> Self {
+        WritePropertyValue::Files {
+            files: files.into_iter().collect(),
+        }
+    }
+
+    pub fn checkbox(checked: bool) -> Self {
+        WritePropertyValue::Checkbox { checkbox: checked }
+    }
+
+    pub fn url<T: Into<String>>(url: T) -> Self {
+        WritePropertyValue::Url {
+            url: Some(url.into()),
+        }
+    }
+
+    pub fn email<T: Into<String>>(email: T) -> Self {
+        WritePropertyValue::Email {
+            email: Some(email.into()),
+        }
+    }
+
+    pub fn phone_number<T: Into<String>>(phone_number: T) -> Self {
+        WritePropertyValue::PhoneNumber {
+            phone_number: Some(phone_number.into()),
+        }
+    }
+
+    /// Converts a value read from a page, `None` for computed values like formulas.
+    pub fn from_value(value: PropertyValue) -> Option<Self> {
+        Some(match value {
+            PropertyValue::Title { title, .. } => WritePropertyValue::Title { title },
+            PropertyValue::Text { rich_text, .. } => WritePropertyValue::Text { rich_text },
+            PropertyValue::Number { number, .. } => WritePropertyValue::Number { number },
+            PropertyValue::Select { select, .. } => WritePropertyValue::Select {
+                select: select.map(SelectReference::from),
+            },
+            PropertyValue::Status { status, .. } => WritePropertyValue::Status {
+                status: status.map(SelectReference::from),
+            },
+            PropertyValue::MultiSelect { multi_select, .. } => WritePropertyValue::MultiSelect {
+                multi_select: multi_select
+                    .into_iter()
+                    .flatten()
+                    .map(SelectReference::from)
+                    .collect(),
+            },
+            PropertyValue::Date { date, .. } => WritePropertyValue::Date { date },
+            PropertyValue::Relation { relation, .. } => WritePropertyValue::Relation {
+                relation: relation.unwrap_or_default(),
+            },
+            PropertyValue::People { people, .. } => WritePropertyValue::People {
+                people: people.into_iter().map(UserReference::from).collect(),
+            },
+            PropertyValue::Files { files, .. } => WritePropertyValue::Files {
+                files: files.unwrap_or_default(),
+            },
+            PropertyValue::Checkbox { checkbox, .. } => WritePropertyValue::Checkbox { checkbox },
+            PropertyValue::Url { url, .. } => WritePropertyValue::Url { url },
+            PropertyValue::Email { email, .. } => WritePropertyValue::Email { email },
+            PropertyValue::PhoneNumber { phone_number, .. } => {
+                WritePropertyValue::PhoneNumber { phone_number }
+            }
+            PropertyValue::Formula { .. }
+            | PropertyValue::Rollup { .. }
+            | PropertyValue::CreatedTime { .. }
+            | PropertyValue::CreatedBy { .. }
+            | PropertyValue::LastEditedTime { .. }
+            | PropertyValue::LastEditedBy { .. }
+            | PropertyValue::Button { .. } => return None,
+        })
+    }
+
+    /// The type of property this value is written to.
+    pub fn kind(&self) -> PropertyKind {
+        use WritePropertyValue::*;
+        match self {
+            Title { .. } => PropertyKind::Title,
+            Text { .. } => PropertyKind::RichText,
+            Number { .. } => PropertyKind::Number,
+            Select { .. } => PropertyKind::Select,
+            Status { .. } => PropertyKind::Status,
+            MultiSelect { .. } => PropertyKind::MultiSelect,
+            Date { .. } => PropertyKind::Date,
+            Relation { .. } => PropertyKind::Relation,
+            People { .. } => PropertyKind::People,
+            Files { .. } => PropertyKind::Files,
+            Checkbox { .. } => PropertyKind::Checkbox,
+            Url { .. } => PropertyKind::Url,
+            Email { .. } => PropertyKind::Email,
+            PhoneNumber { .. } => PropertyKind::PhoneNumber,
+        }
+    }
+}
@@ -5,6 +5,16 @@ use chrono::{DateTime, Utc};
 use serde::ser::SerializeMap;
 use serde::{Serialize, Serializer};
 
+mod builder;
+mod matching;
+
+pub use builder::{
+    created_time, last_edited_time, prop, CheckboxFilter, DateFilter, FilesFilter, FormulaFilter,
+    MultiSelectFilter, NumberFilter, PeopleFilter, PropertyFilter, RelationFilter, SelectFilter,
+    StatusFilter, TextFilter,
+};
+pub use matching::sort_pages;
+
 #[derive(Serialize, Debug, Eq, PartialEq, Hash, Copy, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum SortDirection {
@@ -44,7 +54,7 @@ pub struct Filter {
     value: FilterValue,
 }
 
-#[derive(Serialize, Debug, Eq, PartialEq, Default)]
+#[derive(Serialize, Debug, Eq, PartialEq, Default, Clone)]
 pub struct SearchRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     query: Option<String>,
@@ -275,6 +285,15 @@ pub enum PropertyCondition {
     Formula(FormulaCondition),
 }
 
+/// <https://developers.notion.com/reference/post-database-query-filter#timestamp>
+#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(tag = "timestamp")]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampCondition {
+    CreatedTime { created_time: DateCondition },
+    LastEditedTime { last_edited_time: DateCondition },
+}
+
 #[derive(Serialize, Debug, Eq, PartialEq, Clone)]
 #[serde(untagged)]
 pub enum FilterCondition {
@@ -283,6 +302,11 @@ pub enum FilterCondition {
         #[serde(flatten)]
         condition: PropertyCondition,
     },
+    /// Filters on the time a page was created or last edited.
+    Timestamp {
+        #[serde(flatten)]
+        condition: TimestampCondition,
+    },
     /// Returns pages when **all** of the filters inside the provided vector match.
     And { and: Vec<FilterCondition> },
     /// Returns pages when **any** of the filters inside the provided vector match.
@@ -334,6 +358,21 @@ impl Pageable for DatabaseQuery {
     }
 }
 
+impl Pageable for SearchRequest {
+    fn start_from(
+        self,
+        starting_point: Option<PagingCursor>,
+    ) -> Self {
+        SearchRequest {
+            paging: Some(Paging {
+                start_cursor: starting_point,
+                page_size: self.paging.and_then(|p| p.page_size),
+            }),
+            ..self
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum NotionSearch {
     /// When supplied, limits which pages are returned by comparing the query to the page title.
@@ -406,7 +445,8 @@ mod tests {
     mod text_filters {
         use crate::models::search::PropertyCondition::{Checkbox, Number, RichText, Select};
         use crate::models::search::{
-            CheckboxCondition, FilterCondition, NumberCondition, SelectCondition, TextCondition,
+            CheckboxCondition, DateCondition, FilterCondition, NumberCondition, SelectCondition,
+            TextCondition, TimestampCondition,
         };
         use serde_json::json;
 
@@ -466,6 +506,26 @@ mod tests {
             Ok(())
         }
 
+        #[test]
+        fn timestamp_on_or_after() -> Result<(), Box<dyn std::error::Error>> {
+            let json = serde_json::to_value(FilterCondition::Timestamp {
+                condition: TimestampCondition::LastEditedTime {
+                    last_edited_time: DateCondition::OnOrAfter(
+                        "2022-05-13T20:08:00Z".parse().unwrap(),
+                    ),
+                },
+            })?;
+            assert_eq!(
+                json,
+                json!({
+                    "timestamp": "last_edited_time",
+                    "last_edited_time": {"on_or_after": "2022-05-13T20:08:00Z"}
+                })
+            );
+
+            Ok(())
+        }
+
         #[test]
         fn compound_query_and() -> Result<(), Box<dyn std::error::Error>> {
             let json = serde_json::to_value(FilterCondition::And {
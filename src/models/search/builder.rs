@@ -0,0 +1,421 @@
+//! A shorter way to write a [FilterCondition], starting from [prop()]:
+//!
+//! ```
+//! use rusticnotion::ids::UserId;
+//! use rusticnotion::models::search::{prop, FilterCondition};
+//! use std::str::FromStr;
+//!
+//! let owner = UserId::from_str("71e95936-2737-4e11-b03d-f174f6f13087").unwrap();
+//! let filter: FilterCondition = prop("Status").status().eq("Done")
+//!     & prop("Due").date().before(chrono::Utc::now())
+//!     | prop("Owner").people().contains(owner);
+//! ```
+//!
+//! `&` and `|` combine conditions into [FilterCondition::And] and [FilterCondition::Or],
+//! merging groups of the same kind instead of nesting them.
+
+use super::{
+    CheckboxCondition, DateCondition, FilesCondition, FilterCondition, FormulaCondition,
+    MultiSelectCondition, NumberCondition, PeopleCondition, PropertyCondition, RelationCondition,
+    SelectCondition, StatusCondition, TextCondition, TimestampCondition,
+};
+use crate::ids::{PageId, UserId};
+use crate::models::Number;
+use chrono::{DateTime, Utc};
+use std::ops::{BitAnd, BitOr};
+
+/// Starts a condition on the property with the given name or id,
+/// the method for its type then chooses the condition.
+pub fn prop<S: Into<String>>(property: S) -> PropertyFilter {
+    PropertyFilter {
+        property: property.into(),
+    }
+}
+
+/// Starts a condition on the time pages were created.
+pub fn created_time() -> DateFilter {
+    DateFilter(Target::Timestamp(|created_time| {
+        TimestampCondition::CreatedTime { created_time }
+    }))
+}
+
+/// Starts a condition on the time pages were last edited.
+pub fn last_edited_time() -> DateFilter {
+    DateFilter(Target::Timestamp(|last_edited_time| {
+        TimestampCondition::LastEditedTime { last_edited_time }
+    }))
+}
+
+/// A property to filter on, see [prop()].
+#[derive(Debug, Clone)]
+pub struct PropertyFilter {
+    property: String,
+}
+
+impl PropertyFilter {
+    fn target<C>(
+        self,
+        wrap: fn(C) -> PropertyCondition,
+    ) -> Target<C> {
+        Target::Property {
+            property: self.property,
+            wrap,
+        }
+    }
+
+    /// Conditions on a title, text, url, email or phone number property.
+    pub fn rich_text(self) -> TextFilter {
+        TextFilter(self.target(PropertyCondition::RichText))
+    }
+
+    pub fn number(self) -> NumberFilter {
+        NumberFilter(self.target(PropertyCondition::Number))
+    }
+
+    pub fn checkbox(self) -> CheckboxFilter {
+        CheckboxFilter(self.target(PropertyCondition::Checkbox))
+    }
+
+    pub fn select(self) -> SelectFilter {
+        SelectFilter(self.target(PropertyCondition::Select))
+    }
+
+    pub fn status(self) -> StatusFilter {
+        StatusFilter(self.target(PropertyCondition::Staus))
+    }
+
+    pub fn multi_select(self) -> MultiSelectFilter {
+        MultiSelectFilter(self.target(PropertyCondition::MultiSelect))
+    }
+
+    /// Conditions on a date, created time or last edited time property.
+    pub fn date(self) -> DateFilter {
+        DateFilter(self.target(PropertyCondition::Date))
+    }
+
+    /// Conditions on a people, created by or last edited by property.
+    pub fn people(self) -> PeopleFilter {
+        PeopleFilter(self.target(PropertyCondition::People))
+    }
+
+    pub fn files(self) -> FilesFilter {
+        FilesFilter(self.target(PropertyCondition::Files))
+    }
+
+    pub fn relation(self) -> RelationFilter {
+        RelationFilter(self.target(PropertyCondition::Relation))
+    }
+
+    /// Conditions on the result of a formula, by the type of the result.
+    pub fn formula(self) -> FormulaFilter {
+        FormulaFilter(self)
+    }
+}
+
+/// The result type of a formula to filter on, see [PropertyFilter::formula()].
+#[derive(Debug, Clone)]
+pub struct FormulaFilter(PropertyFilter);
+
+impl FormulaFilter {
+    pub fn text(self) -> TextFilter {
+        TextFilter(
+            self.0
+                .target(|condition| PropertyCondition::Formula(FormulaCondition::Text(condition))),
+        )
+    }
+
+    pub fn number(self) -> NumberFilter {
+        NumberFilter(
+            self.0.target(|condition| {
+                PropertyCondition::Formula(FormulaCondition::Number(condition))
+            }),
+        )
+    }
+
+    pub fn checkbox(self) -> CheckboxFilter {
+        CheckboxFilter(
+            self.0.target(|condition| {
+                PropertyCondition::Formula(FormulaCondition::Checkbox(condition))
+            }),
+        )
+    }
+
+    pub fn date(self) -> DateFilter {
+        DateFilter(
+            self.0
+                .target(|condition| PropertyCondition::Formula(FormulaCondition::Date(condition))),
+        )
+    }
+}
+
+/// Where a condition of type `C` ends up in the [FilterCondition].
+#[derive(Debug, Clone)]
+enum Target<C> {
+    Property {
+        property: String,
+        wrap: fn(C) -> PropertyCondition,
+    },
+    Timestamp(fn(C) -> TimestampCondition),
+}
+
+impl<C> Target<C> {
+    fn filter(
+        self,
+        condition: C,
+    ) -> FilterCondition {
+        match self {
+            Target::Property { property, wrap } => FilterCondition::Property {
+                property,
+                condition: wrap(condition),
+            },
+            Target::Timestamp(wrap) => FilterCondition::Timestamp {
+                condition: wrap(condition),
+            },
+        }
+    }
+}
+
+/// Defines a builder for the conditions of one property type, with a method per condition.
+macro_rules! condition_builder {
+    (
+        $(#[$doc:meta])*
+        $name:ident($condition:ident) {
+            $($method:ident($($arg:ident: $arg_type:ty),*) => $variant:ident$(($value:expr))?;)+
+        }
+    ) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone)]
+        pub struct $name(Target<$condition>);
+
+        impl $name {
+            $(
+                pub fn $method(self, $($arg: $arg_type),*) -> FilterCondition {
+                    self.0.filter($condition::$variant$(($value))?)
+                }
+            )+
+        }
+    };
+}
+
+condition_builder! {
+    /// Conditions on text, see [PropertyFilter::rich_text()].
+    TextFilter(TextCondition) {
+        eq(text: impl Into<String>) => Equals(text.into());
+        ne(text: impl Into<String>) => DoesNotEqual(text.into());
+        contains(text: impl Into<String>) => Contains(text.into());
+        does_not_contain(text: impl Into<String>) => DoesNotContain(text.into());
+        starts_with(text: impl Into<String>) => StartsWith(text.into());
+        ends_with(text: impl Into<String>) => EndsWith(text.into());
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+    }
+}
+
+condition_builder! {
+    /// Conditions on numbers, see [PropertyFilter::number()].
+    NumberFilter(NumberCondition) {
+        eq(number: impl Into<Number>) => Equals(number.into());
+        ne(number: impl Into<Number>) => DoesNotEqual(number.into());
+        gt(number: impl Into<Number>) => GreaterThan(number.into());
+        lt(number: impl Into<Number>) => LessThan(number.into());
+        ge(number: impl Into<Number>) => GreaterThanOrEqualTo(number.into());
+        le(number: impl Into<Number>) => LessThanOrEqualTo(number.into());
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+    }
+}
+
+condition_builder! {
+    /// Conditions on checkboxes, see [PropertyFilter::checkbox()].
+    CheckboxFilter(CheckboxCondition) {
+        eq(checked: bool) => Equals(checked);
+        ne(checked: bool) => DoesNotEqual(checked);
+    }
+}
+
+condition_builder! {
+    /// Conditions on the option name of a select, see [PropertyFilter::select()].
+    SelectFilter(SelectCondition) {
+        eq(option: impl Into<String>) => Equals(option.into());
+        ne(option: impl Into<String>) => DoesNotEqual(option.into());
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+    }
+}
+
+condition_builder! {
+    /// Conditions on the option name of a status, see [PropertyFilter::status()].
+    StatusFilter(StatusCondition) {
+        eq(option: impl Into<String>) => Equals(option.into());
+        ne(option: impl Into<String>) => DoesNotEqual(option.into());
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+    }
+}
+
+condition_builder! {
+    /// Conditions on the option names of a multi-select, see [PropertyFilter::multi_select()].
+    MultiSelectFilter(MultiSelectCondition) {
+        contains(option: impl Into<String>) => Contains(option.into());
+        does_not_contain(option: impl Into<String>) => DoesNotContain(option.into());
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+    }
+}
+
+condition_builder! {
+    /// Conditions on dates, see [PropertyFilter::date()], [created_time()] and
+    /// [last_edited_time()]. Notion compares only the date, the time is ignored.
+    DateFilter(DateCondition) {
+        eq(date: DateTime<Utc>) => Equals(date);
+        before(date: DateTime<Utc>) => Before(date);
+        after(date: DateTime<Utc>) => After(date);
+        on_or_before(date: DateTime<Utc>) => OnOrBefore(date);
+        on_or_after(date: DateTime<Utc>) => OnOrAfter(date);
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+        past_week() => PastWeek;
+        past_month() => PastMonth;
+        past_year() => PastYear;
+        next_week() => NextWeek;
+        next_month() => NextMonth;
+        next_year() => NextYear;
+    }
+}
+
+condition_builder! {
+    /// Conditions on people, see [PropertyFilter::people()].
+    PeopleFilter(PeopleCondition) {
+        contains(user: UserId) => Contains(user);
+        does_not_contain(user: UserId) => DoesNotContain(user);
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+    }
+}
+
+condition_builder! {
+    /// Conditions on files, see [PropertyFilter::files()].
+    FilesFilter(FilesCondition) {
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+    }
+}
+
+condition_builder! {
+    /// Conditions on related pages, see [PropertyFilter::relation()].
+    RelationFilter(RelationCondition) {
+        contains(page: PageId) => Contains(page);
+        does_not_contain(page: PageId) => DoesNotContain(page);
+        is_empty() => IsEmpty;
+        is_not_empty() => IsNotEmpty;
+    }
+}
+
+/// Both conditions have to match, conditions that are already groups of `and` are merged.
+impl BitAnd for FilterCondition {
+    type Output = FilterCondition;
+
+    fn bitand(
+        self,
+        other: FilterCondition,
+    ) -> FilterCondition {
+        let mut and = match self {
+            FilterCondition::And { and } => and,
+            condition => vec![condition],
+        };
+        match other {
+            FilterCondition::And { and: other } => and.extend(other),
+            condition => and.push(condition),
+        }
+        FilterCondition::And { and }
+    }
+}
+
+/// Either condition has to match, conditions that are already groups of `or` are merged.
+impl BitOr for FilterCondition {
+    type Output = FilterCondition;
+
+    fn bitor(
+        self,
+        other: FilterCondition,
+    ) -> FilterCondition {
+        let mut or = match self {
+            FilterCondition::Or { or } => or,
+            condition => vec![condition],
+        };
+        match other {
+            FilterCondition::Or { or: other } => or.extend(other),
+            condition => or.push(condition),
+        }
+        FilterCondition::Or { or }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{created_time, prop};
+    use crate::ids::UserId;
+    use crate::models::search::{
+        FilterCondition, PropertyCondition, StatusCondition, TextCondition,
+    };
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[test]
+    fn builds_property_conditions() {
+        assert_eq!(
+            prop("Status").status().eq("Done"),
+            FilterCondition::Property {
+                property: "Status".to_string(),
+                condition: PropertyCondition::Staus(StatusCondition::Equals("Done".to_string())),
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(prop("Score").formula().number().ge(10)).unwrap(),
+            json!({ "property": "Score", "formula": { "number": { "greater_than_or_equal_to": 10 } } })
+        );
+        assert_eq!(
+            serde_json::to_value(created_time().past_week()).unwrap(),
+            json!({ "timestamp": "created_time", "created_time": { "past_week": {} } })
+        );
+    }
+
+    #[test]
+    fn operators_flatten_groups() {
+        let owner = UserId::from_str("71e95936-2737-4e11-b03d-f174f6f13087").unwrap();
+        let name = |text: &str| prop("Name").rich_text().contains(text);
+
+        let filter = name("a") & name("b") & (name("c") & name("d"))
+            | prop("Owner").people().contains(owner)
+            | name("e");
+
+        assert_eq!(
+            serde_json::to_value(filter).unwrap(),
+            json!({ "or": [
+                { "and": [
+                    { "property": "Name", "rich_text": { "contains": "a" } },
+                    { "property": "Name", "rich_text": { "contains": "b" } },
+                    { "property": "Name", "rich_text": { "contains": "c" } },
+                    { "property": "Name", "rich_text": { "contains": "d" } },
+                ] },
+                { "property": "Owner", "people": { "contains": "71e95936-2737-4e11-b03d-f174f6f13087" } },
+                { "property": "Name", "rich_text": { "contains": "e" } },
+            ] })
+        );
+        assert_eq!(
+            prop("Name").rich_text().is_empty() & prop("Name").rich_text().is_not_empty(),
+            FilterCondition::And {
+                and: vec![
+                    FilterCondition::Property {
+                        property: "Name".to_string(),
+                        condition: PropertyCondition::RichText(TextCondition::IsEmpty),
+                    },
+                    FilterCondition::Property {
+                        property: "Name".to_string(),
+                        condition: PropertyCondition::RichText(TextCondition::IsNotEmpty),
+                    },
+                ],
+            }
+        );
+    }
+}
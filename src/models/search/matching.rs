@@ -0,0 +1,781 @@
+//! Evaluates filters and sorts of a [DatabaseQuery] against pages in memory,
+//! see [FilterCondition::matches()] and [DatabaseQuery::apply()].
+
+use super::{
+    CheckboxCondition, DatabaseQuery, DatabaseSort, DatabaseSortTimestamp, DateCondition,
+    FilesCondition, FilterCondition, FormulaCondition, MultiSelectCondition, NumberCondition,
+    PeopleCondition, PropertyCondition, RelationCondition, SelectCondition, SortDirection,
+    StatusCondition, TextCondition, TimestampCondition,
+};
+use crate::ids::{PageId, UserId};
+use crate::models::properties::{
+    plain_text, DateOrDateTime, DateValue, FormulaResultValue, PropertyValue, RollupValue,
+    SelectedValue,
+};
+use crate::models::{Number, Page};
+use chrono::{DateTime, Days, Months, NaiveDate, Utc};
+use std::cmp::Ordering;
+
+impl FilterCondition {
+    /// Whether Notion would return `page` for a query with this filter.
+    ///
+    /// Relative date conditions like [DateCondition::PastWeek] are evaluated against the
+    /// current time, use [FilterCondition::matches_at()] to choose it.
+    pub fn matches(
+        &self,
+        page: &Page,
+    ) -> bool {
+        self.matches_at(page, Utc::now())
+    }
+
+    /// Like [FilterCondition::matches()], with `now` as the current time.
+    ///
+    /// Properties are looked up by name, then by id. A condition on a property the page doesn't
+    /// have or of another type doesn't match, where Notion would reject the query.
+    pub fn matches_at(
+        &self,
+        page: &Page,
+        now: DateTime<Utc>,
+    ) -> bool {
+        match self {
+            FilterCondition::Property {
+                property,
+                condition,
+            } => page
+                .properties
+                .get_by_name_or_id(property)
+                .is_some_and(|value| property_matches(condition, value, now)),
+            FilterCondition::Timestamp { condition } => match condition {
+                TimestampCondition::CreatedTime { created_time } => {
+                    date_matches(created_time, Some(&time_range(page.created_time)), now)
+                }
+                TimestampCondition::LastEditedTime { last_edited_time } => date_matches(
+                    last_edited_time,
+                    Some(&time_range(page.last_edited_time)),
+                    now,
+                ),
+            },
+            FilterCondition::And { and } => and.iter().all(|filter| filter.matches_at(page, now)),
+            FilterCondition::Or { or } => or.iter().any(|filter| filter.matches_at(page, now)),
+        }
+    }
+}
+
+impl DatabaseSort {
+    /// The order of two pages by this sort.
+    ///
+    /// Empty values come last in both directions, as in Notion. Select and status options are
+    /// ordered by name, Notion orders them as in the database schema which pages don't include.
+    pub fn compare(
+        &self,
+        a: &Page,
+        b: &Page,
+    ) -> Ordering {
+        let key = |page: &Page| -> Option<SortKey> {
+            match (&self.property, self.timestamp) {
+                (Some(property), _) => page
+                    .properties
+                    .get_by_name_or_id(property)
+                    .and_then(sort_key),
+                (None, Some(DatabaseSortTimestamp::CreatedTime)) => {
+                    Some(SortKey::Date(page.created_time))
+                }
+                (None, Some(DatabaseSortTimestamp::LastEditedTime)) => {
+                    Some(SortKey::Date(page.last_edited_time))
+                }
+                (None, None) => None,
+            }
+        };
+        match (key(a), key(b)) {
+            (Some(a), Some(b)) => match self.direction {
+                SortDirection::Ascending => a.cmp(&b),
+                SortDirection::Descending => b.cmp(&a),
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+/// Sorts `pages` by `sorts`, earlier sorts take precedence. Pages that compare equal keep their
+/// order.
+pub fn sort_pages(
+    pages: &mut [Page],
+    sorts: &[DatabaseSort],
+) {
+    pages.sort_by(|a, b| {
+        sorts
+            .iter()
+            .map(|sort| sort.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+impl DatabaseQuery {
+    /// The pages of `pages` this query returns, in the order of its sorts.
+    /// Paging is ignored.
+    ///
+    /// Relative date conditions are evaluated against the current time, use
+    /// [DatabaseQuery::apply_at()] to choose it.
+    pub fn apply(
+        &self,
+        pages: Vec<Page>,
+    ) -> Vec<Page> {
+        self.apply_at(pages, Utc::now())
+    }
+
+    /// Like [DatabaseQuery::apply()], with `now` as the current time.
+    pub fn apply_at(
+        &self,
+        pages: Vec<Page>,
+        now: DateTime<Utc>,
+    ) -> Vec<Page> {
+        let mut pages: Vec<Page> = pages
+            .into_iter()
+            .filter(|page| {
+                self.filter
+                    .as_ref()
+                    .is_none_or(|filter| filter.matches_at(page, now))
+            })
+            .collect();
+        sort_pages(&mut pages, self.sorts.as_deref().unwrap_or_default());
+        pages
+    }
+}
+
+/// Filters refer to properties by name or by id.
+fn property_matches(
+    condition: &PropertyCondition,
+    value: &PropertyValue,
+    now: DateTime<Utc>,
+) -> bool {
+    use PropertyValue::*;
+    match (condition, value) {
+        (PropertyCondition::RichText(condition), Title { title: text, .. })
+        | (
+            PropertyCondition::RichText(condition),
+            Text {
+                rich_text: text, ..
+            },
+        ) => text_matches(condition, &plain_text(text)),
+        (PropertyCondition::RichText(condition), Url { url: text, .. })
+        | (PropertyCondition::RichText(condition), Email { email: text, .. })
+        | (
+            PropertyCondition::RichText(condition),
+            PhoneNumber {
+                phone_number: text, ..
+            },
+        ) => text_matches(condition, text.as_deref().unwrap_or_default()),
+        (PropertyCondition::Number(condition), Number { number, .. }) => {
+            number_matches(condition, number.as_ref())
+        }
+        (PropertyCondition::Checkbox(condition), Checkbox { checkbox, .. }) => {
+            checkbox_matches(condition, *checkbox)
+        }
+        (PropertyCondition::Select(condition), Select { select, .. }) => {
+            let name = option_name(select.as_ref());
+            match condition {
+                SelectCondition::Equals(expected) => name == Some(expected),
+                SelectCondition::DoesNotEqual(expected) => name != Some(expected),
+                SelectCondition::IsEmpty => name.is_none(),
+                SelectCondition::IsNotEmpty => name.is_some(),
+            }
+        }
+        (PropertyCondition::Staus(condition), Status { status, .. }) => {
+            let name = option_name(status.as_ref());
+            match condition {
+                StatusCondition::Equals(expected) => name == Some(expected),
+                StatusCondition::DoesNotEqual(expected) => name != Some(expected),
+                StatusCondition::IsEmpty => name.is_none(),
+                StatusCondition::IsNotEmpty => name.is_some(),
+            }
+        }
+        (PropertyCondition::MultiSelect(condition), MultiSelect { multi_select, .. }) => {
+            let mut names = multi_select
+                .iter()
+                .flatten()
+                .filter_map(|option| option.name.as_ref());
+            match condition {
+                MultiSelectCondition::Contains(expected) => names.any(|name| name == expected),
+                MultiSelectCondition::DoesNotContain(expected) => {
+                    !names.any(|name| name == expected)
+                }
+                MultiSelectCondition::IsEmpty => names.next().is_none(),
+                MultiSelectCondition::IsNotEmpty => names.next().is_some(),
+            }
+        }
+        (PropertyCondition::Date(condition), Date { date, .. }) => {
+            date_matches(condition, date.as_ref().map(date_range).as_ref(), now)
+        }
+        (PropertyCondition::Date(condition), CreatedTime { created_time, .. }) => {
+            date_matches(condition, Some(&time_range(*created_time)), now)
+        }
+        (
+            PropertyCondition::Date(condition),
+            LastEditedTime {
+                last_edited_time, ..
+            },
+        ) => date_matches(condition, Some(&time_range(*last_edited_time)), now),
+        (PropertyCondition::People(condition), People { people, .. }) => {
+            let ids: Vec<&UserId> = people.iter().map(|user| user.id()).collect();
+            people_matches(condition, &ids)
+        }
+        (
+            PropertyCondition::People(condition),
+            CreatedBy {
+                created_by: user, ..
+            },
+        )
+        | (
+            PropertyCondition::People(condition),
+            LastEditedBy {
+                last_edited_by: user,
+                ..
+            },
+        ) => people_matches(condition, &[user.id()]),
+        (PropertyCondition::Files(condition), Files { files, .. }) => {
+            let empty = files.as_ref().is_none_or(Vec::is_empty);
+            match condition {
+                FilesCondition::IsEmpty => empty,
+                FilesCondition::IsNotEmpty => !empty,
+            }
+        }
+        (PropertyCondition::Relation(condition), Relation { relation, .. }) => {
+            let ids: Vec<&PageId> = relation.iter().flatten().map(|page| &page.id).collect();
+            match condition {
+                RelationCondition::Contains(id) => ids.contains(&id),
+                RelationCondition::DoesNotContain(id) => !ids.contains(&id),
+                RelationCondition::IsEmpty => ids.is_empty(),
+                RelationCondition::IsNotEmpty => !ids.is_empty(),
+            }
+        }
+        (PropertyCondition::Formula(condition), Formula { formula, .. }) => {
+            formula_matches(condition, formula, now)
+        }
+        _ => false,
+    }
+}
+
+/// Equality is exact, `contains`, `starts_with` and `ends_with` ignore case like Notion.
+fn text_matches(
+    condition: &TextCondition,
+    text: &str,
+) -> bool {
+    let lowercase = text.to_lowercase();
+    match condition {
+        TextCondition::Equals(expected) => text == expected,
+        TextCondition::DoesNotEqual(expected) => text != expected,
+        TextCondition::Contains(expected) => lowercase.contains(&expected.to_lowercase()),
+        TextCondition::DoesNotContain(expected) => !lowercase.contains(&expected.to_lowercase()),
+        TextCondition::StartsWith(expected) => lowercase.starts_with(&expected.to_lowercase()),
+        TextCondition::EndsWith(expected) => lowercase.ends_with(&expected.to_lowercase()),
+        TextCondition::IsEmpty => text.is_empty(),
+        TextCondition::IsNotEmpty => !text.is_empty(),
+    }
+}
+
+/// An empty number only matches `does_not_equal` and `is_empty`.
+fn number_matches(
+    condition: &NumberCondition,
+    number: Option<&Number>,
+) -> bool {
+    let number = number.and_then(Number::as_f64);
+    let compare = |expected: &Number, test: fn(Ordering) -> bool| {
+        number
+            .zip(expected.as_f64())
+            .and_then(|(number, expected)| number.partial_cmp(&expected))
+            .is_some_and(test)
+    };
+    match condition {
+        NumberCondition::Equals(expected) => compare(expected, Ordering::is_eq),
+        NumberCondition::DoesNotEqual(expected) => !compare(expected, Ordering::is_eq),
+        NumberCondition::GreaterThan(expected) => compare(expected, Ordering::is_gt),
+        NumberCondition::LessThan(expected) => compare(expected, Ordering::is_lt),
+        NumberCondition::GreaterThanOrEqualTo(expected) => compare(expected, Ordering::is_ge),
+        NumberCondition::LessThanOrEqualTo(expected) => compare(expected, Ordering::is_le),
+        NumberCondition::IsEmpty => number.is_none(),
+        NumberCondition::IsNotEmpty => number.is_some(),
+    }
+}
+
+fn checkbox_matches(
+    condition: &CheckboxCondition,
+    checked: bool,
+) -> bool {
+    match condition {
+        CheckboxCondition::Equals(expected) => checked == *expected,
+        CheckboxCondition::DoesNotEqual(expected) => checked != *expected,
+    }
+}
+
+fn people_matches(
+    condition: &PeopleCondition,
+    ids: &[&UserId],
+) -> bool {
+    match condition {
+        PeopleCondition::Contains(id) => ids.contains(&id),
+        PeopleCondition::DoesNotContain(id) => !ids.contains(&id),
+        PeopleCondition::IsEmpty => ids.is_empty(),
+        PeopleCondition::IsNotEmpty => !ids.is_empty(),
+    }
+}
+
+/// A formula condition only matches formulas with a result of its type.
+fn formula_matches(
+    condition: &FormulaCondition,
+    formula: &FormulaResultValue,
+    now: DateTime<Utc>,
+) -> bool {
+    match (condition, formula) {
+        (FormulaCondition::Text(condition), FormulaResultValue::String { string }) => {
+            text_matches(condition, string.as_deref().unwrap_or_default())
+        }
+        (FormulaCondition::Number(condition), FormulaResultValue::Number { number }) => {
+            number_matches(condition, number.as_ref())
+        }
+        (FormulaCondition::Checkbox(condition), FormulaResultValue::Boolean { boolean }) => {
+            checkbox_matches(condition, boolean.unwrap_or_default())
+        }
+        (FormulaCondition::Date(condition), FormulaResultValue::Date { date }) => {
+            date_matches(condition, date.as_ref().map(date_range).as_ref(), now)
+        }
+        _ => false,
+    }
+}
+
+/// The first and the last day of a date, ranges match a condition if any of their days do.
+/// Times are compared by their day in UTC, the time is ignored like Notion does.
+type DayRange = (NaiveDate, NaiveDate);
+
+fn date_range(date: &DateValue) -> DayRange {
+    let start = day(&date.start);
+    let end = date.end.as_ref().map_or(start, day);
+    (start, end.max(start))
+}
+
+fn time_range(time: DateTime<Utc>) -> DayRange {
+    (time.date_naive(), time.date_naive())
+}
+
+fn day(date: &DateOrDateTime) -> NaiveDate {
+    match date {
+        DateOrDateTime::Date(date) => *date,
+        DateOrDateTime::DateTime(time) => time.date_naive(),
+    }
+}
+
+fn date_matches(
+    condition: &DateCondition,
+    range: Option<&DayRange>,
+    now: DateTime<Utc>,
+) -> bool {
+    let (start, end) = match (condition, range) {
+        (DateCondition::IsEmpty, range) => return range.is_none(),
+        (DateCondition::IsNotEmpty, range) => return range.is_some(),
+        (_, None) => return false,
+        (_, Some(range)) => *range,
+    };
+    let today = now.date_naive();
+    let overlaps = |from: Option<NaiveDate>, to: Option<NaiveDate>| {
+        from.zip(to)
+            .is_some_and(|(from, to)| start <= to && end >= from)
+    };
+    match condition {
+        DateCondition::Equals(date) => start <= date.date_naive() && date.date_naive() <= end,
+        DateCondition::Before(date) => start < date.date_naive(),
+        DateCondition::After(date) => end > date.date_naive(),
+        DateCondition::OnOrBefore(date) => start <= date.date_naive(),
+        DateCondition::OnOrAfter(date) => end >= date.date_naive(),
+        DateCondition::PastWeek => overlaps(today.checked_sub_days(Days::new(7)), Some(today)),
+        DateCondition::PastMonth => overlaps(today.checked_sub_months(Months::new(1)), Some(today)),
+        DateCondition::PastYear => overlaps(today.checked_sub_months(Months::new(12)), Some(today)),
+        DateCondition::NextWeek => overlaps(Some(today), today.checked_add_days(Days::new(7))),
+        DateCondition::NextMonth => overlaps(Some(today), today.checked_add_months(Months::new(1))),
+        DateCondition::NextYear => overlaps(Some(today), today.checked_add_months(Months::new(12))),
+        DateCondition::IsEmpty | DateCondition::IsNotEmpty => unreachable!("handled above"),
+    }
+}
+
+fn option_name(option: Option<&SelectedValue>) -> Option<&String> {
+    option.and_then(|option| option.name.as_ref())
+}
+
+/// The value a page is sorted by, values of different types are ordered by type.
+#[derive(Debug)]
+enum SortKey {
+    Boolean(bool),
+    Number(f64),
+    Date(DateTime<Utc>),
+    Text(String),
+}
+
+impl SortKey {
+    fn rank(&self) -> u8 {
+        match self {
+            SortKey::Boolean(_) => 0,
+            SortKey::Number(_) => 1,
+            SortKey::Date(_) => 2,
+            SortKey::Text(_) => 3,
+        }
+    }
+}
+
+impl Ord for SortKey {
+    fn cmp(
+        &self,
+        other: &Self,
+    ) -> Ordering {
+        match (self, other) {
+            (SortKey::Boolean(a), SortKey::Boolean(b)) => a.cmp(b),
+            (SortKey::Number(a), SortKey::Number(b)) => a.total_cmp(b),
+            (SortKey::Date(a), SortKey::Date(b)) => a.cmp(b),
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(
+        &self,
+        other: &Self,
+    ) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortKey {
+    fn eq(
+        &self,
+        other: &Self,
+    ) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for SortKey {}
+
+/// Empty values have no key. Text is compared ignoring case.
+fn sort_key(value: &PropertyValue) -> Option<SortKey> {
+    use PropertyValue::*;
+    let text = |text: String| Some(text.to_lowercase()).filter(|text| !text.is_empty());
+    match value {
+        Title {
+            title: rich_text, ..
+        }
+        | Text { rich_text, .. } => text(plain_text(rich_text)).map(SortKey::Text),
+        Number { number, .. }
+        | Formula {
+            formula: FormulaResultValue::Number { number },
+            ..
+        }
+        | Rollup {
+            rollup: Some(RollupValue::Number { number }),
+            ..
+        } => number
+            .as_ref()
+            .and_then(|number| number.as_f64())
+            .map(SortKey::Number),
+        Select { select: option, .. } | Status { status: option, .. } => {
+            text(option_name(option.as_ref())?.clone()).map(SortKey::Text)
+        }
+        MultiSelect { multi_select, .. } => text(
+            multi_select
+                .iter()
+                .flatten()
+                .filter_map(|option| option.name.clone())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+        .map(SortKey::Text),
+        Date { date, .. }
+        | Formula {
+            formula: FormulaResultValue::Date { date },
+            ..
+        }
+        | Rollup {
+            rollup: Some(RollupValue::Date { date }),
+            ..
+        } => date.as_ref().map(|date| SortKey::Date(date.start.to_utc())),
+        Formula {
+            formula: FormulaResultValue::String { string },
+            ..
+        } => text(string.clone()?).map(SortKey::Text),
+        Formula {
+            formula: FormulaResultValue::Boolean { boolean },
+            ..
+        } => boolean.map(SortKey::Boolean),
+        Checkbox { checkbox, .. } => Some(SortKey::Boolean(*checkbox)),
+        Url { url: value, .. }
+        | Email { email: value, .. }
+        | PhoneNumber {
+            phone_number: value,
+            ..
+        } => text(value.clone()?).map(SortKey::Text),
+        CreatedTime {
+            created_time: time, ..
+        }
+        | LastEditedTime {
+            last_edited_time: time,
+            ..
+        } => Some(SortKey::Date(*time)),
+        People { people, .. } => text(
+            people
+                .iter()
+                .map(|user| user.id().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+        .map(SortKey::Text),
+        CreatedBy {
+            created_by: user, ..
+        }
+        | LastEditedBy {
+            last_edited_by: user,
+            ..
+        } => Some(SortKey::Text(user.id().to_string())),
+        Relation { .. } | Rollup { .. } | Files { .. } | Button { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::search::{
+        sort_pages, CheckboxCondition, DatabaseQuery, DatabaseSort, DatabaseSortTimestamp,
+        DateCondition, FilterCondition, FormulaCondition, NumberCondition, PropertyCondition,
+        SelectCondition, SortDirection, TextCondition, TimestampCondition,
+    };
+    use crate::models::Page;
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde_json::{json, Value};
+
+    fn page(
+        id: u8,
+        properties: Value,
+    ) -> Page {
+        serde_json::from_value(json!({
+            "object": "page",
+            "id": format!("00000000-0000-0000-0000-0000000000{:02}", id),
+            "created_time": format!("2024-03-{:02}T09:00:00.000Z", id),
+            "last_edited_time": "2024-03-20T09:00:00.000Z",
+            "archived": false,
+            "icon": null,
+            "cover": null,
+            "parent": { "type": "workspace", "workspace": true },
+            "properties": properties,
+        }))
+        .unwrap()
+    }
+
+    fn task(
+        id: u8,
+        name: &str,
+        estimate: Option<f64>,
+        due: Value,
+    ) -> Page {
+        page(
+            id,
+            json!({
+                "Name": {
+                    "id": "title",
+                    "type": "title",
+                    "title": [{
+                        "type": "text",
+                        "text": { "content": name, "link": null },
+                        "plain_text": name,
+                    }],
+                },
+                "Estimate": { "id": "est", "type": "number", "number": estimate },
+                "Due": { "id": "due", "type": "date", "date": due },
+                "Stage": {
+                    "id": "st",
+                    "type": "select",
+                    "select": { "name": if estimate.is_some() { "Planned" } else { "Idea" }, "color": "default" },
+                },
+                "Late": {
+                    "id": "late",
+                    "type": "formula",
+                    "formula": { "type": "boolean", "boolean": id.is_multiple_of(2) },
+                },
+            }),
+        )
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()
+    }
+
+    fn property(
+        name: &str,
+        condition: PropertyCondition,
+    ) -> FilterCondition {
+        FilterCondition::Property {
+            property: name.to_string(),
+            condition,
+        }
+    }
+
+    fn date(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, 23, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn text_conditions() {
+        let page = task(1, "Write the Report", None, Value::Null);
+        let matches =
+            |condition| property("Name", PropertyCondition::RichText(condition)).matches(&page);
+
+        assert!(matches(TextCondition::Contains("report".to_string())));
+        assert!(matches(TextCondition::StartsWith("write".to_string())));
+        assert!(matches(TextCondition::Equals(
+            "Write the Report".to_string()
+        )));
+        assert!(!matches(TextCondition::Equals(
+            "write the report".to_string()
+        )));
+        assert!(matches(TextCondition::IsNotEmpty));
+        // Properties can be referred to by id.
+        assert!(property(
+            "title",
+            PropertyCondition::RichText(TextCondition::EndsWith("REPORT".to_string()))
+        )
+        .matches(&page));
+    }
+
+    #[test]
+    fn date_conditions_ignore_the_time() {
+        let range = task(
+            1,
+            "Range",
+            None,
+            json!({ "start": "2024-03-10", "end": "2024-03-12" }),
+        );
+        let time = task(
+            2,
+            "Time",
+            None,
+            json!({ "start": "2024-03-14T08:00:00.000Z" }),
+        );
+        let empty = task(3, "Empty", None, Value::Null);
+        let matches = |page: &Page, condition| {
+            property("Due", PropertyCondition::Date(condition)).matches_at(page, now())
+        };
+
+        assert!(matches(&range, DateCondition::Equals(date(11))));
+        assert!(matches(&range, DateCondition::After(date(11))));
+        assert!(!matches(&range, DateCondition::After(date(12))));
+        assert!(matches(&time, DateCondition::Equals(date(14))));
+        assert!(matches(&time, DateCondition::OnOrBefore(date(14))));
+        assert!(!matches(&time, DateCondition::Before(date(14))));
+        assert!(matches(&time, DateCondition::PastWeek));
+        assert!(!matches(&time, DateCondition::NextWeek));
+        assert!(!matches(&empty, DateCondition::PastWeek));
+        assert!(matches(&empty, DateCondition::IsEmpty));
+
+        let created = FilterCondition::Timestamp {
+            condition: TimestampCondition::CreatedTime {
+                created_time: DateCondition::OnOrAfter(date(2)),
+            },
+        };
+        assert!(!created.matches(&range));
+        assert!(created.matches(&time));
+    }
+
+    #[test]
+    fn compound_and_formula_conditions() {
+        let pages = [
+            task(1, "One", Some(3.0), Value::Null),
+            task(2, "Two", Some(8.0), Value::Null),
+            task(3, "Three", None, Value::Null),
+        ];
+        let filter = FilterCondition::Or {
+            or: vec![
+                FilterCondition::And {
+                    and: vec![
+                        property(
+                            "Estimate",
+                            PropertyCondition::Number(NumberCondition::GreaterThan(5.into())),
+                        ),
+                        property(
+                            "Late",
+                            PropertyCondition::Formula(FormulaCondition::Checkbox(
+                                CheckboxCondition::Equals(true),
+                            )),
+                        ),
+                    ],
+                },
+                property(
+                    "Stage",
+                    PropertyCondition::Select(SelectCondition::Equals("Idea".to_string())),
+                ),
+            ],
+        };
+
+        let matching: Vec<bool> = pages.iter().map(|page| filter.matches(page)).collect();
+        assert_eq!(matching, vec![false, true, true]);
+        // A formula condition of another result type doesn't match.
+        assert!(!property(
+            "Late",
+            PropertyCondition::Formula(FormulaCondition::Text(TextCondition::IsEmpty))
+        )
+        .matches(&pages[0]));
+    }
+
+    #[test]
+    fn sorts_with_empty_values_last() {
+        let mut pages = vec![
+            task(1, "b", None, Value::Null),
+            task(2, "a", Some(8.0), Value::Null),
+            task(3, "c", Some(3.0), Value::Null),
+            task(4, "d", Some(8.0), Value::Null),
+        ];
+        let sorts = [
+            DatabaseSort {
+                property: Some("Estimate".to_string()),
+                timestamp: None,
+                direction: SortDirection::Descending,
+            },
+            DatabaseSort {
+                property: None,
+                timestamp: Some(DatabaseSortTimestamp::CreatedTime),
+                direction: SortDirection::Descending,
+            },
+        ];
+
+        sort_pages(&mut pages, &sorts);
+
+        let names: Vec<String> = pages.iter().filter_map(Page::title).collect();
+        assert_eq!(names, vec!["d", "a", "c", "b"]);
+    }
+
+    #[test]
+    fn applies_queries_at_a_given_time() {
+        let pages = vec![
+            task(1, "b", None, json!({ "start": "2024-03-14" })),
+            task(2, "c", None, json!({ "start": "2024-03-01" })),
+            task(3, "a", None, json!({ "start": "2024-03-12" })),
+        ];
+        let query = DatabaseQuery {
+            // Properties can be referred to by id as well.
+            filter: Some(property(
+                "due",
+                PropertyCondition::Date(DateCondition::PastWeek),
+            )),
+            sorts: Some(vec![DatabaseSort {
+                property: Some("title".to_string()),
+                timestamp: None,
+                direction: SortDirection::Ascending,
+            }]),
+            paging: None,
+        };
+
+        let names: Vec<String> = query
+            .apply_at(pages, now())
+            .iter()
+            .filter_map(Page::title)
+            .collect();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+}
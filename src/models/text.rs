@@ -1,6 +1,8 @@
-use crate::models::properties::DateValue;
-use crate::models::users::User;
-use crate::{Database, Page};
+use crate::ids::{DatabaseId, PageId, UserId};
+use crate::models::block::Equation;
+use crate::models::properties::{DateOrDateTime, DateValue};
+use crate::models::users::{User, UserCommon};
+use chrono::SecondsFormat;
 use serde::{Deserialize, Serialize};
 
 #[cfg(test)]
@@ -32,13 +34,19 @@ pub enum TextColor {
 
 /// Rich text annotations
 /// See <https://developers.notion.com/reference/rich-text#annotations>
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone, Default)]
 pub struct Annotations {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<TextColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub strikethrough: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub underline: Option<bool>,
 }
 
@@ -64,7 +72,69 @@ pub struct Text {
     pub link: Option<Link>,
 }
 
-/// See https://developers.notion.com/reference/rich-text#mention-objects
+/// A mentioned page, only the id of the page is sent.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct PageReference {
+    pub id: PageId,
+}
+
+/// A mentioned database, only the id of the database is sent.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct DatabaseReference {
+    pub id: DatabaseId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct LinkPreviewMention {
+    pub url: String,
+}
+
+/// A link that Notion unfurled into a rich mention.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct LinkMention {
+    pub href: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateMentionDate {
+    Today,
+    Now,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateMentionUser {
+    Me,
+}
+
+/// Mentions inside of template buttons, they are replaced when the template is used.
+/// See <https://developers.notion.com/reference/rich-text#template-mention-type-object>
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateMention {
+    TemplateMentionDate {
+        template_mention_date: TemplateMentionDate,
+    },
+    TemplateMentionUser {
+        template_mention_user: TemplateMentionUser,
+    },
+}
+
+/// See <https://developers.notion.com/reference/rich-text#mention-objects>
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
@@ -72,21 +142,24 @@ pub enum MentionObject {
     User {
         user: User,
     },
-    // TODO: need to add tests
     Page {
-        page: Page,
+        page: PageReference,
     },
-    // TODO: need to add tests
     Database {
-        database: Database,
+        database: DatabaseReference,
     },
     Date {
         date: DateValue,
     },
-    // TODO: need to add LinkPreview
-    // LinkPreview {
-    //
-    // },
+    LinkPreview {
+        link_preview: LinkPreviewMention,
+    },
+    LinkMention {
+        link_mention: LinkMention,
+    },
+    TemplateMention {
+        template_mention: TemplateMention,
+    },
     #[serde(other)]
     Unknown,
 }
@@ -115,10 +188,186 @@ pub enum RichText {
     Equation {
         #[serde(flatten)]
         rich_text: RichTextCommon,
+        equation: Equation,
     },
 }
 
+fn common(plain_text: String) -> RichTextCommon {
+    RichTextCommon {
+        plain_text,
+        href: None,
+        annotations: None,
+    }
+}
+
+fn date_or_date_time(date: &DateOrDateTime) -> String {
+    match date {
+        DateOrDateTime::Date(date) => date.to_string(),
+        DateOrDateTime::DateTime(time) => time.to_rfc3339_opts(SecondsFormat::Millis, true),
+    }
+}
+
+/// Builds rich text for requests, e.g. `RichText::text("docs").bold().link("https://…")`.
+/// The `plain_text` of mentions is a placeholder, Notion replaces it with the name of
+/// the mentioned user or page.
 impl RichText {
+    /// Unformatted text.
+    pub fn text<T: Into<String>>(content: T) -> Self {
+        let content = content.into();
+        RichText::Text {
+            rich_text: common(content.clone()),
+            text: Text {
+                content,
+                link: None,
+            },
+        }
+    }
+
+    pub fn mention_user(id: UserId) -> Self {
+        RichText::Mention {
+            rich_text: common(format!("@{}", id)),
+            mention: MentionObject::User {
+                user: User::User {
+                    common: UserCommon {
+                        id,
+                        name: None,
+                        avatar_url: None,
+                    },
+                    person: None,
+                },
+            },
+        }
+    }
+
+    pub fn mention_page(id: PageId) -> Self {
+        RichText::Mention {
+            rich_text: common(id.to_string()),
+            mention: MentionObject::Page {
+                page: PageReference { id },
+            },
+        }
+    }
+
+    pub fn mention_date(date: DateValue) -> Self {
+        // The same format Notion uses, a date without an end still has the arrow.
+        let plain_text = format!(
+            "{} → {}",
+            date_or_date_time(&date.start),
+            date.end.as_ref().map(date_or_date_time).unwrap_or_default()
+        );
+        RichText::Mention {
+            rich_text: common(plain_text),
+            mention: MentionObject::Date { date },
+        }
+    }
+
+    /// An inline KaTeX equation.
+    pub fn equation<T: Into<String>>(expression: T) -> Self {
+        let expression = expression.into();
+        RichText::Equation {
+            rich_text: common(expression.clone()),
+            equation: Equation { expression },
+        }
+    }
+
+    pub fn bold(self) -> Self {
+        self.annotate(|annotations| annotations.bold = Some(true))
+    }
+
+    pub fn italic(self) -> Self {
+        self.annotate(|annotations| annotations.italic = Some(true))
+    }
+
+    pub fn strikethrough(self) -> Self {
+        self.annotate(|annotations| annotations.strikethrough = Some(true))
+    }
+
+    pub fn underline(self) -> Self {
+        self.annotate(|annotations| annotations.underline = Some(true))
+    }
+
+    pub fn code(self) -> Self {
+        self.annotate(|annotations| annotations.code = Some(true))
+    }
+
+    pub fn color(
+        self,
+        color: TextColor,
+    ) -> Self {
+        self.annotate(|annotations| annotations.color = Some(color))
+    }
+
+    /// Links text to `url`. Mentions and equations can't be links, they are returned unchanged.
+    pub fn link<T: Into<String>>(
+        mut self,
+        url: T,
+    ) -> Self {
+        if let RichText::Text { rich_text, text } = &mut self {
+            let url = url.into();
+            rich_text.href = Some(url.clone());
+            text.link = Some(Link { url });
+        }
+        self
+    }
+
+    fn annotate<F: FnOnce(&mut Annotations)>(
+        mut self,
+        update: F,
+    ) -> Self {
+        update(
+            self.common_mut()
+                .annotations
+                .get_or_insert_with(Annotations::default),
+        );
+        self
+    }
+
+    fn common_mut(&mut self) -> &mut RichTextCommon {
+        use RichText::*;
+        match self {
+            Text { rich_text, .. } | Mention { rich_text, .. } | Equation { rich_text, .. } => {
+                rich_text
+            }
+        }
+    }
+
+    /// A copy without the fields Notion fills in itself, to compare rich text as it was sent
+    /// with rich text as Notion returns it.
+    pub(crate) fn normalized(&self) -> RichText {
+        let mut text = self.clone();
+        let common = text.common_mut();
+        common.plain_text.clear();
+        common.href = None;
+        let annotations = common.annotations.get_or_insert_with(Annotations::default);
+        for flag in [
+            &mut annotations.bold,
+            &mut annotations.code,
+            &mut annotations.italic,
+            &mut annotations.strikethrough,
+            &mut annotations.underline,
+        ] {
+            flag.get_or_insert(false);
+        }
+        annotations.color.get_or_insert(TextColor::Default);
+
+        // Notion returns the full user, a mention only needs its id.
+        if let RichText::Mention {
+            mention: MentionObject::User { user },
+            ..
+        } = &mut text
+        {
+            *user = User::User {
+                common: UserCommon {
+                    id: user.id().clone(),
+                    name: None,
+                    avatar_url: None,
+                },
+                person: None,
+            };
+        }
+        text
+    }
+
     pub fn plain_text(&self) -> &str {
         use RichText::*;
         match self {
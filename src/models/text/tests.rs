@@ -1,7 +1,9 @@
-use crate::ids::UserId;
+use crate::ids::{DatabaseId, PageId, UserId};
 use crate::models::properties::{DateOrDateTime, DateValue};
 use crate::models::text::{
-    Annotations, Link, MentionObject, RichText, RichTextCommon, Text, TextColor,
+    Annotations, DatabaseReference, Link, LinkMention, LinkPreviewMention, MentionObject,
+    PageReference, RichText, RichTextCommon, TemplateMention, TemplateMentionDate,
+    TemplateMentionUser, Text, TextColor,
 };
 use crate::models::users::{Person, User, UserCommon};
 use chrono::{DateTime, NaiveDate};
@@ -204,3 +206,213 @@ fn rich_text_mention_date_with_end_and_time() {
         }
     )
 }
+
+fn mention(rich_text: RichText) -> MentionObject {
+    match rich_text {
+        RichText::Mention { mention, .. } => mention,
+        rich_text => panic!("Expected a mention, got {:?}", rich_text),
+    }
+}
+
+#[test]
+fn rich_text_mention_page() {
+    let rich_text: RichText =
+        serde_json::from_str(include_str!("tests/rich_text_mention_page.json")).unwrap();
+    assert_eq!(rich_text.plain_text(), "This is a test page");
+    assert_eq!(
+        mention(rich_text),
+        MentionObject::Page {
+            page: PageReference {
+                id: PageId::from_str("3c612f56-fdd0-4a30-a4d6-bda7d7426309").unwrap()
+            }
+        }
+    )
+}
+
+#[test]
+fn rich_text_mention_database() {
+    let rich_text: RichText =
+        serde_json::from_str(include_str!("tests/rich_text_mention_database.json")).unwrap();
+    assert_eq!(
+        mention(rich_text),
+        MentionObject::Database {
+            database: DatabaseReference {
+                id: DatabaseId::from_str("a1d8501e-1ac1-43e9-a6bd-ea9fe6c8822b").unwrap()
+            }
+        }
+    )
+}
+
+#[test]
+fn rich_text_mention_link_preview() {
+    let rich_text: RichText =
+        serde_json::from_str(include_str!("tests/rich_text_mention_link_preview.json")).unwrap();
+    assert_eq!(
+        mention(rich_text),
+        MentionObject::LinkPreview {
+            link_preview: LinkPreviewMention {
+                url: "https://github.com/snuna/rusticnotion/pull/1".to_string()
+            }
+        }
+    )
+}
+
+#[test]
+fn rich_text_mention_link_mention() {
+    let rich_text: RichText =
+        serde_json::from_str(include_str!("tests/rich_text_mention_link_mention.json")).unwrap();
+    assert_eq!(
+        mention(rich_text),
+        MentionObject::LinkMention {
+            link_mention: LinkMention {
+                href: "https://github.com/snuna/rusticnotion".to_string(),
+                title: Some("snuna/rusticnotion".to_string()),
+                description: Some(
+                    "Notion Offical API client library for rust, maintained fork.".to_string()
+                ),
+                link_author: None,
+                link_provider: Some("GitHub".to_string()),
+                icon_url: Some("https://github.com/favicon.ico".to_string()),
+                thumbnail_url: None,
+            }
+        }
+    )
+}
+
+#[test]
+fn rich_text_mention_template_date() {
+    let rich_text: RichText =
+        serde_json::from_str(include_str!("tests/rich_text_mention_template_date.json")).unwrap();
+    assert_eq!(
+        mention(rich_text),
+        MentionObject::TemplateMention {
+            template_mention: TemplateMention::TemplateMentionDate {
+                template_mention_date: TemplateMentionDate::Today
+            }
+        }
+    )
+}
+
+#[test]
+fn rich_text_mention_template_user() {
+    let rich_text: RichText =
+        serde_json::from_str(include_str!("tests/rich_text_mention_template_user.json")).unwrap();
+    assert_eq!(
+        mention(rich_text),
+        MentionObject::TemplateMention {
+            template_mention: TemplateMention::TemplateMentionUser {
+                template_mention_user: TemplateMentionUser::Me
+            }
+        }
+    )
+}
+
+#[test]
+fn rich_text_mention_unknown() {
+    let rich_text: RichText =
+        serde_json::from_str(include_str!("tests/rich_text_mention_unknown.json")).unwrap();
+    assert_eq!(rich_text.plain_text(), ":party:");
+    assert_eq!(mention(rich_text), MentionObject::Unknown)
+}
+
+#[test]
+fn serialize_page_mention() {
+    let json = serde_json::to_value(MentionObject::Page {
+        page: PageReference {
+            id: PageId::from_str("3c612f56-fdd0-4a30-a4d6-bda7d7426309").unwrap(),
+        },
+    })
+    .unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "type": "page",
+            "page": { "id": "3c612f56-fdd0-4a30-a4d6-bda7d7426309" }
+        })
+    )
+}
+
+#[test]
+fn rich_text_equation() {
+    let rich_text_equation: RichText =
+        serde_json::from_str(include_str!("tests/rich_text_equation.json")).unwrap();
+    assert_eq!(rich_text_equation.plain_text(), "e=mc^2");
+    assert!(matches!(
+        rich_text_equation,
+        RichText::Equation { equation, .. } if equation.expression == "e=mc^2"
+    ));
+}
+
+#[test]
+fn build_annotated_text() {
+    let text = RichText::text("Rich")
+        .bold()
+        .italic()
+        .color(TextColor::Red)
+        .link("https://github.com/jakeswenson/notion");
+
+    assert_eq!(text.plain_text(), "Rich");
+    assert_eq!(
+        serde_json::to_value(&text).unwrap(),
+        serde_json::json!({
+            "type": "text",
+            "text": {
+                "content": "Rich",
+                "link": { "url": "https://github.com/jakeswenson/notion" }
+            },
+            "annotations": { "bold": true, "italic": true, "color": "red" },
+            "plain_text": "Rich",
+            "href": "https://github.com/jakeswenson/notion"
+        })
+    );
+}
+
+#[test]
+fn build_mentions_and_equations() {
+    let user = UserId::from_str("9a3b5ae0-c6e6-482d-b0e1-ed315ee6dc57").unwrap();
+    assert_eq!(
+        serde_json::to_value(RichText::mention_user(user).bold()).unwrap(),
+        serde_json::json!({
+            "type": "mention",
+            "mention": {
+                "type": "user",
+                "user": { "object": "user", "id": "9a3b5ae0-c6e6-482d-b0e1-ed315ee6dc57" }
+            },
+            "annotations": { "bold": true },
+            "plain_text": "@9a3b5ae0-c6e6-482d-b0e1-ed315ee6dc57"
+        })
+    );
+
+    let page = PageId::from_str("a8df1b95-7d60-4d0b-a50b-ebdfb0ab9153").unwrap();
+    assert_eq!(
+        serde_json::to_value(RichText::mention_page(page)).unwrap()["mention"],
+        serde_json::json!({
+            "type": "page",
+            "page": { "id": "a8df1b95-7d60-4d0b-a50b-ebdfb0ab9153" }
+        })
+    );
+
+    let date = RichText::mention_date(DateValue {
+        start: DateOrDateTime::Date(NaiveDate::from_ymd_opt(2022, 5, 12).unwrap()),
+        end: Some(DateOrDateTime::Date(
+            NaiveDate::from_ymd_opt(2022, 5, 13).unwrap(),
+        )),
+        time_zone: None,
+    });
+    assert_eq!(date.plain_text(), "2022-05-12 → 2022-05-13");
+    assert_eq!(
+        serde_json::to_value(&date).unwrap()["mention"]["date"],
+        serde_json::json!({ "start": "2022-05-12", "end": "2022-05-13", "time_zone": null })
+    );
+
+    // Only text can be a link.
+    let equation = RichText::equation("e=mc^2").link("https://example.com");
+    assert_eq!(
+        serde_json::to_value(&equation).unwrap(),
+        serde_json::json!({
+            "type": "equation",
+            "equation": { "expression": "e=mc^2" },
+            "plain_text": "e=mc^2"
+        })
+    );
+}
@@ -4,7 +4,9 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct UserCommon {
     pub id: UserId,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub avatar_url: Option<String>,
 }
 
@@ -34,6 +36,17 @@ pub enum User {
     User {
         #[serde(flatten)]
         common: UserCommon,
+        #[serde(skip_serializing_if = "Option::is_none")]
         person: Option<Person>,
     },
 }
+
+impl User {
+    pub fn id(&self) -> &UserId {
+        match self {
+            User::Person { common, .. } | User::Bot { common, .. } | User::User { common, .. } => {
+                &common.id
+            }
+        }
+    }
+}
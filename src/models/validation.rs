@@ -0,0 +1,263 @@
+//! Checks of page properties against the schema of their database before sending them,
+//! see [PageCreateRequest::validate()].
+
+use crate::models::properties::{
+    find_by_name_or_id, PropertyConfiguration, PropertyKind, SelectReference, WriteProperties,
+    WritePropertyValue,
+};
+use crate::models::{Database, PageCreateRequest, UpdatePageRequest};
+use thiserror::Error;
+
+/// A property value Notion would reject for the schema of a database.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    #[error("Property '{}' does not exist in the database", .property)]
+    UnknownProperty { property: String },
+
+    #[error("Property '{}' is a {} property, the value is for {}", .property, .expected, .actual)]
+    WrongType {
+        property: String,
+        expected: &'static str,
+        actual: &'static str,
+    },
+
+    #[error("Property '{}' of type {} is computed by Notion and can't be written", .property, .kind)]
+    ReadOnly {
+        property: String,
+        kind: &'static str,
+    },
+
+    #[error("Property '{}' has no option {}", .property, .option)]
+    UnknownOption { property: String, option: String },
+
+    #[error("The title property '{}' is missing", .property)]
+    MissingTitle { property: String },
+}
+
+impl PageCreateRequest {
+    /// Checks the properties of this request against the schema of `database`, the parent of
+    /// the new page, and returns all problems found.
+    ///
+    /// Every property has to exist with the type of its value, select and status options
+    /// have to exist and the title has to be set.
+    pub fn validate(
+        &self,
+        database: &Database,
+    ) -> Result<(), Vec<SchemaError>> {
+        let mut errors = check_properties(&self.properties, database);
+
+        let title = database
+            .properties
+            .iter()
+            .find(|(_, config)| config.kind() == PropertyKind::Title);
+        if let Some((name, config)) = title {
+            let has_title = self.properties.properties.keys().any(|key| {
+                find_property(database, key).is_some_and(|(_, found)| found.id() == config.id())
+            });
+            if !has_title {
+                errors.push(SchemaError::MissingTitle {
+                    property: name.clone(),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl UpdatePageRequest {
+    /// Checks the properties of this request against the schema of the database the page is in,
+    /// like [PageCreateRequest::validate()] but without requiring a title.
+    pub fn validate(
+        &self,
+        database: &Database,
+    ) -> Result<(), Vec<SchemaError>> {
+        let errors = check_properties(&self.properties, database);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn check_properties(
+    properties: &WriteProperties,
+    database: &Database,
+) -> Vec<SchemaError> {
+    // Sorted for a stable order of the errors.
+    let mut keys: Vec<&String> = properties.properties.keys().collect();
+    keys.sort();
+
+    let mut errors = vec![];
+    for key in keys {
+        let value = &properties.properties[key];
+        let (name, config) = match find_property(database, key) {
+            Some(found) => found,
+            None => {
+                errors.push(SchemaError::UnknownProperty {
+                    property: key.clone(),
+                });
+                continue;
+            }
+        };
+
+        let kind = config.kind();
+        if !kind.is_writable() {
+            errors.push(SchemaError::ReadOnly {
+                property: name.clone(),
+                kind: kind.name(),
+            });
+        } else if kind != value.kind() {
+            errors.push(SchemaError::WrongType {
+                property: name.clone(),
+                expected: kind.name(),
+                actual: value.kind().name(),
+            });
+        } else {
+            let selected: Vec<&SelectReference> = match value {
+                WritePropertyValue::Select { select } => select.iter().collect(),
+                WritePropertyValue::Status { status } => status.iter().collect(),
+                WritePropertyValue::MultiSelect { multi_select } => multi_select.iter().collect(),
+                _ => vec![],
+            };
+            for option in selected {
+                if !has_option(config, option) {
+                    errors.push(SchemaError::UnknownOption {
+                        property: name.clone(),
+                        option: describe_option(option),
+                    });
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// Write requests refer to properties by name or by id.
+fn find_property<'a>(
+    database: &'a Database,
+    key: &str,
+) -> Option<(&'a String, &'a PropertyConfiguration)> {
+    find_by_name_or_id(&database.properties, key, PropertyConfiguration::id)
+}
+
+fn has_option(
+    config: &PropertyConfiguration,
+    option: &SelectReference,
+) -> bool {
+    config.options().iter().any(|known| {
+        option.id.as_ref().is_none_or(|id| *id == known.id)
+            && option.name.as_ref().is_none_or(|name| *name == known.name)
+    })
+}
+
+fn describe_option(option: &SelectReference) -> String {
+    match (&option.name, &option.id) {
+        (Some(name), _) => format!("'{}'", name),
+        (None, Some(id)) => format!("with id {:?}", id),
+        (None, None) => "without name or id".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SchemaError;
+    use crate::models::properties::{WriteProperties, WritePropertyValue};
+    use crate::models::{Database, PageCreateRequest, Parent, UpdatePageRequest};
+    use serde_json::json;
+
+    fn database() -> Database {
+        serde_json::from_value(json!({
+            "object": "database",
+            "id": "b8b944b5-cc3d-444e-a25c-a7ddacd528cb",
+            "created_time": "2022-05-13T20:08:00.000Z",
+            "last_edited_time": "2022-05-13T20:08:00.000Z",
+            "title": [],
+            "icon": null,
+            "properties": {
+                "Name": { "id": "title", "type": "title", "title": {} },
+                "Notes": { "id": "n%3Ba", "type": "rich_text", "rich_text": {} },
+                "Stage": {
+                    "id": "st",
+                    "type": "select",
+                    "select": { "options": [
+                        { "id": "a1", "name": "Draft", "color": "gray" },
+                        { "id": "a2", "name": "Done", "color": "green" }
+                    ] }
+                },
+                "Created": { "id": "ct", "type": "created_time", "created_time": {} }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn request(properties: WriteProperties) -> PageCreateRequest {
+        PageCreateRequest {
+            parent: Parent::Database {
+                database_id: database().id,
+            },
+            properties,
+            children: None,
+            icon: None,
+            cover: None,
+        }
+    }
+
+    #[test]
+    fn accepts_valid_requests() {
+        let properties = WriteProperties::new()
+            .with("Name", WritePropertyValue::title("Page"))
+            .with("n%3Ba", WritePropertyValue::rich_text("notes"))
+            .with("Stage", WritePropertyValue::select_by_name("Done"));
+
+        assert_eq!(request(properties).validate(&database()), Ok(()));
+    }
+
+    #[test]
+    fn reports_all_problems() {
+        let properties = WriteProperties::new()
+            .with("Notes", WritePropertyValue::checkbox(true))
+            .with("Stage", WritePropertyValue::select_by_name("Archived"))
+            .with("Created", WritePropertyValue::date_time(chrono::Utc::now()))
+            .with("Missing", WritePropertyValue::url("https://example.com"));
+
+        assert_eq!(
+            request(properties).validate(&database()),
+            Err(vec![
+                SchemaError::ReadOnly {
+                    property: "Created".to_string(),
+                    kind: "created_time",
+                },
+                SchemaError::UnknownProperty {
+                    property: "Missing".to_string(),
+                },
+                SchemaError::WrongType {
+                    property: "Notes".to_string(),
+                    expected: "rich_text",
+                    actual: "checkbox",
+                },
+                SchemaError::UnknownOption {
+                    property: "Stage".to_string(),
+                    option: "'Archived'".to_string(),
+                },
+                SchemaError::MissingTitle {
+                    property: "Name".to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn updates_need_no_title() {
+        let update = UpdatePageRequest::from(
+            WriteProperties::new().with("Stage", WritePropertyValue::select_by_name("Draft")),
+        );
+
+        assert_eq!(update.validate(&database()), Ok(()));
+    }
+}
@@ -0,0 +1,433 @@
+//! Pagination that survives restarts, see [ResumablePager].
+
+use crate::ids::{AsIdentifier, DatabaseId, Identifier};
+use crate::models::error::{ErrorCode, ErrorResponse};
+use crate::models::paging::PagingCursor;
+use crate::models::search::{
+    DatabaseQuery, DatabaseSort, DatabaseSortTimestamp, DateCondition, FilterCondition,
+    SearchRequest, SortDirection, TimestampCondition,
+};
+use crate::models::{ListResponse, Object, Page};
+use crate::{Error, NotionApi};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// What a [ResumablePager] pages through.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CheckpointSource {
+    Database { database_id: DatabaseId },
+    Search,
+}
+
+/// The state of a [ResumablePager], written to disk once a page was processed.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct Checkpoint {
+    pub source: CheckpointSource,
+    /// The serialized query, without a `start_cursor`.
+    pub query: Value,
+    /// The cursor of the next page, `None` before the first page or once finished.
+    pub cursor: Option<PagingCursor>,
+    /// Set when a cursor expired.
+    /// Only results edited on or after this time are requested from then on.
+    pub restarted_from: Option<DateTime<Utc>>,
+    /// The newest `last_edited_time` of all results returned so far.
+    pub last_edited_time: Option<DateTime<Utc>>,
+    /// Ids of the returned results that were edited exactly at `last_edited_time`.
+    /// They are skipped when results are requested again after a cursor expired.
+    pub seen_at_last_edited_time: Vec<String>,
+    /// Number of results returned so far.
+    pub results: u64,
+    pub finished: bool,
+}
+
+/// A result type a [ResumablePager] can return.
+pub trait CheckpointItem: Sized {
+    fn from_object(object: Object) -> Result<Self, Error>;
+
+    fn id(&self) -> &str;
+
+    fn last_edited_time(&self) -> Option<DateTime<Utc>>;
+}
+
+impl CheckpointItem for Page {
+    fn from_object(object: Object) -> Result<Self, Error> {
+        match object {
+            Object::Page { page } => Ok(page),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+
+    fn id(&self) -> &str {
+        self.id.value()
+    }
+
+    fn last_edited_time(&self) -> Option<DateTime<Utc>> {
+        Some(self.last_edited_time)
+    }
+}
+
+impl CheckpointItem for Object {
+    fn from_object(object: Object) -> Result<Self, Error> {
+        Ok(object)
+    }
+
+    fn id(&self) -> &str {
+        match self {
+            Object::Page { page } => page.id.value(),
+            Object::Database { database } => database.id.value(),
+            _ => "",
+        }
+    }
+
+    fn last_edited_time(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Object::Page { page } => Some(page.last_edited_time),
+            Object::Database { database } => Some(database.last_edited_time),
+            _ => None,
+        }
+    }
+}
+
+/// Pages through [query_database()](NotionApi::query_database()) or [search()](NotionApi::search())
+/// and persists its progress to a checkpoint file,
+/// so a long running export can continue where it stopped after a restart.
+///
+/// A page counts as processed once [commit()](Self::commit()) or the next
+/// [next_page()](Self::next_page()) is called. Pages that weren't processed before a crash
+/// are returned again after the restart, so every result is returned at least once.
+///
+/// Results are sorted by `last_edited_time` ascending unless the query sorts them itself.
+/// When a stored cursor is no longer accepted by Notion the pager falls back to requesting
+/// everything edited since the newest result it returned, skipping results it already returned.
+/// That is only possible with the default sort, with other sorts an expired cursor
+/// fails with [Error::CursorExpired].
+pub struct ResumablePager<T> {
+    api: NotionApi,
+    path: PathBuf,
+    checkpoint: Checkpoint,
+    /// The checkpoint includes a page that wasn't committed yet.
+    uncommitted: bool,
+    results: PhantomData<T>,
+}
+
+impl ResumablePager<Page> {
+    /// Pages through the results of a database query.
+    /// Continues from the checkpoint at `path` if it exists.
+    pub async fn database<D, Q, P>(
+        api: &NotionApi,
+        database: D,
+        query: Q,
+        path: P,
+    ) -> Result<Self, Error>
+    where
+        D: AsIdentifier<DatabaseId>,
+        Q: Into<DatabaseQuery>,
+        P: Into<PathBuf>,
+    {
+        let mut query = query.into();
+        if query.sorts.is_none() {
+            query.sorts = Some(vec![default_database_sort()]);
+        }
+
+        Self::open(
+            api,
+            CheckpointSource::Database {
+                database_id: database.as_id().clone(),
+            },
+            query_json(&query)?,
+            path.into(),
+        )
+        .await
+    }
+}
+
+impl ResumablePager<Object> {
+    /// Pages through the results of a search.
+    /// Continues from the checkpoint at `path` if it exists.
+    pub async fn search<Q, P>(
+        api: &NotionApi,
+        query: Q,
+        path: P,
+    ) -> Result<Self, Error>
+    where
+        Q: Into<SearchRequest>,
+        P: Into<PathBuf>,
+    {
+        let mut query = query_json(&query.into())?;
+        if query.get("sort").is_none() {
+            query["sort"] = default_search_sort();
+        }
+
+        Self::open(api, CheckpointSource::Search, query, path.into()).await
+    }
+}
+
+impl<T: CheckpointItem> ResumablePager<T> {
+    async fn open(
+        api: &NotionApi,
+        source: CheckpointSource,
+        query: Value,
+        path: PathBuf,
+    ) -> Result<Self, Error> {
+        let checkpoint = match read_checkpoint(&path).await? {
+            Some(checkpoint) => {
+                if checkpoint.source != source || checkpoint.query != query {
+                    return Err(Error::CheckpointMismatch { path });
+                }
+                tracing::debug!(
+                    path = %path.display(),
+                    results = checkpoint.results,
+                    "Resuming from checkpoint"
+                );
+                checkpoint
+            }
+            None => Checkpoint {
+                source,
+                query,
+                cursor: None,
+                restarted_from: None,
+                last_edited_time: None,
+                seen_at_last_edited_time: vec![],
+                results: 0,
+                finished: false,
+            },
+        };
+
+        Ok(Self {
+            api: api.clone(),
+            path,
+            checkpoint,
+            uncommitted: false,
+            results: PhantomData,
+        })
+    }
+
+    /// The progress so far, including the last returned page even if it wasn't committed yet.
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    /// Marks the last returned page as processed and writes the checkpoint file.
+    pub async fn commit(&mut self) -> Result<(), Error> {
+        if self.uncommitted {
+            write_checkpoint(&self.path, &self.checkpoint).await?;
+            self.uncommitted = false;
+        }
+        Ok(())
+    }
+
+    /// Commits the previous page and fetches the next page of results.
+    /// Returns `None` once all results were returned.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<T>>, Error> {
+        self.commit().await?;
+        if self.checkpoint.finished {
+            return Ok(None);
+        }
+
+        let response = match self.fetch().await {
+            Err(Error::ApiError { error })
+                if self.checkpoint.cursor.is_some() && is_invalid_cursor(&error) =>
+            {
+                if !self.sorted_by_last_edited_time() {
+                    return Err(Error::CursorExpired {
+                        path: self.path.clone(),
+                    });
+                }
+                tracing::warn!(
+                    message = %error.message,
+                    "Paging cursor was rejected, restarting from the last edited time"
+                );
+                self.checkpoint.cursor = None;
+                self.checkpoint.restarted_from = self.checkpoint.last_edited_time;
+                self.fetch().await?
+            }
+            response => response?,
+        };
+
+        let mut results = vec![];
+        for object in response.results {
+            let item = T::from_object(object)?;
+            if self.already_returned(&item) {
+                continue;
+            }
+            self.record(&item);
+            results.push(item);
+        }
+
+        self.checkpoint.cursor = if response.has_more {
+            response.next_cursor
+        } else {
+            None
+        };
+        self.checkpoint.finished = self.checkpoint.cursor.is_none();
+        self.uncommitted = true;
+
+        Ok(Some(results))
+    }
+
+    /// Whether the results come in the default order, the only one in which
+    /// everything not returned yet was edited after the results returned so far.
+    fn sorted_by_last_edited_time(&self) -> bool {
+        let query = &self.checkpoint.query;
+        match self.checkpoint.source {
+            CheckpointSource::Database { .. } => {
+                query.get("sorts") == Some(&json!([default_database_sort()]))
+            }
+            CheckpointSource::Search => query.get("sort") == Some(&default_search_sort()),
+        }
+    }
+
+    async fn fetch(&self) -> Result<ListResponse<Object>, Error> {
+        let mut body = self.checkpoint.query.clone();
+        if let Some(cursor) = &self.checkpoint.cursor {
+            body["start_cursor"] = json!(cursor);
+        }
+
+        let path = match &self.checkpoint.source {
+            CheckpointSource::Database { database_id } => {
+                if let Some(since) = self.checkpoint.restarted_from {
+                    let since = query_json(&FilterCondition::Timestamp {
+                        condition: TimestampCondition::LastEditedTime {
+                            last_edited_time: DateCondition::OnOrAfter(since),
+                        },
+                    })?;
+                    body["filter"] = match body.get("filter") {
+                        Some(filter) => json!({ "and": [filter, since] }),
+                        None => since,
+                    };
+                }
+                format!("databases/{}/query", database_id)
+            }
+            // Search can't filter by time, results returned before are skipped instead.
+            CheckpointSource::Search => "search".to_string(),
+        };
+
+        self.api.post_list(&path, &body).await
+    }
+
+    fn already_returned(
+        &self,
+        item: &T,
+    ) -> bool {
+        if self.checkpoint.restarted_from.is_none() {
+            return false;
+        }
+        match (item.last_edited_time(), self.checkpoint.last_edited_time) {
+            (Some(edited), Some(newest)) => {
+                edited < newest
+                    || (edited == newest
+                        && self
+                            .checkpoint
+                            .seen_at_last_edited_time
+                            .iter()
+                            .any(|id| id == item.id()))
+            }
+            _ => false,
+        }
+    }
+
+    fn record(
+        &mut self,
+        item: &T,
+    ) {
+        self.checkpoint.results += 1;
+        let edited = match item.last_edited_time() {
+            Some(edited) => edited,
+            None => return,
+        };
+        match self.checkpoint.last_edited_time {
+            Some(newest) if edited < newest => {}
+            Some(newest) if edited == newest => self
+                .checkpoint
+                .seen_at_last_edited_time
+                .push(item.id().to_string()),
+            _ => {
+                self.checkpoint.last_edited_time = Some(edited);
+                self.checkpoint.seen_at_last_edited_time = vec![item.id().to_string()];
+            }
+        }
+    }
+}
+
+impl NotionApi {
+    async fn post_list(
+        &self,
+        path: &str,
+        body: &Value,
+    ) -> Result<ListResponse<Object>, Error> {
+        let result = self
+            .make_json_request(self.client.post(self.url(path)).json(body))
+            .await?;
+
+        match result {
+            Object::List { list } => Ok(list),
+            response => Err(Error::UnexpectedResponse {
+                response: Box::new(response),
+            }),
+        }
+    }
+}
+
+fn default_database_sort() -> DatabaseSort {
+    DatabaseSort {
+        property: None,
+        timestamp: Some(DatabaseSortTimestamp::LastEditedTime),
+        direction: SortDirection::Ascending,
+    }
+}
+
+fn default_search_sort() -> Value {
+    json!({
+        "timestamp": "last_edited_time",
+        "direction": "ascending",
+    })
+}
+
+/// Notion rejects expired or otherwise invalid cursors with a validation error naming
+/// `start_cursor`, other validation errors are about the query itself.
+fn is_invalid_cursor(error: &ErrorResponse) -> bool {
+    error.code == ErrorCode::ValidationError && error.message.contains("start_cursor")
+}
+
+fn query_json<T: Serialize>(query: &T) -> Result<Value, Error> {
+    let mut query =
+        serde_json::to_value(query).map_err(|source| Error::JsonParseError { source })?;
+    if let Some(query) = query.as_object_mut() {
+        query.remove("start_cursor");
+    }
+    Ok(query)
+}
+
+async fn read_checkpoint(path: &Path) -> Result<Option<Checkpoint>, Error> {
+    match tokio::fs::read(path).await {
+        Ok(json) => serde_json::from_slice(&json)
+            .map(Some)
+            .map_err(|source| Error::JsonParseError { source }),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(Error::CheckpointIoError { source }),
+    }
+}
+
+/// Writes to a temporary file first, so a crash never leaves a partially written checkpoint.
+async fn write_checkpoint(
+    path: &Path,
+    checkpoint: &Checkpoint,
+) -> Result<(), Error> {
+    let json =
+        serde_json::to_vec_pretty(checkpoint).map_err(|source| Error::JsonParseError { source })?;
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    tokio::fs::write(&temporary, json)
+        .await
+        .map_err(|source| Error::CheckpointIoError { source })?;
+    tokio::fs::rename(&temporary, path)
+        .await
+        .map_err(|source| Error::CheckpointIoError { source })
+}
@@ -0,0 +1,87 @@
+use crate::ids::{AsIdentifier, BlockId, DatabaseId};
+use crate::models::block::Block;
+use crate::models::paging::{Pageable, Paging};
+use crate::models::search::{DatabaseQuery, SearchRequest};
+use crate::models::{ListResponse, Object, Page};
+use crate::{Error, NotionApi};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use std::future::Future;
+
+/// Lazily fetches the pages of a list endpoint and yields their results one by one.
+/// The stream ends after the first error or once `max_items` results were yielded.
+fn paginate<'a, T, Q, F, Fut>(
+    query: Q,
+    max_items: Option<usize>,
+    fetch: F,
+) -> impl Stream<Item = Result<T, Error>> + 'a
+where
+    T: 'a,
+    Q: Pageable + Clone + 'a,
+    F: Fn(Q) -> Fut + 'a,
+    Fut: Future<Output = Result<ListResponse<T>, Error>> + 'a,
+{
+    stream::try_unfold(Some(query), move |query| {
+        let request = query.map(|query| (query.clone(), fetch(query)));
+        async move {
+            let (query, response) = match request {
+                Some(request) => request,
+                None => return Ok::<_, Error>(None),
+            };
+            let response = response.await?;
+            let next = match response.next_cursor {
+                Some(cursor) if response.has_more => Some(query.start_from(Some(cursor))),
+                _ => None,
+            };
+
+            Ok(Some((
+                stream::iter(response.results.into_iter().map(Ok)),
+                next,
+            )))
+        }
+    })
+    .try_flatten()
+    .take(max_items.unwrap_or(usize::MAX))
+}
+
+impl NotionApi {
+    /// Streams all pages matching `query`, fetching the next page of results only when needed.
+    /// At most `max_items` pages are yielded if set.
+    pub fn query_database_stream<D, T>(
+        &self,
+        database: D,
+        query: T,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<Page, Error>> + '_
+    where
+        T: Into<DatabaseQuery>,
+        D: AsIdentifier<DatabaseId>,
+    {
+        let database_id = database.as_id().clone();
+        paginate(query.into(), max_items, move |query| {
+            self.query_database(database_id.clone(), query)
+        })
+    }
+
+    /// Streams all search results, fetching the next page of results only when needed.
+    /// At most `max_items` objects are yielded if set.
+    pub fn search_stream<T: Into<SearchRequest>>(
+        &self,
+        query: T,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<Object, Error>> + '_ {
+        paginate(query.into(), max_items, move |query| self.search(query))
+    }
+
+    /// Streams the direct children of a block, fetching the next page of results only when needed.
+    /// At most `max_items` blocks are yielded if set.
+    pub fn block_children_stream<T: AsIdentifier<BlockId>>(
+        &self,
+        block_id: T,
+        max_items: Option<usize>,
+    ) -> impl Stream<Item = Result<Block, Error>> + '_ {
+        let block_id = block_id.as_id().clone();
+        paginate(Paging::default(), max_items, move |paging| {
+            self.get_block_children_with_paging(block_id.clone(), paging)
+        })
+    }
+}
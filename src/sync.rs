@@ -0,0 +1,655 @@
+//! Minimal updates of existing block trees, see [NotionApi::sync_page_content()].
+
+use crate::ids::{AsIdentifier, BlockId, PageId};
+use crate::models::block::{Block, CreateBlock};
+use crate::models::limits;
+use crate::{Error, NotionApi};
+use serde_json::Value;
+
+/// How many requests [NotionApi::sync_page_content()] keeps in flight to fetch the page.
+const FETCH_CONCURRENCY: usize = 3;
+
+/// Types of blocks whose content can't be changed through an update.
+const NOT_UPDATABLE: &[&str] = &[
+    "child_page",
+    "child_database",
+    "column_list",
+    "column",
+    "link_preview",
+    "synced_block",
+    "template",
+    "unsupported",
+];
+
+/// Types of blocks that are never deleted, deleting them moves the page or database to the trash.
+const PINNED: &[&str] = &["child_page", "child_database"];
+
+/// Types of blocks that can't be created through the API, so can't be moved by recreating them.
+const NOT_CREATABLE: &[&str] = &[
+    "child_page",
+    "child_database",
+    "link_preview",
+    "unsupported",
+];
+
+/// One change to a block tree, as computed by [diff_blocks()].
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum BlockOperation {
+    /// Replaces the content of an existing block, its children are left alone.
+    Update {
+        block_id: BlockId,
+        block: CreateBlock,
+    },
+    /// Inserts `blocks` into `parent` after its child `after`, or at the end if it's `None`.
+    Append {
+        parent: BlockId,
+        after: Option<BlockId>,
+        blocks: Vec<CreateBlock>,
+    },
+    /// Deletes an existing block together with its children.
+    Delete { block_id: BlockId },
+}
+
+/// Computes the operations turning the `existing` children of `parent` into `desired`.
+///
+/// Unchanged blocks are kept, changed blocks are updated in place when they keep their type
+/// and replaced otherwise. Existing blocks missing from `desired` are deleted, except child
+/// pages and databases which stay where they are.
+///
+/// Notion only inserts blocks after an existing one, so blocks inserted before the first kept
+/// block of a level go after it and that block is recreated behind them. If it can't be
+/// recreated, e.g. because it is a child page, the new blocks stay after it.
+/// The operations have to be applied in order.
+pub fn diff_blocks(
+    parent: &BlockId,
+    existing: &[Block],
+    desired: &[CreateBlock],
+) -> Vec<BlockOperation> {
+    let mut operations = vec![];
+    diff_level(parent, existing, desired, &mut operations);
+    operations
+}
+
+fn diff_level(
+    parent: &BlockId,
+    existing: &[Block],
+    desired: &[CreateBlock],
+    operations: &mut Vec<BlockOperation>,
+) {
+    let old: Vec<Normalized> = existing
+        .iter()
+        .map(|block| Normalized::new(&CreateBlock::from(block.clone())))
+        .collect();
+    let new: Vec<Normalized> = desired.iter().map(Normalized::new).collect();
+
+    let mut level = Level {
+        parent,
+        after: None,
+        run: vec![],
+        moved: None,
+        operations: vec![],
+    };
+    for step in align(existing, &old, &new) {
+        match step {
+            Step::Keep(i, j) | Step::Update(i, j)
+                if level.needs_anchor() && recreatable(&existing[i]) =>
+            {
+                level.move_block(&existing[i], desired[j].clone());
+            }
+            Step::Keep(i, _) | Step::Pin(i) => level.anchor(&existing[i]),
+            Step::Update(i, j) => {
+                let block = &existing[i];
+                level.anchor(block);
+                if old[i].content != new[j].content {
+                    level.operations.push(BlockOperation::Update {
+                        block_id: block.as_id().clone(),
+                        block: desired[j].clone(),
+                    });
+                }
+                let children = desired[j].nested().map(Vec::as_slice).unwrap_or_default();
+                diff_level(
+                    block.as_id(),
+                    block.children(),
+                    children,
+                    &mut level.operations,
+                );
+            }
+            Step::Delete(i) => level.operations.push(BlockOperation::Delete {
+                block_id: existing[i].as_id().clone(),
+            }),
+            Step::Insert(j) => level.run.push(desired[j].clone()),
+        }
+    }
+    level.flush();
+    operations.append(&mut level.operations);
+}
+
+/// A block as JSON, with and without its children.
+struct Normalized {
+    kind: String,
+    full: Value,
+    content: Value,
+}
+
+impl Normalized {
+    fn new(block: &CreateBlock) -> Self {
+        let full = normalize(block, true);
+        let content = normalize(block, false);
+        Normalized {
+            kind: full["type"].as_str().unwrap_or_default().to_string(),
+            full,
+            content,
+        }
+    }
+
+    fn children(&self) -> &Value {
+        &self.full[&self.kind]["children"]
+    }
+}
+
+enum Step {
+    Keep(usize, usize),
+    /// A block that can't be deleted and isn't in the desired blocks.
+    Pin(usize),
+    Update(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Aligns the existing blocks with the desired ones using as few operations as possible.
+///
+/// Deletes and inserts cost two each, updates one for the content and one for changed
+/// children, so that blocks are rather updated than replaced and keep their ids.
+/// Pinned blocks are kept for free instead of being deleted.
+fn align(
+    existing: &[Block],
+    old: &[Normalized],
+    new: &[Normalized],
+) -> Vec<Step> {
+    let update_cost = |i: usize, j: usize| -> Option<usize> {
+        if old[i].full == new[j].full {
+            Some(0)
+        } else if updatable(&existing[i], &old[i], &new[j]) {
+            Some(
+                (old[i].content != new[j].content) as usize
+                    + (old[i].children() != new[j].children()) as usize,
+            )
+        } else {
+            None
+        }
+    };
+
+    let delete_cost = |i: usize| -> usize {
+        if PINNED.contains(&existing[i].block_type()) {
+            0
+        } else {
+            2
+        }
+    };
+
+    // costs[i][j] is the cost of turning old[i..] into new[j..]
+    let mut costs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..=old.len()).rev() {
+        for j in (0..=new.len()).rev() {
+            costs[i][j] = if i == old.len() {
+                2 * (new.len() - j)
+            } else if j == new.len() {
+                delete_cost(i) + costs[i + 1][j]
+            } else {
+                let replace = (delete_cost(i) + costs[i + 1][j]).min(2 + costs[i][j + 1]);
+                match update_cost(i, j) {
+                    Some(cost) => replace.min(cost + costs[i + 1][j + 1]),
+                    None => replace,
+                }
+            };
+        }
+    }
+
+    let delete = |i: usize| {
+        if delete_cost(i) == 0 {
+            Step::Pin(i)
+        } else {
+            Step::Delete(i)
+        }
+    };
+    let mut steps = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i == old.len() {
+            steps.push(Step::Insert(j));
+            j += 1;
+        } else if j == new.len() {
+            steps.push(delete(i));
+            i += 1;
+        } else {
+            match update_cost(i, j) {
+                Some(cost) if cost + costs[i + 1][j + 1] == costs[i][j] => {
+                    steps.push(if cost == 0 {
+                        Step::Keep(i, j)
+                    } else {
+                        Step::Update(i, j)
+                    });
+                    i += 1;
+                    j += 1;
+                }
+                _ if delete_cost(i) + costs[i + 1][j] == costs[i][j] => {
+                    steps.push(delete(i));
+                    i += 1;
+                }
+                _ => {
+                    steps.push(Step::Insert(j));
+                    j += 1;
+                }
+            }
+        }
+    }
+    steps
+}
+
+/// The operations for the children of one block, collected until it's clear where new blocks
+/// can be inserted.
+struct Level<'a> {
+    parent: &'a BlockId,
+    /// The block new blocks are inserted after.
+    after: Option<BlockId>,
+    /// New blocks waiting to be inserted before the next kept block.
+    run: Vec<CreateBlock>,
+    /// The first kept block, if it is recreated after the new blocks in front of it.
+    moved: Option<BlockId>,
+    operations: Vec<BlockOperation>,
+}
+
+impl Level<'_> {
+    /// Whether new blocks wait in front of the first kept block.
+    fn needs_anchor(&self) -> bool {
+        self.after.is_none() && !self.run.is_empty()
+    }
+
+    /// Inserts the waiting blocks after `block` and recreates it behind them.
+    fn move_block(
+        &mut self,
+        block: &Block,
+        desired: CreateBlock,
+    ) {
+        self.after = Some(block.as_id().clone());
+        self.moved = Some(block.as_id().clone());
+        self.run.push(desired);
+    }
+
+    /// Keeps `block` in place, the waiting blocks are inserted before it.
+    fn anchor(
+        &mut self,
+        block: &Block,
+    ) {
+        if self.needs_anchor() {
+            // The block can't be moved, the waiting blocks go after it instead.
+            self.after = Some(block.as_id().clone());
+            return;
+        }
+        self.flush();
+        self.after = Some(block.as_id().clone());
+    }
+
+    fn flush(&mut self) {
+        if !self.run.is_empty() {
+            self.operations.push(BlockOperation::Append {
+                parent: self.parent.clone(),
+                after: self.after.clone(),
+                blocks: std::mem::take(&mut self.run),
+            });
+        }
+        if let Some(block_id) = self.moved.take() {
+            self.operations.push(BlockOperation::Delete { block_id });
+        }
+    }
+}
+
+fn recreatable(block: &Block) -> bool {
+    !NOT_CREATABLE.contains(&block.block_type()) && block.children().iter().all(recreatable)
+}
+
+fn updatable(
+    block: &Block,
+    old: &Normalized,
+    new: &Normalized,
+) -> bool {
+    if old.kind != new.kind || NOT_UPDATABLE.contains(&block.block_type()) {
+        return false;
+    }
+    match block {
+        Block::Table { .. } => {
+            old.content["table"]["table_width"] == new.content["table"]["table_width"]
+        }
+        _ => true,
+    }
+}
+
+/// The block as JSON without the fields Notion fills in itself, so that fetched blocks compare
+/// equal to the blocks they were created from.
+fn normalize(
+    block: &CreateBlock,
+    with_children: bool,
+) -> Value {
+    let mut block = block.clone();
+    normalize_text(&mut block);
+    let mut value = serde_json::to_value(block).expect("blocks serialize to JSON");
+    if let Some(kind) = value["type"].as_str().map(str::to_string) {
+        if let Some(fields) = value[&kind].as_object_mut() {
+            let empty = match fields.get("children") {
+                Some(Value::Array(children)) => children.is_empty(),
+                Some(Value::Null) => true,
+                _ => false,
+            };
+            if empty || !with_children {
+                fields.remove("children");
+            }
+        }
+    }
+    value
+}
+
+fn normalize_text(block: &mut CreateBlock) {
+    for text in block.text_fields_mut() {
+        for run in text.iter_mut() {
+            *run = run.normalized();
+        }
+    }
+    if let Some(children) = block.nested_mut() {
+        children.iter_mut().for_each(normalize_text);
+    }
+}
+
+impl NotionApi {
+    /// Changes the content of a page to `content`, keeping the blocks that are already there.
+    ///
+    /// Only the differences are sent, see [diff_blocks()], which keeps the ids, comments and
+    /// backlinks of unchanged blocks. Returns the applied operations.
+    pub async fn sync_page_content<T: AsIdentifier<PageId>>(
+        &self,
+        page_id: T,
+        mut content: Vec<CreateBlock>,
+    ) -> Result<Vec<BlockOperation>, Error> {
+        limits::fit_blocks(&mut content);
+        let parent = BlockId::from(page_id.as_id().clone());
+        let existing = self
+            .fetch_block_tree(&parent, usize::MAX, FETCH_CONCURRENCY)
+            .await?;
+
+        let operations = diff_blocks(&parent, &existing, &content);
+        for operation in &operations {
+            self.apply_block_operation(operation).await?;
+        }
+        Ok(operations)
+    }
+
+    /// Applies a single operation computed by [diff_blocks()].
+    /// Appends of more blocks than Notion accepts at once are split into several requests.
+    pub async fn apply_block_operation(
+        &self,
+        operation: &BlockOperation,
+    ) -> Result<(), Error> {
+        match operation {
+            BlockOperation::Update { block_id, block } => {
+                self.update_block(block_id, block.clone()).await?;
+            }
+            BlockOperation::Delete { block_id } => {
+                self.delete_block(block_id).await?;
+            }
+            BlockOperation::Append {
+                parent,
+                after,
+                blocks,
+            } => {
+                let mut after = after.clone();
+                for batch in limits::split_blocks(blocks.clone()) {
+                    limits::check_arrays(&batch.blocks)
+                        .map_err(|errors| Error::LimitsExceeded { errors })?;
+                    let created = match &after {
+                        Some(after) => {
+                            self.append_block_children_after(parent, after, batch.blocks)
+                                .await?
+                        }
+                        None => self.append_block_children(parent, batch.blocks).await?,
+                    };
+                    self.append_deferred_children(&created.results, batch.deferred)
+                        .await?;
+                    if let Some(last) = created.results.last() {
+                        after = Some(last.as_id().clone());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_blocks, BlockOperation};
+    use crate::ids::BlockId;
+    use crate::models::block::{Block, CreateBlock, Text, TextAndChildren};
+    use crate::models::text::{RichText, TextColor};
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn id(n: u8) -> BlockId {
+        BlockId::from_str(&format!("00000000-0000-0000-0000-000000000{:03}", n)).unwrap()
+    }
+
+    fn existing(
+        n: u8,
+        kind: &str,
+        content: &str,
+        children: Vec<Block>,
+    ) -> Block {
+        let mut block: Block = serde_json::from_value(json!({
+            "object": "block",
+            "id": id(n),
+            "created_time": "2022-05-13T20:08:00.000Z",
+            "last_edited_time": "2022-05-13T20:08:00.000Z",
+            "created_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+            "last_edited_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+            "has_children": !children.is_empty(),
+            "archived": false,
+            "type": kind,
+            kind: {
+                "rich_text": [{
+                    "type": "text",
+                    "text": { "content": content, "link": null },
+                    "annotations": {
+                        "bold": false, "italic": false, "strikethrough": false,
+                        "underline": false, "code": false, "color": "default"
+                    },
+                    "plain_text": content,
+                    "href": null
+                }],
+                "color": "default"
+            }
+        }))
+        .unwrap();
+        if !children.is_empty() {
+            block.set_children(children);
+        }
+        block
+    }
+
+    fn paragraph(
+        content: &str,
+        children: Vec<CreateBlock>,
+    ) -> CreateBlock {
+        CreateBlock::Paragraph {
+            paragraph: TextAndChildren {
+                rich_text: vec![RichText::text(content)],
+                children: if children.is_empty() {
+                    None
+                } else {
+                    Some(children)
+                },
+                color: TextColor::Default,
+            },
+        }
+    }
+
+    fn heading(content: &str) -> CreateBlock {
+        CreateBlock::Heading1 {
+            heading_1: Text {
+                rich_text: vec![RichText::text(content)],
+            },
+        }
+    }
+
+    #[test]
+    fn unchanged_blocks_need_no_operations() {
+        let parent = id(0);
+        let blocks = vec![
+            existing(
+                1,
+                "paragraph",
+                "a",
+                vec![existing(11, "paragraph", "aa", vec![])],
+            ),
+            existing(2, "heading_1", "b", vec![]),
+        ];
+        let desired = vec![paragraph("a", vec![paragraph("aa", vec![])]), heading("b")];
+
+        assert_eq!(diff_blocks(&parent, &blocks, &desired), vec![]);
+    }
+
+    #[test]
+    fn minimal_operations() {
+        let parent = id(0);
+        let blocks = vec![
+            existing(1, "paragraph", "a", vec![]),
+            existing(
+                2,
+                "paragraph",
+                "b",
+                vec![existing(21, "paragraph", "x", vec![])],
+            ),
+            existing(3, "heading_1", "c", vec![]),
+            existing(4, "paragraph", "d", vec![]),
+        ];
+        let desired = vec![
+            paragraph("a", vec![]),
+            heading("new"),
+            paragraph(
+                "b changed",
+                vec![paragraph("x", vec![]), paragraph("y", vec![])],
+            ),
+            heading("c"),
+            heading("e"),
+        ];
+
+        assert_eq!(
+            diff_blocks(&parent, &blocks, &desired),
+            vec![
+                BlockOperation::Append {
+                    parent: id(0),
+                    after: Some(id(1)),
+                    blocks: vec![heading("new")],
+                },
+                BlockOperation::Update {
+                    block_id: id(2),
+                    block: desired[2].clone(),
+                },
+                BlockOperation::Append {
+                    parent: id(2),
+                    after: Some(id(21)),
+                    blocks: vec![paragraph("y", vec![])],
+                },
+                BlockOperation::Delete { block_id: id(4) },
+                BlockOperation::Append {
+                    parent: id(0),
+                    after: Some(id(3)),
+                    blocks: vec![heading("e")],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn replaces_blocks_of_another_type() {
+        let parent = id(0);
+        let blocks = vec![
+            existing(1, "paragraph", "a", vec![]),
+            existing(2, "paragraph", "b", vec![]),
+        ];
+        let desired = vec![paragraph("a", vec![]), heading("b")];
+
+        assert_eq!(
+            diff_blocks(&parent, &blocks, &desired),
+            vec![
+                BlockOperation::Delete { block_id: id(2) },
+                BlockOperation::Append {
+                    parent: id(0),
+                    after: Some(id(1)),
+                    blocks: vec![heading("b")],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn recreates_only_the_first_block_when_inserting_at_the_start() {
+        let parent = id(0);
+        let blocks = vec![
+            existing(1, "paragraph", "a", vec![]),
+            existing(2, "paragraph", "b", vec![]),
+            existing(3, "paragraph", "c", vec![]),
+        ];
+        let desired = vec![
+            heading("first"),
+            paragraph("a", vec![]),
+            heading("second"),
+            paragraph("b", vec![]),
+            paragraph("c", vec![]),
+        ];
+
+        assert_eq!(
+            diff_blocks(&parent, &blocks, &desired),
+            vec![
+                BlockOperation::Append {
+                    parent: id(0),
+                    after: Some(id(1)),
+                    blocks: desired[..3].to_vec(),
+                },
+                BlockOperation::Delete { block_id: id(1) },
+            ]
+        );
+    }
+
+    #[test]
+    fn never_deletes_child_pages() {
+        let child_page: Block = serde_json::from_value(json!({
+            "object": "block",
+            "id": id(1),
+            "created_time": "2022-05-13T20:08:00.000Z",
+            "last_edited_time": "2022-05-13T20:08:00.000Z",
+            "created_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+            "last_edited_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+            "has_children": false,
+            "archived": false,
+            "type": "child_page",
+            "child_page": { "title": "Notes" }
+        }))
+        .unwrap();
+        let parent = id(0);
+        let blocks = vec![child_page, existing(2, "paragraph", "a", vec![])];
+        let desired = vec![heading("first"), paragraph("b", vec![])];
+
+        assert_eq!(
+            diff_blocks(&parent, &blocks, &desired),
+            vec![
+                BlockOperation::Append {
+                    parent: id(0),
+                    after: Some(id(1)),
+                    blocks: vec![heading("first")],
+                },
+                BlockOperation::Update {
+                    block_id: id(2),
+                    block: paragraph("b", vec![]),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use test_log::test;
+mod common;
+use common::{list_json, mock_client, paragraph_json};
+use rusticnotion::{
+    ids::{AsIdentifier, BlockId},
+    models::block::{CreateBlock, TextAndChildren},
+    models::paging::Paging,
+    models::text::{RichText, TextColor},
+};
+use std::str::FromStr;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const PARENT: &str = "b8b944b5-cc3d-444e-a25c-a7ddacd528cb";
+
+async fn mount_two_pages(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path(format!("/blocks/{}/children", PARENT)))
+        .and(query_param("start_cursor", "cursor-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![paragraph_json(
+                "00000000-0000-0000-0000-000000000003",
+                "third",
+                false,
+            )],
+            None,
+        )))
+        .with_priority(1)
+        .expect(1)
+        .mount(server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path(format!("/blocks/{}/children", PARENT)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![
+                paragraph_json("00000000-0000-0000-0000-000000000001", "first", false),
+                paragraph_json("00000000-0000-0000-0000-000000000002", "second", false),
+            ],
+            Some("cursor-2"),
+        )))
+        .mount(server)
+        .await;
+}
+
+#[test(tokio::test)]
+async fn get_block_children_with_paging() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_two_pages(&server).await;
+    let api = mock_client(&server);
+
+    let first = api.get_block_children(BlockId::from_str(PARENT)?).await?;
+    assert_eq!(first.results.len(), 2);
+    assert!(first.has_more);
+
+    let second = api
+        .get_block_children_with_paging(
+            BlockId::from_str(PARENT)?,
+            Paging {
+                start_cursor: first.next_cursor,
+                page_size: Some(2),
+            },
+        )
+        .await?;
+    assert_eq!(second.results.len(), 1);
+    assert!(!second.has_more);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn get_all_block_children() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_two_pages(&server).await;
+    let api = mock_client(&server);
+
+    let children = api
+        .get_all_block_children(BlockId::from_str(PARENT)?)
+        .await?;
+
+    let ids: Vec<String> = children
+        .iter()
+        .map(|block| block.as_id().to_string())
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            "00000000-0000-0000-0000-000000000001",
+            "00000000-0000-0000-0000-000000000002",
+            "00000000-0000-0000-0000-000000000003",
+        ]
+    );
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn append_all_block_children_in_batches() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path(format!("/blocks/{}/children", PARENT)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![paragraph_json(
+                "00000000-0000-0000-0000-000000000001",
+                "created",
+                false,
+            )],
+            None,
+        )))
+        .expect(3)
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+    let children = (0..250)
+        .map(|_| CreateBlock::Paragraph {
+            paragraph: TextAndChildren {
+                rich_text: vec![RichText::text("x".repeat(2500))],
+                children: None,
+                color: TextColor::Default,
+            },
+        })
+        .collect();
+
+    let created = api
+        .append_all_block_children(BlockId::from_str(PARENT)?, children)
+        .await?;
+    assert_eq!(created.len(), 3);
+
+    let requests = server.received_requests().await.unwrap();
+    let sizes: Vec<usize> = requests
+        .iter()
+        .map(|request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let first = &body["children"][0]["paragraph"]["rich_text"];
+            assert_eq!(first.as_array().unwrap().len(), 2);
+            body["children"].as_array().unwrap().len()
+        })
+        .collect();
+    assert_eq!(sizes, vec![100, 100, 50]);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn append_all_block_children_defers_nested_children() -> Result<(), Box<dyn std::error::Error>>
+{
+    const TOGGLE: &str = "00000000-0000-0000-0000-000000000001";
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path(format!("/blocks/{}/children", PARENT)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![paragraph_json(TOGGLE, "toggle", true)],
+            None,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path(format!("/blocks/{}/children", TOGGLE)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![paragraph_json(
+                "00000000-0000-0000-0000-000000000002",
+                "child",
+                false,
+            )],
+            None,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+    let paragraph = |content: String| CreateBlock::Paragraph {
+        paragraph: TextAndChildren {
+            rich_text: vec![RichText::text(content)],
+            children: None,
+            color: TextColor::Default,
+        },
+    };
+    let toggle = CreateBlock::Toggle {
+        toggle: TextAndChildren {
+            rich_text: vec![RichText::text("toggle")],
+            children: Some((0..150).map(|n| paragraph(n.to_string())).collect()),
+            color: TextColor::Default,
+        },
+    };
+
+    api.append_all_block_children(BlockId::from_str(PARENT)?, vec![toggle])
+        .await?;
+
+    let requests = server.received_requests().await.unwrap();
+    let children: Vec<usize> = requests
+        .iter()
+        .map(|request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            match body["children"][0]["toggle"]["children"].as_array() {
+                Some(nested) => nested.len(),
+                None => body["children"].as_array().unwrap().len(),
+            }
+        })
+        .collect();
+    assert_eq!(children, vec![100, 50]);
+
+    Ok(())
+}
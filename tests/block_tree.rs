@@ -0,0 +1,141 @@
+use test_log::test;
+mod common;
+use common::{list_json, mock_client, paragraph_json};
+use rusticnotion::ids::{AsIdentifier, BlockId};
+use rusticnotion::models::block::Block;
+use serde_json::Value;
+use std::str::FromStr;
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const PARENT: &str = "b8b944b5-cc3d-444e-a25c-a7ddacd528cb";
+
+fn block_id(n: u16) -> String {
+    format!("00000000-0000-0000-0000-000000000{:03}", n)
+}
+
+async fn mount_children(
+    server: &MockServer,
+    parent: &str,
+    children: Vec<Value>,
+    delay: Duration,
+    expected_requests: u64,
+) {
+    Mock::given(method("GET"))
+        .and(path(format!("/blocks/{}/children", parent)))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(list_json(children, None))
+                .set_delay(delay),
+        )
+        .expect(expected_requests)
+        .mount(server)
+        .await;
+}
+
+/// ```text
+/// 1
+///   11
+///     111
+/// 2
+/// 3
+///   31
+/// ```
+async fn mount_tree(
+    server: &MockServer,
+    deepest_requests: u64,
+) {
+    mount_children(
+        server,
+        PARENT,
+        vec![
+            paragraph_json(&block_id(1), "1", true),
+            paragraph_json(&block_id(2), "2", false),
+            paragraph_json(&block_id(3), "3", true),
+        ],
+        Duration::ZERO,
+        1,
+    )
+    .await;
+    // The first subtree is slower, its blocks must still come first.
+    mount_children(
+        server,
+        &block_id(1),
+        vec![paragraph_json(&block_id(11), "11", true)],
+        Duration::from_millis(200),
+        1,
+    )
+    .await;
+    mount_children(
+        server,
+        &block_id(11),
+        vec![paragraph_json(&block_id(111), "111", false)],
+        Duration::ZERO,
+        deepest_requests,
+    )
+    .await;
+    mount_children(
+        server,
+        &block_id(3),
+        vec![paragraph_json(&block_id(31), "31", false)],
+        Duration::ZERO,
+        1,
+    )
+    .await;
+}
+
+fn children(block: &Block) -> &[Block] {
+    match block {
+        Block::Paragraph { paragraph, .. } => paragraph.children.as_deref().unwrap_or_default(),
+        _ => panic!("Expected a paragraph"),
+    }
+}
+
+fn ids(blocks: &[Block]) -> Vec<BlockId> {
+    blocks.iter().map(|block| block.as_id().clone()).collect()
+}
+
+fn expected(numbers: &[u16]) -> Vec<BlockId> {
+    numbers
+        .iter()
+        .map(|&n| BlockId::from_str(&block_id(n)).unwrap())
+        .collect()
+}
+
+#[test(tokio::test)]
+async fn fetch_block_tree_fills_children() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_tree(&server, 1).await;
+    let api = mock_client(&server);
+
+    let tree = api
+        .fetch_block_tree(BlockId::from_str(PARENT)?, 10, 4)
+        .await?;
+
+    assert_eq!(ids(&tree), expected(&[1, 2, 3]));
+    assert_eq!(ids(children(&tree[0])), expected(&[11]));
+    assert_eq!(ids(children(&children(&tree[0])[0])), expected(&[111]));
+    assert!(children(&tree[1]).is_empty());
+    assert_eq!(ids(children(&tree[2])), expected(&[31]));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn fetch_block_tree_stops_at_max_depth() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_tree(&server, 0).await;
+    let api = mock_client(&server);
+
+    let tree = api
+        .fetch_block_tree(BlockId::from_str(PARENT)?, 2, 1)
+        .await?;
+
+    let nested = &children(&tree[0])[0];
+    assert_eq!(ids(std::slice::from_ref(nested)), expected(&[11]));
+    assert!(children(nested).is_empty());
+    assert_eq!(ids(children(&tree[2])), expected(&[31]));
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use dotenv::dotenv;
 use rusticnotion::NotionApi;
+use serde_json::{json, Value};
+use wiremock::MockServer;
 
 pub fn test_token() -> String {
     dotenv().ok();
@@ -16,6 +18,97 @@ pub fn test_token() -> String {
     token.trim().to_string()
 }
 
+#[allow(dead_code)]
 pub fn test_client() -> NotionApi {
     NotionApi::new(test_token()).unwrap()
 }
+
+/// A client talking to a local [MockServer] instead of the Notion API.
+#[allow(dead_code)]
+pub fn mock_client(server: &MockServer) -> NotionApi {
+    NotionApi::new("secret_mock_token".to_string())
+        .unwrap()
+        .with_base_url(server.uri())
+}
+
+/// A minimal paragraph block as returned by the API.
+#[allow(dead_code)]
+pub fn paragraph_json(
+    id: &str,
+    content: &str,
+    has_children: bool,
+) -> Value {
+    json!({
+        "object": "block",
+        "id": id,
+        "created_time": "2022-05-13T20:08:00.000Z",
+        "last_edited_time": "2022-05-13T20:08:00.000Z",
+        "created_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+        "last_edited_by": { "object": "user", "id": "e2507360-468c-4e0f-a928-7bbcbbb45353" },
+        "has_children": has_children,
+        "archived": false,
+        "type": "paragraph",
+        "paragraph": {
+            "rich_text": [{
+                "type": "text",
+                "text": { "content": content, "link": null },
+                "annotations": {
+                    "bold": false, "italic": false, "strikethrough": false,
+                    "underline": false, "code": false, "color": "default"
+                },
+                "plain_text": content,
+                "href": null
+            }],
+            "color": "default"
+        }
+    })
+}
+
+/// A page with a title and a formula property as returned by the API.
+#[allow(dead_code)]
+pub fn page_json(
+    id: &str,
+    parent: &str,
+    title: &str,
+) -> Value {
+    json!({
+        "object": "page",
+        "id": id,
+        "created_time": "2022-05-13T20:08:00.000Z",
+        "last_edited_time": "2022-05-13T20:08:00.000Z",
+        "archived": false,
+        "icon": { "type": "emoji", "emoji": "📄" },
+        "parent": { "type": "page_id", "page_id": parent },
+        "properties": {
+            "title": {
+                "id": "title",
+                "type": "title",
+                "title": [{
+                    "type": "text",
+                    "text": { "content": title, "link": null },
+                    "plain_text": title,
+                    "href": null
+                }]
+            },
+            "Words": {
+                "id": "abcd",
+                "type": "formula",
+                "formula": { "type": "number", "number": 2 }
+            }
+        }
+    })
+}
+
+/// Wraps `results` in a list response.
+#[allow(dead_code)]
+pub fn list_json(
+    results: Vec<Value>,
+    next_cursor: Option<&str>,
+) -> Value {
+    json!({
+        "object": "list",
+        "results": results,
+        "next_cursor": next_cursor,
+        "has_more": next_cursor.is_some(),
+    })
+}
@@ -0,0 +1,250 @@
+use chrono::NaiveDate;
+use rusticnotion::ids::PageId;
+use rusticnotion::models::mapping::{NotionPage, PropertyKind, PropertySchema};
+use rusticnotion::models::properties::{PropertyError, PropertyValue};
+use rusticnotion::models::{Database, Parent, Properties};
+use serde_json::json;
+use std::str::FromStr;
+
+#[derive(rusticnotion_derive::NotionPage, Debug, PartialEq)]
+struct Task {
+    #[notion(name = "Name", kind = "title")]
+    name: String,
+    #[notion(name = "Due", kind = "date")]
+    due: Option<NaiveDate>,
+    #[notion(kind = "checkbox")]
+    done: bool,
+    #[notion(name = "Estimate", kind = "number")]
+    estimate: Option<i64>,
+    #[notion(name = "State", kind = "select")]
+    state: String,
+    #[notion(name = "Tags", kind = "multi_select")]
+    tags: Vec<String>,
+    #[notion(name = "Blocked by", kind = "relation")]
+    blocked_by: Vec<PageId>,
+    #[notion(name = "Score", kind = "formula")]
+    score: Option<f64>,
+    #[notion(skip)]
+    comment: String,
+}
+
+fn database() -> Database {
+    serde_json::from_value(json!({
+        "object": "database",
+        "id": "a1d8501e-1ac1-43e9-a6bd-ea9fe6c8822b",
+        "created_time": "2022-05-13T20:08:00.000Z",
+        "last_edited_time": "2022-05-13T20:08:00.000Z",
+        "title": [],
+        "icon": null,
+        "properties": {
+            "Name": { "id": "title", "type": "title", "title": {} },
+            "Due": { "id": "d%3Ae", "type": "date", "date": {} },
+            "done": { "id": "d0n3", "type": "checkbox", "checkbox": {} },
+            "Estimate": { "id": "esti", "type": "number", "number": { "format": "number" } },
+            "State": {
+                "id": "st8",
+                "type": "select",
+                "select": { "options": [{ "id": "1", "name": "Open", "color": "green" }] }
+            },
+            "Tags": {
+                "id": "tags",
+                "type": "multi_select",
+                "multi_select": { "options": [] }
+            },
+            "Blocked by": {
+                "id": "blck",
+                "type": "relation",
+                "relation": {
+                    "database_id": "a1d8501e-1ac1-43e9-a6bd-ea9fe6c8822b",
+                    "type": "single_property",
+                    "single_property": {}
+                }
+            },
+            "Score": {
+                "id": "scr",
+                "type": "formula",
+                "formula": { "expression": "prop(\"Estimate\") * 2" }
+            }
+        }
+    }))
+    .unwrap()
+}
+
+fn properties() -> Properties {
+    serde_json::from_value(json!({
+        "Name": {
+            "id": "title",
+            "type": "title",
+            "title": [{
+                "type": "text",
+                "text": { "content": "Write docs", "link": null },
+                "plain_text": "Write docs",
+                "href": null
+            }]
+        },
+        "Due": {
+            "id": "d%3Ae",
+            "type": "date",
+            "date": { "start": "2022-02-22", "end": null, "time_zone": null }
+        },
+        "done": { "id": "d0n3", "type": "checkbox", "checkbox": false },
+        "Estimate": { "id": "esti", "type": "number", "number": null },
+        "State": {
+            "id": "st8",
+            "type": "select",
+            "select": { "id": "1", "name": "Open", "color": "green" }
+        },
+        "Tags": {
+            "id": "tags",
+            "type": "multi_select",
+            "multi_select": [{ "id": "2", "name": "docs", "color": "red" }]
+        },
+        "Blocked by": {
+            "id": "blck",
+            "type": "relation",
+            "relation": [{ "id": "90eeeed8-2cdd-4af4-9cc1-3d24aff5f63c" }]
+        },
+        "Score": {
+            "id": "scr",
+            "type": "formula",
+            "formula": { "type": "number", "number": 4 }
+        }
+    }))
+    .unwrap()
+}
+
+fn task() -> Task {
+    Task {
+        name: "Write docs".to_string(),
+        due: NaiveDate::from_ymd_opt(2022, 2, 22),
+        done: false,
+        estimate: None,
+        state: "Open".to_string(),
+        tags: vec!["docs".to_string()],
+        blocked_by: vec![PageId::from_str("90eeeed8-2cdd-4af4-9cc1-3d24aff5f63c").unwrap()],
+        score: Some(4.0),
+        comment: String::new(),
+    }
+}
+
+#[test]
+fn reads_struct_from_properties() {
+    assert_eq!(Task::from_properties(&properties()), Ok(task()));
+}
+
+#[test]
+fn reports_property_errors() {
+    let mut properties = properties();
+    properties.properties.remove("State");
+    assert_eq!(
+        Task::from_properties(&properties),
+        Err(PropertyError::Missing {
+            property: "'State'".to_string()
+        })
+    );
+
+    let mut properties = self::properties();
+    properties.properties.insert(
+        "done".to_string(),
+        properties.properties["Estimate"].clone(),
+    );
+    assert_eq!(
+        Task::from_properties(&properties),
+        Err(PropertyError::WrongType {
+            property: "'done'".to_string(),
+            expected: "checkbox",
+            actual: "number",
+        })
+    );
+}
+
+#[test]
+fn rejects_readable_property_of_other_kind() {
+    // The url could be read as a `String`, but `State` is declared as a select.
+    let mut properties = properties();
+    properties.properties.insert(
+        "State".to_string(),
+        serde_json::from_value(json!({
+            "id": "st8",
+            "type": "url",
+            "url": "https://example.com"
+        }))
+        .unwrap(),
+    );
+    assert_eq!(
+        Task::from_properties(&properties),
+        Err(PropertyError::WrongType {
+            property: "'State'".to_string(),
+            expected: "select",
+            actual: "url",
+        })
+    );
+}
+
+#[test]
+fn writes_struct_to_properties() {
+    let database = database();
+    let properties = task().to_properties(&database).unwrap();
+
+    // Formulas are computed by Notion, everything else is written.
+    assert!(!properties.properties.contains_key("Score"));
+    assert!(matches!(
+        properties.properties["Estimate"],
+        PropertyValue::Number { number: None, .. }
+    ));
+
+    let mut written = properties;
+    written.properties.insert(
+        "Score".to_string(),
+        self::properties().properties["Score"].clone(),
+    );
+    assert_eq!(Task::from_properties(&written), Ok(task()));
+}
+
+#[test]
+fn writes_create_request() {
+    let database = database();
+    let request = task().to_create_request(&database).unwrap();
+
+    assert_eq!(
+        request.parent,
+        Parent::Database {
+            database_id: database.id.clone()
+        }
+    );
+    let properties = serde_json::to_value(&request.properties).unwrap();
+    assert_eq!(
+        properties["State"],
+        json!({ "select": { "id": "1", "name": "Open" } })
+    );
+    assert_eq!(
+        properties["Tags"],
+        json!({ "multi_select": [{ "name": "docs" }] })
+    );
+    assert_eq!(
+        properties["Blocked by"],
+        json!({ "relation": [{ "id": "90eeeed8-2cdd-4af4-9cc1-3d24aff5f63c" }] })
+    );
+}
+
+#[test]
+fn checks_schema_against_database() {
+    assert_eq!(Task::schema().len(), 8);
+    assert_eq!(
+        Task::schema()[0],
+        PropertySchema {
+            name: "Name",
+            kind: PropertyKind::Title
+        }
+    );
+    assert_eq!(Task::check_schema(&database()), Ok(()));
+
+    let mut database = database();
+    database.properties.remove("Due");
+    assert_eq!(
+        Task::check_schema(&database),
+        Err(PropertyError::Missing {
+            property: "'Due'".to_string()
+        })
+    );
+}
@@ -0,0 +1,468 @@
+use test_log::test;
+mod common;
+use common::{list_json, mock_client, page_json, paragraph_json};
+use rusticnotion::duplicate::SkipReason;
+use rusticnotion::ids::{DatabaseId, PageId};
+use rusticnotion::models::Parent;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const SOURCE: &str = "11111111-1111-1111-1111-111111111111";
+const SOURCE_CHILD_PAGE: &str = "22222222-2222-2222-2222-222222222222";
+const SOURCE_PARAGRAPH: &str = "33333333-3333-3333-3333-333333333333";
+const NEW_PARENT: &str = "44444444-4444-4444-4444-444444444444";
+const COPY: &str = "55555555-5555-5555-5555-555555555555";
+const COPY_CHILD_PAGE: &str = "66666666-6666-6666-6666-666666666666";
+const COPY_PARAGRAPH: &str = "77777777-7777-7777-7777-777777777777";
+
+fn block_json(
+    id: &str,
+    block_type: &str,
+    content: Value,
+) -> Value {
+    let mut block = paragraph_json(id, "", false);
+    let block = block.as_object_mut().unwrap();
+    block.remove("paragraph");
+    block.insert("type".to_string(), json!(block_type));
+    block.insert(block_type.to_string(), content);
+    json!(block)
+}
+
+async fn mount(
+    server: &MockServer,
+    http_method: &str,
+    url: String,
+    response: Value,
+) {
+    Mock::given(method(http_method))
+        .and(path(url))
+        .respond_with(ResponseTemplate::new(200).set_body_json(response))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[test(tokio::test)]
+async fn duplicate_page_with_nested_content() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+
+    mount(
+        &server,
+        "GET",
+        format!("/pages/{}", SOURCE),
+        page_json(SOURCE, NEW_PARENT, "Template"),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/pages/{}", SOURCE_CHILD_PAGE),
+        page_json(SOURCE_CHILD_PAGE, SOURCE, "Child"),
+    )
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/pages"))
+        .and(body_partial_json(
+            json!({ "parent": { "page_id": NEW_PARENT } }),
+        ))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(page_json(COPY, NEW_PARENT, "Template")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/pages"))
+        .and(body_partial_json(json!({ "parent": { "page_id": COPY } })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(
+            COPY_CHILD_PAGE,
+            COPY,
+            "Child",
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", SOURCE),
+        list_json(
+            vec![
+                paragraph_json(SOURCE_PARAGRAPH, "first", true),
+                block_json(SOURCE_CHILD_PAGE, "child_page", json!({ "title": "Child" })),
+                block_json(
+                    "88888888-8888-8888-8888-888888888888",
+                    "unsupported",
+                    json!({}),
+                ),
+            ],
+            None,
+        ),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", SOURCE_PARAGRAPH),
+        list_json(
+            vec![paragraph_json(
+                "99999999-9999-9999-9999-999999999999",
+                "nested",
+                false,
+            )],
+            None,
+        ),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", SOURCE_CHILD_PAGE),
+        list_json(vec![], None),
+    )
+    .await;
+    mount(
+        &server,
+        "PATCH",
+        format!("/blocks/{}/children", COPY),
+        list_json(vec![paragraph_json(COPY_PARAGRAPH, "first", false)], None),
+    )
+    .await;
+    mount(
+        &server,
+        "PATCH",
+        format!("/blocks/{}/children", COPY_PARAGRAPH),
+        list_json(
+            vec![paragraph_json(
+                "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa",
+                "nested",
+                false,
+            )],
+            None,
+        ),
+    )
+    .await;
+
+    let report = mock_client(&server)
+        .duplicate_page(
+            PageId::from_str(SOURCE)?,
+            Parent::Page {
+                page_id: PageId::from_str(NEW_PARENT)?,
+            },
+        )
+        .await?;
+
+    assert_eq!(report.page.id, PageId::from_str(COPY)?);
+    assert_eq!(report.child_pages.len(), 1);
+    assert_eq!(report.child_pages[0].id, PageId::from_str(COPY_CHILD_PAGE)?);
+    assert_eq!(report.skipped_properties.len(), 2);
+    assert!(report
+        .skipped_properties
+        .iter()
+        .all(|property| property.name == "Words"));
+    assert_eq!(report.skipped_blocks.len(), 1);
+    assert_eq!(report.skipped_blocks[0].block_type, "unsupported");
+    assert_eq!(report.skipped_blocks[0].reason, SkipReason::Unsupported);
+
+    // The paragraph has to be created before the child page to keep the order.
+    let requests = server.received_requests().await.unwrap();
+    let order: Vec<String> = requests
+        .iter()
+        .filter(|request| request.method.to_string() != "GET")
+        .map(|request| request.url.path().to_string())
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            "/pages".to_string(),
+            format!("/blocks/{}/children", COPY),
+            format!("/blocks/{}/children", COPY_PARAGRAPH),
+            "/pages".to_string(),
+        ]
+    );
+
+    let nested: Value = serde_json::from_slice(
+        &requests
+            .iter()
+            .find(|request| request.url.path() == format!("/blocks/{}/children", COPY_PARAGRAPH))
+            .unwrap()
+            .body,
+    )?;
+    assert_eq!(
+        nested["children"][0]["paragraph"]["rich_text"][0]["plain_text"],
+        "nested"
+    );
+
+    Ok(())
+}
+
+fn numbered_id(
+    prefix: u32,
+    number: usize,
+) -> String {
+    format!("{:08x}-0000-0000-0000-{:012x}", prefix, number)
+}
+
+#[test(tokio::test)]
+async fn duplicate_page_respects_request_limits() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    let source_column_list = numbered_id(1, 0);
+    let source_column = numbered_id(2, 0);
+    let source_table = numbered_id(3, 0);
+    let copy_column_list = numbered_id(4, 0);
+    let copy_column = numbered_id(5, 0);
+    let copy_table = numbered_id(9, 0);
+
+    mount(
+        &server,
+        "GET",
+        format!("/pages/{}", SOURCE),
+        page_json(SOURCE, NEW_PARENT, "Template"),
+    )
+    .await;
+    mount(
+        &server,
+        "POST",
+        "/pages".to_string(),
+        page_json(COPY, NEW_PARENT, "Template"),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", SOURCE),
+        list_json(
+            vec![
+                block_json(&source_column_list, "column_list", json!({})),
+                block_json(
+                    &source_table,
+                    "table",
+                    json!({ "table_width": 1, "has_column_header": false, "has_row_header": false }),
+                ),
+            ],
+            None,
+        ),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", source_column_list),
+        list_json(vec![block_json(&source_column, "column", json!({}))], None),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", source_column),
+        list_json(
+            (0..101)
+                .map(|number| paragraph_json(&numbered_id(6, number), "line", false))
+                .collect(),
+            None,
+        ),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", source_table),
+        list_json(
+            (0..101)
+                .map(|number| {
+                    block_json(&numbered_id(7, number), "table_row", json!({ "cells": [] }))
+                })
+                .collect(),
+            None,
+        ),
+    )
+    .await;
+    mount(
+        &server,
+        "PATCH",
+        format!("/blocks/{}/children", COPY),
+        list_json(
+            vec![
+                block_json(&copy_column_list, "column_list", json!({})),
+                block_json(
+                    &copy_table,
+                    "table",
+                    json!({ "table_width": 1, "has_column_header": false, "has_row_header": false }),
+                ),
+            ],
+            None,
+        ),
+    )
+    .await;
+    mount(
+        &server,
+        "PATCH",
+        format!("/blocks/{}/children", copy_table),
+        list_json(
+            vec![block_json(
+                &numbered_id(10, 0),
+                "table_row",
+                json!({ "cells": [] }),
+            )],
+            None,
+        ),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", copy_column_list),
+        list_json(vec![block_json(&copy_column, "column", json!({}))], None),
+    )
+    .await;
+    mount(
+        &server,
+        "PATCH",
+        format!("/blocks/{}/children", copy_column),
+        list_json(
+            vec![paragraph_json(&numbered_id(8, 0), "line", false)],
+            None,
+        ),
+    )
+    .await;
+
+    let report = mock_client(&server)
+        .duplicate_page(
+            PageId::from_str(SOURCE)?,
+            Parent::Page {
+                page_id: PageId::from_str(NEW_PARENT)?,
+            },
+        )
+        .await?;
+
+    assert!(report.skipped_blocks.is_empty());
+
+    let requests = server.received_requests().await.unwrap();
+    let body = |url: String| -> Value {
+        serde_json::from_slice(
+            &requests
+                .iter()
+                .find(|request| request.method.to_string() == "PATCH" && request.url.path() == url)
+                .unwrap()
+                .body,
+        )
+        .unwrap()
+    };
+    let appended = body(format!("/blocks/{}/children", COPY));
+    assert_eq!(appended["children"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        appended["children"][0]["column_list"]["children"][0]["column"]["children"]
+            .as_array()
+            .unwrap()
+            .len(),
+        100
+    );
+    let deferred = body(format!("/blocks/{}/children", copy_column));
+    assert_eq!(deferred["children"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        appended["children"][1]["table"]["children"]
+            .as_array()
+            .unwrap()
+            .len(),
+        100
+    );
+    let rows = body(format!("/blocks/{}/children", copy_table));
+    assert_eq!(rows["children"].as_array().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn duplicate_page_into_database_matches_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    let mut source = page_json(SOURCE, NEW_PARENT, "Template");
+    let properties = source["properties"].as_object_mut().unwrap();
+    properties.insert(
+        "Stage".to_string(),
+        json!({
+            "id": "st",
+            "type": "select",
+            "select": { "id": "source-option", "name": "Done", "color": "green" }
+        }),
+    );
+    properties.insert(
+        "Estimate".to_string(),
+        json!({ "id": "es", "type": "number", "number": 3 }),
+    );
+    properties.insert(
+        "Notes".to_string(),
+        json!({ "id": "no", "type": "rich_text", "rich_text": [] }),
+    );
+    mount(&server, "GET", format!("/pages/{}", SOURCE), source).await;
+    mount(
+        &server,
+        "GET",
+        format!("/databases/{}", NEW_PARENT),
+        json!({
+            "object": "database",
+            "id": NEW_PARENT,
+            "created_time": "2022-05-13T20:08:00.000Z",
+            "last_edited_time": "2022-05-13T20:08:00.000Z",
+            "title": [],
+            "icon": null,
+            "properties": {
+                "Name": { "id": "title", "type": "title", "title": {} },
+                "Stage": {
+                    "id": "xy",
+                    "type": "select",
+                    "select": { "options": [{ "id": "target-option", "name": "Done", "color": "green" }] }
+                },
+                "Estimate": { "id": "ab", "type": "rich_text", "rich_text": {} }
+            }
+        }),
+    )
+    .await;
+    mount(
+        &server,
+        "POST",
+        "/pages".to_string(),
+        page_json(COPY, NEW_PARENT, "Template"),
+    )
+    .await;
+    mount(
+        &server,
+        "GET",
+        format!("/blocks/{}/children", SOURCE),
+        list_json(vec![], None),
+    )
+    .await;
+
+    let report = mock_client(&server)
+        .duplicate_page(
+            PageId::from_str(SOURCE)?,
+            Parent::Database {
+                database_id: DatabaseId::from_str(NEW_PARENT)?,
+            },
+        )
+        .await?;
+
+    let mut skipped: Vec<&str> = report
+        .skipped_properties
+        .iter()
+        .map(|property| property.name.as_str())
+        .collect();
+    skipped.sort_unstable();
+    assert_eq!(skipped, vec!["Estimate", "Notes", "Words"]);
+
+    let requests = server.received_requests().await.unwrap();
+    let created = requests
+        .iter()
+        .find(|request| request.url.path() == "/pages")
+        .unwrap();
+    let body: Value = serde_json::from_slice(&created.body)?;
+    let properties = body["properties"].as_object().unwrap();
+    let mut names: Vec<&String> = properties.keys().collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["Name", "Stage"]);
+    assert_eq!(properties["Stage"]["select"], json!({ "name": "Done" }));
+
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use test_log::test;
+mod common;
+use common::mock_client;
+use rusticnotion::ids::BlockId;
+use rusticnotion::models::block::{CreateBlock, FileObject};
+use rusticnotion::models::file_uploads::{FileUpload, FileUploadStatus, MULTI_PART_CHUNK_SIZE};
+use serde_json::{json, Value};
+use std::str::FromStr;
+use wiremock::matchers::{body_json, body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const UPLOAD_ID: &str = "b52b8ed6-e029-4707-a671-832549c09de3";
+
+fn file_upload_json(status: &str) -> Value {
+    json!({
+        "object": "file_upload",
+        "id": UPLOAD_ID,
+        "created_time": "2025-03-15T20:53:00.000Z",
+        "last_edited_time": "2025-03-15T20:53:00.000Z",
+        "expiry_time": "2025-03-15T21:53:00.000Z",
+        "status": status,
+        "filename": "notes.txt",
+        "content_type": "text/plain",
+        "content_length": null
+    })
+}
+
+#[test(tokio::test)]
+async fn upload_single_part_file() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/file_uploads"))
+        .and(body_json(json!({
+            "mode": "single_part",
+            "filename": "notes.txt",
+            "content_type": "text/plain"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file_upload_json("pending")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/file_uploads/{}/send", UPLOAD_ID)))
+        .and(body_string_contains("Hello from rusticnotion"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file_upload_json("uploaded")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let file = std::env::temp_dir()
+        .join(format!("rusticnotion-upload-test-{}", std::process::id()))
+        .join("notes.txt");
+    std::fs::create_dir_all(file.parent().unwrap())?;
+    std::fs::write(&file, "Hello from rusticnotion")?;
+
+    let upload = mock_client(&server)
+        .upload_file(&file, Some("text/plain".to_string()))
+        .await;
+    std::fs::remove_dir_all(file.parent().unwrap())?;
+    assert_eq!(upload?.status, FileUploadStatus::Uploaded);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn upload_multi_part_reader() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/file_uploads"))
+        .and(body_json(json!({
+            "mode": "multi_part",
+            "filename": "export.bin",
+            "number_of_parts": 3
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file_upload_json("pending")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/file_uploads/{}/send", UPLOAD_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file_upload_json("pending")))
+        .expect(3)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/file_uploads/{}/complete", UPLOAD_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file_upload_json("uploaded")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let content_length = 2 * MULTI_PART_CHUNK_SIZE + 1024;
+    let content = std::io::Cursor::new(vec![7u8; content_length as usize]);
+
+    let upload = mock_client(&server)
+        .upload_from_reader(content, "export.bin".to_string(), None, content_length)
+        .await?;
+    assert_eq!(upload.status, FileUploadStatus::Uploaded);
+
+    let requests = server.received_requests().await.unwrap();
+    let part_sizes: Vec<usize> = requests
+        .iter()
+        .filter(|request| request.url.path().ends_with("/send"))
+        .map(|request| request.body.len())
+        .collect();
+    assert_eq!(part_sizes.len(), 3);
+    assert!(part_sizes[0] > MULTI_PART_CHUNK_SIZE as usize);
+    assert!(part_sizes[2] < MULTI_PART_CHUNK_SIZE as usize);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn upload_fails_on_short_reader_without_creating_upload(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/file_uploads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file_upload_json("pending")))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let result = mock_client(&server)
+        .upload_from_reader(
+            std::io::Cursor::new(b"short".to_vec()),
+            "notes.txt".to_string(),
+            None,
+            1024,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(rusticnotion::Error::FileReadError { .. })
+    ));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn upload_reports_pending_upload_on_short_later_part(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/file_uploads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file_upload_json("pending")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(format!("/file_uploads/{}/send", UPLOAD_ID)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file_upload_json("pending")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let content = std::io::Cursor::new(vec![7u8; MULTI_PART_CHUNK_SIZE as usize + 10]);
+    let result = mock_client(&server)
+        .upload_from_reader(
+            content,
+            "export.bin".to_string(),
+            None,
+            3 * MULTI_PART_CHUNK_SIZE,
+        )
+        .await;
+    match result {
+        Err(rusticnotion::Error::FileUploadIncomplete {
+            file_upload_id,
+            source,
+        }) => {
+            assert_eq!(file_upload_id.to_string(), UPLOAD_ID);
+            assert!(matches!(*source, rusticnotion::Error::FileReadError { .. }));
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn blocks_referencing_uploads_use_the_upload_api_version(
+) -> Result<(), Box<dyn std::error::Error>> {
+    const PARENT: &str = "b8b944b5-cc3d-444e-a25c-a7ddacd528cb";
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path(format!("/blocks/{}/children", PARENT)))
+        .and(header("Notion-Version", "2022-06-28"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "object": "list", "results": [], "next_cursor": null, "has_more": false }),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path(format!("/blocks/{}/children", PARENT)))
+        .and(header("Notion-Version", "2022-02-22"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({ "object": "list", "results": [], "next_cursor": null, "has_more": false }),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let upload: FileUpload = serde_json::from_value(file_upload_json("uploaded"))?;
+    let api = mock_client(&server);
+
+    api.append_block_children(
+        BlockId::from_str(PARENT)?,
+        vec![CreateBlock::Image {
+            image: FileObject::from(&upload),
+        }],
+    )
+    .await?;
+    api.append_block_children(BlockId::from_str(PARENT)?, vec![CreateBlock::Divider])
+        .await?;
+
+    Ok(())
+}
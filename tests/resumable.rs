@@ -0,0 +1,297 @@
+use test_log::test;
+mod common;
+use common::{list_json, mock_client, page_json};
+use rusticnotion::ids::DatabaseId;
+use rusticnotion::models::search::{DatabaseQuery, DatabaseSort, SortDirection};
+use rusticnotion::resumable::ResumablePager;
+use rusticnotion::Error;
+use serde_json::json;
+use std::path::PathBuf;
+use std::str::FromStr;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const DATABASE: &str = "a1d8501e-1ac1-43e9-a6bd-ea9fe6c8822b";
+const PARENT: &str = "b8b944b5-cc3d-444e-a25c-a7ddacd528cb";
+
+fn page_id(n: u8) -> String {
+    format!("00000000-0000-0000-0000-0000000000{:02}", n)
+}
+
+fn checkpoint_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("rusticnotion-{}-{}.json", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn query_path() -> String {
+    format!("/databases/{}/query", DATABASE)
+}
+
+async fn mount_first_page(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path(query_path()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![
+                page_json(&page_id(1), PARENT, "First"),
+                page_json(&page_id(2), PARENT, "Second"),
+            ],
+            Some("page-2"),
+        )))
+        .mount(server)
+        .await;
+}
+
+fn titles(pages: Option<Vec<rusticnotion::models::Page>>) -> Vec<Option<String>> {
+    pages
+        .unwrap_or_default()
+        .iter()
+        .map(|page| page.title())
+        .collect()
+}
+
+#[test(tokio::test)]
+async fn resumes_from_checkpoint_file() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_first_page(&server).await;
+    Mock::given(method("POST"))
+        .and(path(query_path()))
+        .and(body_partial_json(json!({ "start_cursor": "page-2" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![page_json(&page_id(3), PARENT, "Third")],
+            None,
+        )))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+    let checkpoint = checkpoint_path("resume");
+    let database = DatabaseId::from_str(DATABASE)?;
+
+    let mut pager = ResumablePager::database(
+        &api,
+        database.clone(),
+        DatabaseQuery::default(),
+        &checkpoint,
+    )
+    .await?;
+    assert_eq!(
+        titles(pager.next_page().await?),
+        vec![Some("First".to_string()), Some("Second".to_string())]
+    );
+    pager.commit().await?;
+    drop(pager);
+
+    let mut pager =
+        ResumablePager::database(&api, database, DatabaseQuery::default(), &checkpoint).await?;
+    assert_eq!(pager.checkpoint().results, 2);
+    assert_eq!(
+        titles(pager.next_page().await?),
+        vec![Some("Third".to_string())]
+    );
+    assert!(pager.checkpoint().finished);
+    assert!(pager.next_page().await?.is_none());
+
+    let requests = server.received_requests().await.unwrap();
+    let first: serde_json::Value = serde_json::from_slice(&requests[0].body)?;
+    assert_eq!(
+        first["sorts"],
+        json!([{ "timestamp": "last_edited_time", "direction": "ascending" }])
+    );
+
+    std::fs::remove_file(&checkpoint)?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn restarts_from_last_edited_time_when_cursor_expires(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_first_page(&server).await;
+    Mock::given(method("POST"))
+        .and(path(query_path()))
+        .and(body_partial_json(json!({ "start_cursor": "page-2" })))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "object": "error",
+            "status": 400,
+            "code": "validation_error",
+            "message": "The start_cursor provided is invalid."
+        })))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(query_path()))
+        .and(body_partial_json(json!({
+            "filter": {
+                "timestamp": "last_edited_time",
+                "last_edited_time": { "on_or_after": "2022-05-13T20:08:00Z" }
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![
+                page_json(&page_id(1), PARENT, "First"),
+                page_json(&page_id(2), PARENT, "Second"),
+                page_json(&page_id(3), PARENT, "Third"),
+            ],
+            None,
+        )))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+    let checkpoint = checkpoint_path("restart");
+    let database = DatabaseId::from_str(DATABASE)?;
+
+    let mut pager =
+        ResumablePager::database(&api, database, DatabaseQuery::default(), &checkpoint).await?;
+    pager.next_page().await?;
+    assert_eq!(
+        titles(pager.next_page().await?),
+        vec![Some("Third".to_string())]
+    );
+    assert!(pager.checkpoint().restarted_from.is_some());
+    assert_eq!(pager.checkpoint().results, 3);
+
+    std::fs::remove_file(&checkpoint)?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn rejects_checkpoint_of_other_query() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_first_page(&server).await;
+    let api = mock_client(&server);
+    let checkpoint = checkpoint_path("mismatch");
+
+    let mut pager = ResumablePager::database(
+        &api,
+        DatabaseId::from_str(DATABASE)?,
+        DatabaseQuery::default(),
+        &checkpoint,
+    )
+    .await?;
+    pager.next_page().await?;
+    pager.commit().await?;
+
+    let other = ResumablePager::database(
+        &api,
+        DatabaseId::from_str(PARENT)?,
+        DatabaseQuery::default(),
+        &checkpoint,
+    )
+    .await;
+    assert!(matches!(other, Err(Error::CheckpointMismatch { .. })));
+
+    std::fs::remove_file(&checkpoint)?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn returns_uncommitted_page_again() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_first_page(&server).await;
+    let api = mock_client(&server);
+    let checkpoint = checkpoint_path("uncommitted");
+    let database = DatabaseId::from_str(DATABASE)?;
+
+    let mut pager = ResumablePager::database(
+        &api,
+        database.clone(),
+        DatabaseQuery::default(),
+        &checkpoint,
+    )
+    .await?;
+    assert_eq!(titles(pager.next_page().await?).len(), 2);
+    // Stops before the page was processed.
+    drop(pager);
+
+    let mut pager =
+        ResumablePager::database(&api, database, DatabaseQuery::default(), &checkpoint).await?;
+    assert_eq!(pager.checkpoint().results, 0);
+    assert_eq!(
+        titles(pager.next_page().await?),
+        vec![Some("First".to_string()), Some("Second".to_string())]
+    );
+
+    let _ = std::fs::remove_file(&checkpoint);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn fails_on_expired_cursor_with_custom_sort() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_first_page(&server).await;
+    Mock::given(method("POST"))
+        .and(path(query_path()))
+        .and(body_partial_json(json!({ "start_cursor": "page-2" })))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "object": "error",
+            "status": 400,
+            "code": "validation_error",
+            "message": "The start_cursor provided is invalid."
+        })))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+    let checkpoint = checkpoint_path("custom-sort");
+    let query = DatabaseQuery {
+        sorts: Some(vec![DatabaseSort {
+            property: Some("Name".to_string()),
+            timestamp: None,
+            direction: SortDirection::Ascending,
+        }]),
+        ..Default::default()
+    };
+
+    let mut pager =
+        ResumablePager::database(&api, DatabaseId::from_str(DATABASE)?, query, &checkpoint).await?;
+    pager.next_page().await?;
+    assert!(matches!(
+        pager.next_page().await,
+        Err(Error::CursorExpired { .. })
+    ));
+
+    std::fs::remove_file(&checkpoint)?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn passes_other_validation_errors_through() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_first_page(&server).await;
+    Mock::given(method("POST"))
+        .and(path(query_path()))
+        .and(body_partial_json(json!({ "start_cursor": "page-2" })))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "object": "error",
+            "status": 400,
+            "code": "validation_error",
+            "message": "Could not find property with name or id: Stage"
+        })))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+    let checkpoint = checkpoint_path("other-validation-error");
+
+    let mut pager = ResumablePager::database(
+        &api,
+        DatabaseId::from_str(DATABASE)?,
+        DatabaseQuery::default(),
+        &checkpoint,
+    )
+    .await?;
+    pager.next_page().await?;
+    assert!(matches!(
+        pager.next_page().await,
+        Err(Error::ApiError { .. })
+    ));
+
+    std::fs::remove_file(&checkpoint)?;
+    Ok(())
+}
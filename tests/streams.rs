@@ -0,0 +1,157 @@
+use test_log::test;
+mod common;
+use common::{list_json, mock_client, page_json, paragraph_json};
+use futures::{StreamExt, TryStreamExt};
+use rusticnotion::ids::{BlockId, DatabaseId};
+use rusticnotion::models::search::{DatabaseQuery, NotionSearch};
+use rusticnotion::Error;
+use serde_json::json;
+use std::str::FromStr;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const DATABASE: &str = "a1d8501e-1ac1-43e9-a6bd-ea9fe6c8822b";
+const PARENT: &str = "b8b944b5-cc3d-444e-a25c-a7ddacd528cb";
+
+fn page_id(n: u8) -> String {
+    format!("00000000-0000-0000-0000-0000000000{:02}", n)
+}
+
+async fn mount_database_pages(server: &MockServer) {
+    let query_path = format!("/databases/{}/query", DATABASE);
+    Mock::given(method("POST"))
+        .and(path(query_path.clone()))
+        .and(body_partial_json(json!({ "start_cursor": "page-2" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![page_json(&page_id(3), PARENT, "Third")],
+            None,
+        )))
+        .with_priority(1)
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(query_path))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![
+                page_json(&page_id(1), PARENT, "First"),
+                page_json(&page_id(2), PARENT, "Second"),
+            ],
+            Some("page-2"),
+        )))
+        .mount(server)
+        .await;
+}
+
+#[test(tokio::test)]
+async fn query_database_stream_follows_cursors() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_database_pages(&server).await;
+    let api = mock_client(&server);
+
+    let titles: Vec<Option<String>> = api
+        .query_database_stream(
+            DatabaseId::from_str(DATABASE)?,
+            DatabaseQuery::default(),
+            None,
+        )
+        .map_ok(|page| page.title())
+        .try_collect()
+        .await?;
+
+    assert_eq!(
+        titles,
+        vec![
+            Some("First".to_string()),
+            Some("Second".to_string()),
+            Some("Third".to_string())
+        ]
+    );
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn query_database_stream_stops_at_max_items() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    mount_database_pages(&server).await;
+    let api = mock_client(&server);
+
+    let pages: Vec<_> = api
+        .query_database_stream(
+            DatabaseId::from_str(DATABASE)?,
+            DatabaseQuery::default(),
+            Some(2),
+        )
+        .try_collect()
+        .await?;
+
+    assert_eq!(pages.len(), 2);
+    // The second page of results is never requested.
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn search_stream_stops_on_first_error() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/search"))
+        .and(body_partial_json(json!({ "start_cursor": "page-2" })))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "object": "error",
+            "status": 400,
+            "code": "validation_error",
+            "message": "start_cursor is invalid"
+        })))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![page_json(&page_id(1), PARENT, "First")],
+            Some("page-2"),
+        )))
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+
+    let results: Vec<_> = api
+        .search_stream(NotionSearch::filter_by_pages(), None)
+        .collect()
+        .await;
+
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::ApiError { .. })));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn block_children_stream() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/blocks/{}/children", PARENT)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![
+                paragraph_json(&page_id(1), "first", false),
+                paragraph_json(&page_id(2), "second", false),
+            ],
+            None,
+        )))
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+
+    let blocks: Vec<_> = api
+        .block_children_stream(BlockId::from_str(PARENT)?, None)
+        .try_collect()
+        .await?;
+
+    assert_eq!(blocks.len(), 2);
+
+    Ok(())
+}
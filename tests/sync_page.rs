@@ -0,0 +1,120 @@
+use test_log::test;
+mod common;
+use common::{list_json, mock_client, paragraph_json};
+use rusticnotion::{
+    ids::{BlockId, PageId},
+    models::block::{CreateBlock, Text, TextAndChildren},
+    models::text::{RichText, TextColor},
+    sync::BlockOperation,
+};
+use serde_json::Value;
+use std::str::FromStr;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const PAGE: &str = "b8b944b5-cc3d-444e-a25c-a7ddacd528cb";
+
+fn block_id(n: u16) -> String {
+    format!("00000000-0000-0000-0000-000000000{:03}", n)
+}
+
+fn paragraph(content: &str) -> CreateBlock {
+    CreateBlock::Paragraph {
+        paragraph: TextAndChildren {
+            rich_text: vec![RichText::text(content)],
+            children: None,
+            color: TextColor::Default,
+        },
+    }
+}
+
+#[test(tokio::test)]
+async fn sync_page_content_sends_only_changes() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!("/blocks/{}/children", PAGE)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![
+                paragraph_json(&block_id(1), "kept", false),
+                paragraph_json(&block_id(2), "old", false),
+                paragraph_json(&block_id(3), "gone", false),
+            ],
+            None,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path(format!("/blocks/{}", block_id(2))))
+        .respond_with(ResponseTemplate::new(200).set_body_json(paragraph_json(
+            &block_id(2),
+            "new",
+            false,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path(format!("/blocks/{}", block_id(3))))
+        .respond_with(ResponseTemplate::new(200).set_body_json(paragraph_json(
+            &block_id(3),
+            "gone",
+            false,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path(format!("/blocks/{}/children", PAGE)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list_json(
+            vec![paragraph_json(&block_id(4), "added", false)],
+            None,
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+    let heading = CreateBlock::Heading1 {
+        heading_1: Text {
+            rich_text: vec![RichText::text("added")],
+        },
+    };
+
+    let operations = api
+        .sync_page_content(
+            PageId::from_str(PAGE)?,
+            vec![paragraph("kept"), paragraph("new"), heading.clone()],
+        )
+        .await?;
+
+    assert_eq!(
+        operations,
+        vec![
+            BlockOperation::Update {
+                block_id: BlockId::from_str(&block_id(2))?,
+                block: paragraph("new"),
+            },
+            BlockOperation::Delete {
+                block_id: BlockId::from_str(&block_id(3))?,
+            },
+            BlockOperation::Append {
+                parent: BlockId::from_str(PAGE)?,
+                after: Some(BlockId::from_str(&block_id(2))?),
+                blocks: vec![heading],
+            },
+        ]
+    );
+
+    let requests = server.received_requests().await.unwrap();
+    let update: Value = serde_json::from_slice(&requests[1].body)?;
+    assert_eq!(
+        update["paragraph"]["rich_text"][0]["text"]["content"],
+        "new"
+    );
+    assert!(update["paragraph"].get("children").is_none());
+    let append: Value = serde_json::from_slice(&requests[3].body)?;
+    assert_eq!(append["after"], block_id(2));
+    assert_eq!(append["children"][0]["type"], "heading_1");
+
+    Ok(())
+}
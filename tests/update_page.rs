@@ -0,0 +1,42 @@
+use test_log::test;
+mod common;
+use common::{mock_client, page_json};
+use rusticnotion::ids::PageId;
+use rusticnotion::models::properties::{WriteProperties, WritePropertyValue};
+use serde_json::json;
+use std::str::FromStr;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const PAGE: &str = "00000000-0000-0000-0000-000000000001";
+const PARENT: &str = "b8b944b5-cc3d-444e-a25c-a7ddacd528cb";
+
+#[test(tokio::test)]
+async fn update_page_sends_only_changed_properties() -> Result<(), Box<dyn std::error::Error>> {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path(format!("/pages/{}", PAGE)))
+        .and(body_json(json!({
+            "properties": {
+                "Done": { "checkbox": true },
+                "State": { "status": { "name": "Finished" } }
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_json(PAGE, PARENT, "Task")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let api = mock_client(&server);
+
+    let page = api
+        .update_page(
+            PageId::from_str(PAGE)?,
+            WriteProperties::new()
+                .with("Done", WritePropertyValue::checkbox(true))
+                .with("State", WritePropertyValue::status_by_name("Finished")),
+        )
+        .await?;
+    assert_eq!(page.title(), Some("Task".to_string()));
+
+    Ok(())
+}
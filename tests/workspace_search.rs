@@ -4,6 +4,7 @@ use common::test_client;
 use rusticnotion::models::search::{FilterProperty, FilterValue, NotionSearch};
 
 #[test(tokio::test)]
+#[allow(deprecated)]
 async fn list_databases() -> Result<(), Box<dyn std::error::Error>> {
     let api = test_client();
 